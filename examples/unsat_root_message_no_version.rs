@@ -74,6 +74,9 @@ impl ReportFormatter<Package, Range<SemanticVersion>, String> for CustomReportFo
                     format!("there is no version of {package} in {set}")
                 }
             }
+            External::PackageNotFound(package) => {
+                format!("package {package} does not exist")
+            }
             External::Custom(package, set, reason) => {
                 if set == &Range::full() {
                     format!("dependencies of {package} are unavailable because {reason}")
@@ -0,0 +1,130 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! A [DependencyProvider] wrapper remembering the answers of another one.
+
+use std::cell::RefCell;
+use std::collections::{BTreeMap, BTreeSet};
+use std::hash::Hash;
+
+use crate::solver::forward_to_dependency_provider;
+use crate::{Dependencies, DependencyProvider, Map, ProgressInfo, VersionPreference};
+
+/// Memoizes the versions and dependencies returned by another [DependencyProvider].
+///
+/// When retrieving them is slow, for example over the network,
+/// each question is only asked once to the inner provider,
+/// even across several resolutions using the same cache.
+/// Errors are not cached, so a failed query is retried the next time.
+/// Versions chosen while avoiding [already tried](DependencyProvider::choose_version_with_tried)
+/// ones are not cached either, since they depend on the versions tried.
+/// Prioritization and cancellation are always delegated to the inner provider.
+#[derive(Clone)]
+pub struct CachingDependencyProvider<DP: DependencyProvider> {
+    inner: DP,
+    #[allow(clippy::type_complexity)]
+    versions: RefCell<Map<DP::P, Map<DP::VS, Option<DP::V>>>>,
+    #[allow(clippy::type_complexity)]
+    dependencies: RefCell<Map<DP::P, BTreeMap<DP::V, Dependencies<DP::P, DP::VS, DP::M>>>>,
+}
+
+impl<DP: DependencyProvider> CachingDependencyProvider<DP> {
+    /// Wrap a dependency provider with an empty cache.
+    pub fn new(inner: DP) -> Self {
+        Self {
+            inner,
+            versions: RefCell::new(Map::default()),
+            dependencies: RefCell::new(Map::default()),
+        }
+    }
+
+    /// The wrapped dependency provider.
+    pub fn inner(&self) -> &DP {
+        &self.inner
+    }
+
+    /// Forget everything that was cached.
+    pub fn clear(&mut self) {
+        self.versions.get_mut().clear();
+        self.dependencies.get_mut().clear();
+    }
+
+    /// Return the wrapped dependency provider, dropping the cache.
+    pub fn into_inner(self) -> DP {
+        self.inner
+    }
+}
+
+impl<DP: DependencyProvider> DependencyProvider for CachingDependencyProvider<DP>
+where
+    DP::VS: Hash,
+{
+    type P = DP::P;
+    type V = DP::V;
+    type VS = DP::VS;
+    type M = DP::M;
+
+    type Priority = DP::Priority;
+
+    type Err = DP::Err;
+
+    fn choose_version(&self, package: &DP::P, range: &DP::VS) -> Result<Option<DP::V>, DP::Err> {
+        self.choose_version_with_tried(package, range, &BTreeSet::new())
+    }
+
+    fn choose_version_with_tried(
+        &self,
+        package: &DP::P,
+        range: &DP::VS,
+        already_tried: &BTreeSet<DP::V>,
+    ) -> Result<Option<DP::V>, DP::Err> {
+        if !already_tried.is_empty() {
+            return self
+                .inner
+                .choose_version_with_tried(package, range, already_tried);
+        }
+        if let Some(version) = self
+            .versions
+            .borrow()
+            .get(package)
+            .and_then(|ranges| ranges.get(range))
+        {
+            return Ok(version.clone());
+        }
+        let version = self
+            .inner
+            .choose_version_with_tried(package, range, already_tried)?;
+        self.versions
+            .borrow_mut()
+            .entry(package.clone())
+            .or_default()
+            .insert(range.clone(), version.clone());
+        Ok(version)
+    }
+
+    fn get_dependencies(
+        &self,
+        package: &DP::P,
+        version: &DP::V,
+    ) -> Result<Dependencies<DP::P, DP::VS, DP::M>, DP::Err> {
+        if let Some(dependencies) = self
+            .dependencies
+            .borrow()
+            .get(package)
+            .and_then(|versions| versions.get(version))
+        {
+            return Ok(dependencies.clone());
+        }
+        let dependencies = self.inner.get_dependencies(package, version)?;
+        self.dependencies
+            .borrow_mut()
+            .entry(package.clone())
+            .or_default()
+            .insert(version.clone(), dependencies.clone());
+        Ok(dependencies)
+    }
+
+    forward_to_dependency_provider!(
+        inner: prioritize, package_exists, is_deprioritized, version_preference, prefetch,
+        should_cancel, on_progress
+    );
+}
@@ -0,0 +1,231 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! A [VersionSet] of enumerated versions, for packages with a known, non-contiguous list of
+//! versions.
+//!
+//! Unlike [Range](crate::Range), a [DiscreteVersionSet] only ever contains the versions it lists,
+//! or all the versions except the ones it lists.
+//! The complement of a set listing some versions is the set excluding them,
+//! so there is no need to know all the versions that exist.
+
+use std::collections::BTreeSet;
+use std::fmt::{self, Debug, Display};
+
+use crate::VersionSet;
+
+/// A set of versions listed one by one, or of all the versions except the listed ones.
+///
+/// Both variants list a finite number of versions,
+/// so a set has a single representation as long as there are infinitely many possible versions.
+/// With a finite version type, such as [bool],
+/// a set including all its values is not equal to the [full](VersionSet::full) set.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum DiscreteVersionSet<V: Ord> {
+    /// Only the listed versions.
+    Include(BTreeSet<V>),
+    /// All the versions except the listed ones.
+    Exclude(BTreeSet<V>),
+}
+
+impl<V: Ord> FromIterator<V> for DiscreteVersionSet<V> {
+    fn from_iter<T: IntoIterator<Item = V>>(iter: T) -> Self {
+        Self::Include(iter.into_iter().collect())
+    }
+}
+
+impl<V: Debug + Display + Clone + Ord> VersionSet for DiscreteVersionSet<V> {
+    type V = V;
+
+    fn empty() -> Self {
+        Self::Include(BTreeSet::new())
+    }
+
+    fn singleton(v: V) -> Self {
+        Self::Include(BTreeSet::from([v]))
+    }
+
+    fn complement(&self) -> Self {
+        match self {
+            Self::Include(versions) => Self::Exclude(versions.clone()),
+            Self::Exclude(versions) => Self::Include(versions.clone()),
+        }
+    }
+
+    fn intersection(&self, other: &Self) -> Self {
+        match (self, other) {
+            (Self::Include(a), Self::Include(b)) => {
+                Self::Include(a.intersection(b).cloned().collect())
+            }
+            (Self::Include(a), Self::Exclude(b)) | (Self::Exclude(b), Self::Include(a)) => {
+                Self::Include(a.difference(b).cloned().collect())
+            }
+            (Self::Exclude(a), Self::Exclude(b)) => Self::Exclude(a.union(b).cloned().collect()),
+        }
+    }
+
+    fn contains(&self, v: &V) -> bool {
+        match self {
+            Self::Include(versions) => versions.contains(v),
+            Self::Exclude(versions) => !versions.contains(v),
+        }
+    }
+
+    fn full() -> Self {
+        Self::Exclude(BTreeSet::new())
+    }
+
+    fn union(&self, other: &Self) -> Self {
+        match (self, other) {
+            (Self::Include(a), Self::Include(b)) => Self::Include(a.union(b).cloned().collect()),
+            (Self::Include(a), Self::Exclude(b)) | (Self::Exclude(b), Self::Include(a)) => {
+                Self::Exclude(b.difference(a).cloned().collect())
+            }
+            (Self::Exclude(a), Self::Exclude(b)) => {
+                Self::Exclude(a.intersection(b).cloned().collect())
+            }
+        }
+    }
+
+    fn as_singleton(&self) -> Option<&V> {
+        match self {
+            Self::Include(versions) if versions.len() == 1 => versions.first(),
+            _ => None,
+        }
+    }
+
+    /// The number of versions listed, and more than that for the excluding sets.
+    fn approximate_len(&self) -> usize {
+        match self {
+            Self::Include(versions) => versions.len(),
+            Self::Exclude(versions) => usize::MAX - versions.len(),
+        }
+    }
+
+    fn is_disjoint(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::Include(a), Self::Include(b)) => a.is_disjoint(b),
+            (Self::Include(a), Self::Exclude(b)) | (Self::Exclude(b), Self::Include(a)) => {
+                a.is_subset(b)
+            }
+            (Self::Exclude(_), Self::Exclude(_)) => false,
+        }
+    }
+
+    fn subset_of(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::Include(a), Self::Include(b)) => a.is_subset(b),
+            (Self::Include(a), Self::Exclude(b)) => a.is_disjoint(b),
+            (Self::Exclude(_), Self::Include(_)) => false,
+            (Self::Exclude(a), Self::Exclude(b)) => b.is_subset(a),
+        }
+    }
+}
+
+impl<V: Ord + Display> Display for DiscreteVersionSet<V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (versions, prefix, separator) = match self {
+            Self::Include(versions) if versions.is_empty() => return write!(f, "∅"),
+            Self::Exclude(versions) if versions.is_empty() => return write!(f, "*"),
+            Self::Include(versions) => (versions, "", " | "),
+            Self::Exclude(versions) => (versions, "!= ", ", "),
+        };
+        for (idx, v) in versions.iter().enumerate() {
+            if idx > 0 {
+                write!(f, "{separator}")?;
+            }
+            write!(f, "{prefix}{v}")?;
+        }
+        Ok(())
+    }
+}
+
+// TESTS #######################################################################
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn set(versions: &[u32]) -> DiscreteVersionSet<u32> {
+        versions.iter().copied().collect()
+    }
+
+    #[test]
+    fn contains() {
+        let s = set(&[1, 7]);
+        assert!(s.contains(&1));
+        assert!(!s.contains(&4));
+        assert!(s.contains(&7));
+        assert!(!s.complement().contains(&1));
+        assert!(s.complement().contains(&1000));
+        assert!(!DiscreteVersionSet::empty().contains(&1));
+        assert!(DiscreteVersionSet::full().contains(&9));
+        assert_eq!(DiscreteVersionSet::singleton(4).as_singleton(), Some(&4));
+        assert_eq!(s.complement().as_singleton(), None);
+        assert_eq!(s.approximate_len(), 2);
+        assert!(s.approximate_len() < s.complement().approximate_len());
+    }
+
+    #[test]
+    fn intersection() {
+        assert_eq!(set(&[1, 4, 7]).intersection(&set(&[4, 7, 9])), set(&[4, 7]));
+        assert_eq!(
+            set(&[1]).intersection(&set(&[9])),
+            DiscreteVersionSet::empty()
+        );
+        assert_eq!(
+            set(&[1, 4]).intersection(&DiscreteVersionSet::full()),
+            set(&[1, 4])
+        );
+        assert_eq!(
+            set(&[1, 4, 7]).intersection(&set(&[4]).complement()),
+            set(&[1, 7])
+        );
+        assert_eq!(
+            set(&[1]).complement().intersection(&set(&[4]).complement()),
+            set(&[1, 4]).complement()
+        );
+        assert!(set(&[1]).is_disjoint(&set(&[9])));
+        assert!(set(&[1]).is_disjoint(&set(&[1, 4]).complement()));
+        assert!(!set(&[1]).complement().is_disjoint(&set(&[4]).complement()));
+        assert!(set(&[4]).subset_of(&set(&[1, 4])));
+        assert!(set(&[4]).subset_of(&set(&[1]).complement()));
+        assert!(set(&[1, 4]).complement().subset_of(&set(&[1]).complement()));
+        assert!(!set(&[1]).complement().subset_of(&set(&[1, 4])));
+    }
+
+    #[test]
+    fn union() {
+        assert_eq!(set(&[1, 4]).union(&set(&[4, 9])), set(&[1, 4, 9]));
+        assert_eq!(
+            set(&[1, 4]).union(&set(&[1, 4]).complement()),
+            DiscreteVersionSet::full()
+        );
+        assert_eq!(
+            set(&[1]).union(&set(&[1, 4]).complement()),
+            set(&[4]).complement()
+        );
+        assert_eq!(set(&[1]).union(&DiscreteVersionSet::empty()), set(&[1]));
+    }
+
+    #[test]
+    fn complement() {
+        assert_eq!(
+            set(&[1, 7]).complement(),
+            DiscreteVersionSet::Exclude(BTreeSet::from([1, 7]))
+        );
+        assert_eq!(
+            DiscreteVersionSet::<u32>::empty().complement(),
+            DiscreteVersionSet::full()
+        );
+        assert_eq!(set(&[1, 7]).complement().complement(), set(&[1, 7]));
+    }
+
+    #[test]
+    fn display() {
+        assert_eq!(set(&[1, 4, 7]).to_string(), "1 | 4 | 7");
+        assert_eq!(set(&[1, 4]).complement().to_string(), "!= 1, != 4");
+        assert_eq!(DiscreteVersionSet::<u32>::empty().to_string(), "∅");
+        assert_eq!(DiscreteVersionSet::<u32>::full().to_string(), "*");
+    }
+}
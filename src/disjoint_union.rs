@@ -0,0 +1,236 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! A [VersionSet] over versions of two different kinds,
+//! such as `Stable(u32) | Nightly(Date)`.
+//!
+//! Versions of each kind are handled by their own [VersionSet],
+//! and a [DisjointUnionVersionSet] holds one set of each kind.
+//! All the operations are done kind by kind, in particular the complement of a set
+//! contains the complement of each of its parts: `not (stable >=2)` is `stable <2`
+//! together with all the nightly versions.
+
+use std::fmt::{self, Display};
+
+use crate::VersionSet;
+
+/// A version of one of two kinds.
+///
+/// All [Left](Either::Left) versions are ordered before all [Right](Either::Right) versions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Either<A, B> {
+    /// A version of the first kind.
+    Left(A),
+    /// A version of the second kind.
+    Right(B),
+}
+
+impl<A: Display, B: Display> Display for Either<A, B> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Left(a) => write!(f, "{a}"),
+            Self::Right(b) => write!(f, "{b}"),
+        }
+    }
+}
+
+/// A set of [Either] versions, made of a set of versions of each kind.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DisjointUnionVersionSet<A, B> {
+    left: A,
+    right: B,
+}
+
+impl<A: VersionSet, B: VersionSet> DisjointUnionVersionSet<A, B> {
+    /// The set with the versions of `left` and the versions of `right`.
+    pub fn new(left: A, right: B) -> Self {
+        Self { left, right }
+    }
+
+    /// The set with only versions of the first kind, those of `left`.
+    pub fn from_left(left: A) -> Self {
+        Self::new(left, B::empty())
+    }
+
+    /// The set with only versions of the second kind, those of `right`.
+    pub fn from_right(right: B) -> Self {
+        Self::new(A::empty(), right)
+    }
+
+    /// The versions of the first kind in the set.
+    pub fn left(&self) -> &A {
+        &self.left
+    }
+
+    /// The versions of the second kind in the set.
+    pub fn right(&self) -> &B {
+        &self.right
+    }
+}
+
+impl<A: VersionSet, B: VersionSet> VersionSet for DisjointUnionVersionSet<A, B> {
+    type V = Either<A::V, B::V>;
+
+    fn empty() -> Self {
+        Self::new(A::empty(), B::empty())
+    }
+
+    fn singleton(v: Self::V) -> Self {
+        match v {
+            Either::Left(a) => Self::from_left(A::singleton(a)),
+            Either::Right(b) => Self::from_right(B::singleton(b)),
+        }
+    }
+
+    fn complement(&self) -> Self {
+        Self::new(self.left.complement(), self.right.complement())
+    }
+
+    fn intersection(&self, other: &Self) -> Self {
+        Self::new(
+            self.left.intersection(&other.left),
+            self.right.intersection(&other.right),
+        )
+    }
+
+    fn contains(&self, v: &Self::V) -> bool {
+        match v {
+            Either::Left(a) => self.left.contains(a),
+            Either::Right(b) => self.right.contains(b),
+        }
+    }
+
+    fn full() -> Self {
+        Self::new(A::full(), B::full())
+    }
+
+    fn union(&self, other: &Self) -> Self {
+        Self::new(self.left.union(&other.left), self.right.union(&other.right))
+    }
+
+    fn approximate_len(&self) -> usize {
+        self.left.approximate_len() + self.right.approximate_len()
+    }
+
+    fn is_disjoint(&self, other: &Self) -> bool {
+        self.left.is_disjoint(&other.left) && self.right.is_disjoint(&other.right)
+    }
+
+    fn subset_of(&self, other: &Self) -> bool {
+        self.left.subset_of(&other.left) && self.right.subset_of(&other.right)
+    }
+}
+
+impl<A: VersionSet, B: VersionSet> Display for DisjointUnionVersionSet<A, B> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (self.left == A::empty(), self.right == B::empty()) {
+            (true, true) => write!(f, "∅"),
+            (false, true) => write!(f, "{}", self.left),
+            (true, false) => write!(f, "{}", self.right),
+            (false, false) => write!(f, "{} | {}", self.left, self.right),
+        }
+    }
+}
+
+// TESTS #######################################################################
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Range;
+
+    /// A nightly release date.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+    struct Date(u16, u8, u8);
+
+    impl Display for Date {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            write!(f, "{}-{:02}-{:02}", self.0, self.1, self.2)
+        }
+    }
+
+    type Channel = Either<u32, Date>;
+    type ChannelVS = DisjointUnionVersionSet<Range<u32>, Range<Date>>;
+
+    fn stable(v: u32) -> Channel {
+        Either::Left(v)
+    }
+
+    fn nightly(y: u16, m: u8, d: u8) -> Channel {
+        Either::Right(Date(y, m, d))
+    }
+
+    #[test]
+    fn contains() {
+        let set = ChannelVS::new(
+            Range::higher_than(2u32),
+            Range::strictly_lower_than(Date(2024, 6, 1)),
+        );
+        assert!(set.contains(&stable(3)));
+        assert!(!set.contains(&stable(1)));
+        assert!(set.contains(&nightly(2024, 1, 1)));
+        assert!(!set.contains(&nightly(2024, 6, 1)));
+        assert!(ChannelVS::singleton(stable(1)).contains(&stable(1)));
+        assert!(!ChannelVS::singleton(stable(1)).contains(&nightly(2024, 1, 1)));
+        assert!(ChannelVS::full().contains(&nightly(1970, 1, 1)));
+        assert!(!ChannelVS::empty().contains(&stable(0)));
+    }
+
+    #[test]
+    fn complement() {
+        let set = ChannelVS::from_left(Range::higher_than(2u32));
+        let complement = set.complement();
+        assert_eq!(
+            complement,
+            ChannelVS::new(Range::strictly_lower_than(2u32), Range::full())
+        );
+        for v in [stable(1), stable(2), nightly(2024, 1, 1)] {
+            assert_ne!(set.contains(&v), complement.contains(&v));
+        }
+        assert_eq!(complement.complement(), set);
+        assert_eq!(ChannelVS::empty().complement(), ChannelVS::full());
+    }
+
+    #[test]
+    fn intersection_and_union() {
+        let s1 = ChannelVS::new(
+            Range::between(1u32, 5u32),
+            Range::higher_than(Date(2024, 1, 1)),
+        );
+        let s2 = ChannelVS::new(Range::higher_than(3u32), Range::empty());
+        assert_eq!(
+            s1.intersection(&s2),
+            ChannelVS::from_left(Range::between(3u32, 5u32))
+        );
+        assert_eq!(
+            s1.union(&s2),
+            ChannelVS::new(
+                Range::higher_than(1u32),
+                Range::higher_than(Date(2024, 1, 1))
+            )
+        );
+        // The default union, through complements, agrees with the componentwise one.
+        assert_eq!(
+            s1.union(&s2),
+            s1.complement().intersection(&s2.complement()).complement()
+        );
+        assert!(s2.subset_of(&s1.union(&s2)));
+        assert!(
+            ChannelVS::from_left(Range::full()).is_disjoint(&ChannelVS::from_right(Range::full()))
+        );
+    }
+
+    #[test]
+    fn display() {
+        assert_eq!(ChannelVS::empty().to_string(), "∅");
+        assert_eq!(
+            ChannelVS::from_left(Range::higher_than(2u32)).to_string(),
+            ">=2"
+        );
+        assert_eq!(
+            ChannelVS::new(Range::singleton(1u32), Range::singleton(Date(2024, 6, 1))).to_string(),
+            "1 | 2024-06-01"
+        );
+    }
+}
@@ -4,7 +4,7 @@
 
 use thiserror::Error;
 
-use crate::{DependencyProvider, DerivationTree};
+use crate::{DependencyProvider, DerivationTree, SelectedDependencies, Set};
 
 /// There is no solution for this set of dependencies.
 pub type NoSolutionError<DP> = DerivationTree<
@@ -20,6 +20,12 @@ pub enum PubGrubError<DP: DependencyProvider> {
     #[error("No solution")]
     NoSolution(NoSolutionError<DP>),
 
+    /// There is no solution for this set of dependencies,
+    /// because of several independent conflicts,
+    /// as found by [resolve_all_conflicts](crate::resolve_all_conflicts).
+    #[error("No solution, with {} independent conflicts", .0.len())]
+    IndependentConflicts(Vec<NoSolutionError<DP>>),
+
     /// Error arising when the implementer of [DependencyProvider] returned an error in the method
     /// [get_dependencies](DependencyProvider::get_dependencies).
     #[error("Retrieving dependencies of {package} {version} failed")]
@@ -41,7 +47,42 @@ pub enum PubGrubError<DP: DependencyProvider> {
     /// Error arising when the implementer of [DependencyProvider]
     /// returned an error in the method [should_cancel](DependencyProvider::should_cancel).
     #[error("We should cancel")]
-    ErrorInShouldCancel(#[source] DP::Err),
+    ErrorInShouldCancel {
+        /// Error raised by the implementer of [DependencyProvider].
+        source: DP::Err,
+        /// Packages decided so far, with their chosen version,
+        /// to show partial progress for example on a timeout.
+        /// They may not be part of a solution, if there is one.
+        decided: SelectedDependencies<DP>,
+    },
+
+    /// The resolution went over one of the [ResolveLimits](crate::ResolveLimits)
+    /// given to [resolve_with_limits](crate::resolve_with_limits).
+    #[error(
+        "Resolution limits exceeded with {incompatibilities} incompatibilities \
+        at decision level {decision_level}"
+    )]
+    LimitExceeded {
+        /// Number of incompatibilities when the resolution was stopped.
+        incompatibilities: usize,
+        /// Decision level when the resolution was stopped.
+        decision_level: u32,
+    },
+
+    /// Error arising when the implementer of [DependencyProvider] returned a version
+    /// outside of the requested range in the method
+    /// [choose_version](DependencyProvider::choose_version).
+    ///
+    /// This is a bug of the dependency provider.
+    #[error("Version {version} chosen for {package} is not in the requested range {range}")]
+    InvalidVersionChoice {
+        /// Package whose version was chosen.
+        package: DP::P,
+        /// Version returned by the dependency provider.
+        version: DP::V,
+        /// Range of versions that was requested.
+        range: DP::VS,
+    },
 
     /// Something unexpected happened.
     #[error("{0}")]
@@ -54,6 +95,45 @@ impl<DP: DependencyProvider> From<NoSolutionError<DP>> for PubGrubError<DP> {
     }
 }
 
+impl<DP: DependencyProvider> PubGrubError<DP> {
+    /// Packages involved in the failure if there is no solution,
+    /// see [DerivationTree::packages].
+    /// Returns [None] for the other errors.
+    pub fn involved_packages(&self) -> Option<Set<&DP::P>> {
+        match self {
+            Self::NoSolution(derivation_tree) => Some(derivation_tree.packages()),
+            Self::IndependentConflicts(derivation_trees) => Some(
+                derivation_trees
+                    .iter()
+                    .flat_map(|derivation_tree| derivation_tree.packages())
+                    .collect(),
+            ),
+            _ => None,
+        }
+    }
+
+    /// Whether this is a genuine conflict between the dependencies,
+    /// which resolving again with the same inputs would run into again.
+    pub fn is_no_solution(&self) -> bool {
+        matches!(self, Self::NoSolution(_) | Self::IndependentConflicts(_))
+    }
+
+    /// Whether this error was returned by a method of the [DependencyProvider],
+    /// and may be transient, for example a network failure.
+    pub fn is_provider_error(&self) -> bool {
+        match self {
+            Self::ErrorRetrievingDependencies { .. }
+            | Self::ErrorChoosingPackageVersion(_)
+            | Self::ErrorInShouldCancel { .. } => true,
+            Self::NoSolution(_)
+            | Self::IndependentConflicts(_)
+            | Self::LimitExceeded { .. }
+            | Self::InvalidVersionChoice { .. }
+            | Self::Failure(_) => false,
+        }
+    }
+}
+
 impl<DP> std::fmt::Debug for PubGrubError<DP>
 where
     DP: DependencyProvider,
@@ -61,6 +141,9 @@ where
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             Self::NoSolution(err) => f.debug_tuple("NoSolution").field(&err).finish(),
+            Self::IndependentConflicts(errs) => {
+                f.debug_tuple("IndependentConflicts").field(&errs).finish()
+            }
             Self::ErrorRetrievingDependencies {
                 package,
                 version,
@@ -75,9 +158,29 @@ where
                 .debug_tuple("ErrorChoosingPackageVersion")
                 .field(arg0)
                 .finish(),
-            Self::ErrorInShouldCancel(arg0) => {
-                f.debug_tuple("ErrorInShouldCancel").field(arg0).finish()
-            }
+            Self::ErrorInShouldCancel { source, decided } => f
+                .debug_struct("ErrorInShouldCancel")
+                .field("source", source)
+                .field("decided", decided)
+                .finish(),
+            Self::LimitExceeded {
+                incompatibilities,
+                decision_level,
+            } => f
+                .debug_struct("LimitExceeded")
+                .field("incompatibilities", incompatibilities)
+                .field("decision_level", decision_level)
+                .finish(),
+            Self::InvalidVersionChoice {
+                package,
+                version,
+                range,
+            } => f
+                .debug_struct("InvalidVersionChoice")
+                .field("package", package)
+                .field("version", version)
+                .field("range", range)
+                .finish(),
             Self::Failure(arg0) => f.debug_tuple("Failure").field(arg0).finish(),
         }
     }
@@ -0,0 +1,288 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Optional dependencies enabled by features, like Cargo features.
+//!
+//! PubGrub only knows about packages and versions,
+//! so each feature of a package is modeled as a package of its own, [Feature::Enabled],
+//! with the same versions as the package.
+//! A version of a feature package depends on exactly the same version of the [Feature::Base] package,
+//! and on the optional dependencies that the feature enables.
+//! Requiring a feature of a dependency then simply means depending on its feature package.
+//!
+//! [FeatureDependencies] describes the dependencies of one package version,
+//! and expands them into the dependencies of these packages.
+//! [FeatureDependencyProvider] is a basic [DependencyProvider] built on top of it.
+
+use std::cmp::Reverse;
+use std::collections::BTreeMap;
+use std::convert::Infallible;
+use std::fmt::{self, Display};
+
+use crate::{Dependencies, DependencyConstraints, DependencyProvider, Map, Package, VersionSet};
+
+/// A package, or one of its features.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Feature<P> {
+    /// The package itself, with its required dependencies.
+    Base(P),
+    /// A feature of the package, with the optional dependencies it enables.
+    Enabled(P, String),
+}
+
+impl<P> Feature<P> {
+    /// The package this is the base or a feature of.
+    pub fn package(&self) -> &P {
+        match self {
+            Self::Base(package) | Self::Enabled(package, _) => package,
+        }
+    }
+}
+
+impl<P: Display> Display for Feature<P> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Base(package) => write!(f, "{}", package),
+            Self::Enabled(package, feature) => write!(f, "{}[{}]", package, feature),
+        }
+    }
+}
+
+/// A dependency, with the features required on it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+struct FeatureDependency<P, VS> {
+    package: P,
+    range: VS,
+    features: Vec<String>,
+}
+
+/// Dependencies of a package version, some of them only enabled by a feature.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(
+    feature = "serde",
+    serde(bound(
+        serialize = "VS: serde::Serialize, P: serde::Serialize",
+        deserialize = "VS: serde::Deserialize<'de>, P: serde::Deserialize<'de>"
+    ))
+)]
+pub struct FeatureDependencies<P: Package, VS> {
+    required: Vec<FeatureDependency<P, VS>>,
+    features: Map<String, Vec<FeatureDependency<P, VS>>>,
+}
+
+impl<P: Package, VS: VersionSet> Default for FeatureDependencies<P, VS> {
+    fn default() -> Self {
+        Self {
+            required: Vec::new(),
+            features: Map::default(),
+        }
+    }
+}
+
+impl<P: Package, VS: VersionSet> FeatureDependencies<P, VS> {
+    /// No dependencies, and no features.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a required dependency, with the given features enabled.
+    pub fn with_dependency(
+        mut self,
+        package: P,
+        range: VS,
+        features: impl IntoIterator<Item = impl Into<String>>,
+    ) -> Self {
+        self.required.push(FeatureDependency {
+            package,
+            range,
+            features: features.into_iter().map(Into::into).collect(),
+        });
+        self
+    }
+
+    /// Declare a feature, without optional dependencies yet.
+    pub fn with_feature(mut self, feature: impl Into<String>) -> Self {
+        self.features.entry(feature.into()).or_default();
+        self
+    }
+
+    /// Add an optional dependency, enabled by `feature`, with the given features enabled.
+    ///
+    /// The package itself can be given to enable other features of the same version.
+    pub fn with_feature_dependency(
+        mut self,
+        feature: impl Into<String>,
+        package: P,
+        range: VS,
+        features: impl IntoIterator<Item = impl Into<String>>,
+    ) -> Self {
+        self.features
+            .entry(feature.into())
+            .or_default()
+            .push(FeatureDependency {
+                package,
+                range,
+                features: features.into_iter().map(Into::into).collect(),
+            });
+        self
+    }
+
+    /// Names of the features of this package version.
+    pub fn features(&self) -> impl Iterator<Item = &str> {
+        self.features.keys().map(String::as_str)
+    }
+
+    /// Dependencies of the base or a feature of the package,
+    /// when these are the dependencies of its given version.
+    ///
+    /// A feature depends on the same version of the base package,
+    /// and enabling a feature of a dependency adds a dependency on that feature.
+    /// Returns [None] if the feature does not exist.
+    pub fn dependencies(
+        &self,
+        package: &Feature<P>,
+        version: &VS::V,
+    ) -> Option<DependencyConstraints<Feature<P>, VS>> {
+        let mut constraints = DependencyConstraints::default();
+        let own_version = VS::singleton(version.clone());
+        let add = |constraints: &mut DependencyConstraints<Feature<P>, VS>,
+                   dependency: Feature<P>,
+                   range: &VS| {
+            constraints
+                .entry(dependency)
+                .and_modify(|r: &mut VS| *r = r.intersection(range))
+                .or_insert_with(|| range.clone());
+        };
+        let dependencies = match package {
+            Feature::Base(_) => &self.required,
+            Feature::Enabled(p, feature) => {
+                let dependencies = self.features.get(feature)?;
+                add(&mut constraints, Feature::Base(p.clone()), &own_version);
+                dependencies
+            }
+        };
+        for dependency in dependencies {
+            let is_self = &dependency.package == package.package();
+            let range = if is_self {
+                &own_version
+            } else {
+                add(
+                    &mut constraints,
+                    Feature::Base(dependency.package.clone()),
+                    &dependency.range,
+                );
+                &dependency.range
+            };
+            for feature in &dependency.features {
+                let feature = Feature::Enabled(dependency.package.clone(), feature.clone());
+                if &feature != package {
+                    add(&mut constraints, feature, range);
+                }
+            }
+        }
+        Some(constraints)
+    }
+}
+
+/// A basic implementation of [DependencyProvider] for packages with features.
+///
+/// Like [OfflineDependencyProvider](crate::OfflineDependencyProvider),
+/// all dependencies are stored in memory,
+/// the newest version in the range is chosen,
+/// and the packages with the fewest versions are decided first.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(
+    feature = "serde",
+    serde(bound(
+        serialize = "VS::V: serde::Serialize, VS: serde::Serialize, P: serde::Serialize",
+        deserialize = "VS::V: serde::Deserialize<'de>, VS: serde::Deserialize<'de>, P: serde::Deserialize<'de>"
+    ))
+)]
+#[cfg_attr(feature = "serde", serde(transparent))]
+pub struct FeatureDependencyProvider<P: Package, VS: VersionSet> {
+    dependencies: Map<P, BTreeMap<VS::V, FeatureDependencies<P, VS>>>,
+}
+
+impl<P: Package, VS: VersionSet> Default for FeatureDependencyProvider<P, VS> {
+    fn default() -> Self {
+        Self {
+            dependencies: Map::default(),
+        }
+    }
+}
+
+impl<P: Package, VS: VersionSet> FeatureDependencyProvider<P, VS> {
+    /// Creates an empty FeatureDependencyProvider with no dependencies.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers the dependencies of a package and version pair.
+    ///
+    /// The dependencies of the same package and version pair are replaced if they were already registered.
+    pub fn add_dependencies(
+        &mut self,
+        package: P,
+        version: impl Into<VS::V>,
+        dependencies: FeatureDependencies<P, VS>,
+    ) {
+        self.dependencies
+            .entry(package)
+            .or_default()
+            .insert(version.into(), dependencies);
+    }
+}
+
+impl<P: Package, VS: VersionSet> DependencyProvider for FeatureDependencyProvider<P, VS> {
+    type P = Feature<P>;
+    type V = VS::V;
+    type VS = VS;
+    type M = String;
+
+    type Err = Infallible;
+
+    fn choose_version(
+        &self,
+        package: &Feature<P>,
+        range: &VS,
+    ) -> Result<Option<VS::V>, Infallible> {
+        Ok(self
+            .dependencies
+            .get(package.package())
+            .and_then(|versions| versions.keys().rev().find(|v| range.contains(v)).cloned()))
+    }
+
+    type Priority = Reverse<usize>;
+    fn prioritize(&self, package: &Feature<P>, range: &VS) -> Self::Priority {
+        Reverse(
+            self.dependencies
+                .get(package.package())
+                .map(|versions| versions.keys().filter(|v| range.contains(v)).count())
+                .unwrap_or(0),
+        )
+    }
+
+    fn get_dependencies(
+        &self,
+        package: &Feature<P>,
+        version: &VS::V,
+    ) -> Result<Dependencies<Feature<P>, VS, Self::M>, Infallible> {
+        let dependencies = self
+            .dependencies
+            .get(package.package())
+            .and_then(|versions| versions.get(version))
+            .and_then(|dependencies| dependencies.dependencies(package, version));
+        Ok(match dependencies {
+            None => Dependencies::Unavailable(match package {
+                Feature::Base(_) => "its dependencies could not be determined".to_string(),
+                Feature::Enabled(_, feature) => {
+                    format!("because the feature {} does not exist", feature)
+                }
+            }),
+            Some(dependencies) => Dependencies::Available(dependencies),
+        })
+    }
+}
@@ -0,0 +1,109 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! A [DependencyProvider] wrapper hiding some versions of another one.
+
+use std::collections::BTreeSet;
+
+use crate::solver::forward_to_dependency_provider;
+use crate::{Dependencies, DependencyProvider, Map, ProgressInfo, VersionPreference, VersionSet};
+
+/// Restricts the versions chosen by another [DependencyProvider]
+/// to an allowlist and a denylist, for example for a security policy.
+///
+/// The range of every version choice is intersected with the allowed versions of the package
+/// before being given to the inner provider, so denied versions are never chosen.
+/// When no allowed version is left, the report says there is no version in the requested range.
+/// Packages without allowed or denied versions are left as is.
+#[derive(Clone)]
+pub struct FilteredDependencyProvider<DP: DependencyProvider> {
+    inner: DP,
+    allowed: Map<DP::P, DP::VS>,
+}
+
+impl<DP: DependencyProvider> FilteredDependencyProvider<DP> {
+    /// Wrap a dependency provider, without filtering any version yet.
+    pub fn new(inner: DP) -> Self {
+        Self {
+            inner,
+            allowed: Map::default(),
+        }
+    }
+
+    /// Only allow the given versions of a package,
+    /// among the ones allowed so far.
+    pub fn allow(mut self, package: DP::P, versions: DP::VS) -> Self {
+        let allowed = match self.allowed.remove(&package) {
+            Some(allowed) => allowed.intersection(&versions),
+            None => versions,
+        };
+        self.allowed.insert(package, allowed);
+        self
+    }
+
+    /// Deny the given versions of a package.
+    pub fn deny(self, package: DP::P, versions: DP::VS) -> Self {
+        self.allow(package, versions.complement())
+    }
+
+    /// The wrapped dependency provider.
+    pub fn inner(&self) -> &DP {
+        &self.inner
+    }
+
+    /// Return the wrapped dependency provider, dropping the filters.
+    pub fn into_inner(self) -> DP {
+        self.inner
+    }
+
+    fn filter(&self, package: &DP::P, range: &DP::VS) -> Option<DP::VS> {
+        self.allowed
+            .get(package)
+            .map(|allowed| allowed.intersection(range))
+    }
+}
+
+impl<DP: DependencyProvider> DependencyProvider for FilteredDependencyProvider<DP> {
+    type P = DP::P;
+    type V = DP::V;
+    type VS = DP::VS;
+    type M = DP::M;
+
+    fn prioritize(&self, package: &DP::P, range: &DP::VS) -> DP::Priority {
+        match self.filter(package, range) {
+            Some(range) => self.inner.prioritize(package, &range),
+            None => self.inner.prioritize(package, range),
+        }
+    }
+
+    type Priority = DP::Priority;
+
+    type Err = DP::Err;
+
+    fn choose_version(&self, package: &DP::P, range: &DP::VS) -> Result<Option<DP::V>, DP::Err> {
+        match self.filter(package, range) {
+            Some(range) => self.inner.choose_version(package, &range),
+            None => self.inner.choose_version(package, range),
+        }
+    }
+
+    fn choose_version_with_tried(
+        &self,
+        package: &DP::P,
+        range: &DP::VS,
+        already_tried: &BTreeSet<DP::V>,
+    ) -> Result<Option<DP::V>, DP::Err> {
+        match self.filter(package, range) {
+            Some(range) => self
+                .inner
+                .choose_version_with_tried(package, &range, already_tried),
+            None => self
+                .inner
+                .choose_version_with_tried(package, range, already_tried),
+        }
+    }
+
+    forward_to_dependency_provider!(
+        inner: package_exists, is_deprioritized, version_preference, get_dependencies, prefetch,
+        should_cancel, on_progress
+    );
+}
@@ -1,8 +1,13 @@
+use std::cmp::Ordering;
 use std::fmt;
-use std::hash::{Hash, Hasher};
+use std::hash::{BuildHasherDefault, Hash, Hasher};
 use std::marker::PhantomData;
 use std::ops::{Index, Range};
 
+use rustc_hash::FxHasher;
+
+type FnvIndexSet<V> = indexmap::IndexSet<V, BuildHasherDefault<FxHasher>>;
+
 /// The index of a value allocated in an arena that holds `T`s.
 ///
 /// The Clone, Copy and other traits are defined manually because
@@ -31,6 +36,18 @@ impl<T> PartialEq for Id<T> {
 
 impl<T> Eq for Id<T> {}
 
+impl<T> PartialOrd for Id<T> {
+    fn partial_cmp(&self, other: &Id<T>) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<T> Ord for Id<T> {
+    fn cmp(&self, other: &Id<T>) -> Ordering {
+        self.raw.cmp(&other.raw)
+    }
+}
+
 impl<T> Hash for Id<T> {
     fn hash<H: Hasher>(&self, state: &mut H) {
         self.raw.hash(state)
@@ -47,6 +64,14 @@ impl<T> fmt::Debug for Id<T> {
     }
 }
 
+/// Only the raw index is displayed,
+/// the value it identifies is not known without its arena.
+impl<T> fmt::Display for Id<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.raw)
+    }
+}
+
 impl<T> Id<T> {
     pub(crate) fn into_raw(self) -> usize {
         self.raw as usize
@@ -109,6 +134,10 @@ impl<T> Arena<T> {
         let end = Id::from(self.data.len() as u32);
         Range { start, end }
     }
+
+    pub(crate) fn len(&self) -> usize {
+        self.data.len()
+    }
 }
 
 impl<T> Index<Id<T>> for Arena<T> {
@@ -124,3 +153,55 @@ impl<T> Index<Range<Id<T>>> for Arena<T> {
         &self.data[(id.start.raw as usize)..(id.end.raw as usize)]
     }
 }
+
+/// An arena storing each distinct value once.
+///
+/// Allocating a value equal to one already in the arena returns the id of the existing one,
+/// so ids can be compared and hashed instead of the values.
+/// This is used to intern packages, which are cheaper to manipulate as ids.
+#[derive(Clone, PartialEq, Eq)]
+pub(crate) struct HashArena<T: Hash + Eq> {
+    data: FnvIndexSet<T>,
+}
+
+impl<T: Hash + Eq + fmt::Debug> fmt::Debug for HashArena<T> {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fmt.debug_struct("HashArena")
+            .field("len", &self.data.len())
+            .field("data", &self.data)
+            .finish()
+    }
+}
+
+impl<T: Hash + Eq> Default for HashArena<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Hash + Eq> HashArena<T> {
+    pub(crate) fn new() -> Self {
+        Self {
+            data: FnvIndexSet::default(),
+        }
+    }
+
+    pub(crate) fn alloc(&mut self, value: T) -> Id<T> {
+        let (raw, _) = self.data.insert_full(value);
+        Id::from(raw as u32)
+    }
+
+    /// The id of a value, if it was allocated in the arena.
+    pub(crate) fn get_id(&self, value: &T) -> Option<Id<T>> {
+        self.data
+            .get_index_of(value)
+            .map(|raw| Id::from(raw as u32))
+    }
+}
+
+impl<T: Hash + Eq> Index<Id<T>> for HashArena<T> {
+    type Output = T;
+    fn index(&self, id: Id<T>) -> &T {
+        &self.data[id.raw as usize]
+    }
+}
@@ -7,19 +7,25 @@ use std::collections::HashSet as Set;
 use std::sync::Arc;
 
 use crate::internal::{
-    Arena, DecisionLevel, IncompDpId, Incompatibility, PartialSolution, Relation, SatisfierSearch,
-    SmallVec,
+    Arena, DecisionLevel, HashArena, Id, IncompDp, IncompDpId, Incompatibility, PartialSolution,
+    Relation, SatisfierSearch, SmallVec,
+};
+use crate::{
+    DependencyProvider, DerivationTree, Map, PubGrubError, ResolveEvent, ResolveStats, VersionSet,
 };
-use crate::{DependencyProvider, DerivationTree, Map, NoSolutionError, VersionSet};
 
 /// Current state of the PubGrub algorithm.
+///
+/// Packages are interned in the `package_store` when they enter the state,
+/// and only manipulated by their [Id] in the algorithm,
+/// which is cheaper to copy, compare and hash than the packages themselves.
 #[derive(Clone)]
 pub(crate) struct State<DP: DependencyProvider> {
-    root_package: DP::P,
+    root_package: Id<DP::P>,
     root_version: DP::V,
 
     #[allow(clippy::type_complexity)]
-    incompatibilities: Map<DP::P, Vec<IncompDpId<DP>>>,
+    incompatibilities: Map<Id<DP::P>, Vec<IncompDpId<DP>>>,
 
     /// Store the ids of incompatibilities that are already contradicted.
     /// For each one keep track of the decision level when it was found to be contradicted.
@@ -29,31 +35,50 @@ pub(crate) struct State<DP: DependencyProvider> {
     /// All incompatibilities expressing dependencies,
     /// with common dependents merged.
     #[allow(clippy::type_complexity)]
-    merged_dependencies: Map<(DP::P, DP::P), SmallVec<IncompDpId<DP>>>,
+    merged_dependencies: Map<(Id<DP::P>, Id<DP::P>), SmallVec<IncompDpId<DP>>>,
 
     /// Partial solution.
     /// TODO: remove pub.
     pub(crate) partial_solution: PartialSolution<DP>,
 
     /// The store is the reference storage for all incompatibilities.
-    pub(crate) incompatibility_store: Arena<Incompatibility<DP::P, DP::VS, DP::M>>,
+    pub(crate) incompatibility_store: Arena<IncompDp<DP>>,
+
+    /// The store is the reference storage for all packages.
+    pub(crate) package_store: HashArena<DP::P>,
+
+    /// Number of conflicts resolved so far.
+    pub(crate) conflict_count: u64,
+
+    /// Statistics of the resolution so far.
+    pub(crate) stats: ResolveStats,
+
+    /// Maximum number of incompatibilities in the `incompatibility_store`,
+    /// adding more fails with [PubGrubError::LimitExceeded].
+    pub(crate) max_incompatibilities: usize,
+
+    /// Events of the resolution so far, only recorded when it is not [None].
+    #[allow(clippy::type_complexity)]
+    pub(crate) history: Option<Vec<ResolveEvent<DP::P, DP::V>>>,
 
     /// This is a stack of work to be done in `unit_propagation`.
     /// It can definitely be a local variable to that method, but
     /// this way we can reuse the same allocation for better performance.
-    unit_propagation_buffer: SmallVec<DP::P>,
+    unit_propagation_buffer: SmallVec<Id<DP::P>>,
 }
 
 impl<DP: DependencyProvider> State<DP> {
     /// Initialization of PubGrub state.
     pub(crate) fn init(root_package: DP::P, root_version: DP::V) -> Self {
+        let mut package_store = HashArena::new();
+        let root_package = package_store.alloc(root_package);
         let mut incompatibility_store = Arena::new();
         let not_root_id = incompatibility_store.alloc(Incompatibility::not_root(
-            root_package.clone(),
+            root_package,
             root_version.clone(),
         ));
         let mut incompatibilities = Map::default();
-        incompatibilities.insert(root_package.clone(), vec![not_root_id]);
+        incompatibilities.insert(root_package, vec![not_root_id]);
         Self {
             root_package,
             root_version,
@@ -61,44 +86,121 @@ impl<DP: DependencyProvider> State<DP> {
             contradicted_incompatibilities: Map::default(),
             partial_solution: PartialSolution::empty(),
             incompatibility_store,
+            package_store,
+            conflict_count: 0,
+            stats: ResolveStats::default(),
+            max_incompatibilities: usize::MAX,
+            history: None,
             unit_propagation_buffer: SmallVec::Empty,
             merged_dependencies: Map::default(),
         }
     }
 
+    /// Prepare a new resolution from the same root package and version.
+    ///
+    /// The partial solution is emptied, and the incompatibilities are kept,
+    /// except the ones created from the versions or dependencies of an `invalidated` package
+    /// or derived from such an incompatibility.
+    pub(crate) fn restart(&mut self, invalidated: &crate::Set<DP::P>) {
+        // Packages that were never interned cannot be the source of any incompatibility.
+        let invalidated: crate::Set<Id<DP::P>> = invalidated
+            .iter()
+            .filter_map(|p| self.package_store.get_id(p))
+            .collect();
+        let mut valid: Map<IncompDpId<DP>, bool> = Map::default();
+        let mut stack = Vec::new();
+        for &id in self.incompatibilities.values().flatten() {
+            stack.push(id);
+            while let Some(&id) = stack.last() {
+                if valid.contains_key(&id) {
+                    stack.pop();
+                    continue;
+                }
+                let incompat = &self.incompatibility_store[id];
+                let is_valid = match incompat.causes() {
+                    Some((id1, id2)) => match (valid.get(&id1), valid.get(&id2)) {
+                        (Some(&valid1), Some(&valid2)) => valid1 && valid2,
+                        (valid1, _) => {
+                            // Check the causes first.
+                            stack.push(if valid1.is_none() { id1 } else { id2 });
+                            continue;
+                        }
+                    },
+                    None => !incompat
+                        .source_package()
+                        .is_some_and(|p| invalidated.contains(p)),
+                };
+                valid.insert(id, is_valid);
+                stack.pop();
+            }
+        }
+        for ids in self.incompatibilities.values_mut() {
+            ids.retain(|id| valid[id]);
+        }
+        // Merged dependencies are all created from the dependencies of their dependent package.
+        self.merged_dependencies
+            .retain(|(dependent, _), _| !invalidated.contains(dependent));
+        self.contradicted_incompatibilities.clear();
+        self.partial_solution = PartialSolution::empty();
+        self.conflict_count = 0;
+        self.stats = ResolveStats::default();
+    }
+
     /// Add an incompatibility to the state.
-    pub(crate) fn add_incompatibility(&mut self, incompat: Incompatibility<DP::P, DP::VS, DP::M>) {
+    pub(crate) fn add_incompatibility(
+        &mut self,
+        incompat: IncompDp<DP>,
+    ) -> Result<(), PubGrubError<DP>> {
         let id = self.incompatibility_store.alloc(incompat);
         self.merge_incompatibility(id);
+        self.check_incompatibility_limit()
     }
 
     /// Add an incompatibility to the state.
     pub(crate) fn add_incompatibility_from_dependencies(
         &mut self,
-        package: DP::P,
+        package: Id<DP::P>,
         version: DP::V,
         deps: impl IntoIterator<Item = (DP::P, DP::VS)>,
-    ) -> std::ops::Range<IncompDpId<DP>> {
+    ) -> Result<std::ops::Range<IncompDpId<DP>>, PubGrubError<DP>> {
         // Create incompatibilities and allocate them in the store.
         let new_incompats_id_range =
             self.incompatibility_store
-                .alloc_iter(deps.into_iter().map(|dep| {
+                .alloc_iter(deps.into_iter().map(|(dep_p, dep_vs)| {
+                    let dep_pid = self.package_store.alloc(dep_p);
                     Incompatibility::from_dependency(
-                        package.clone(),
+                        package,
                         <DP::VS as VersionSet>::singleton(version.clone()),
-                        dep,
+                        (dep_pid, dep_vs),
                     )
                 }));
         // Merge the newly created incompatibilities with the older ones.
         for id in IncompDpId::<DP>::range_to_iter(new_incompats_id_range.clone()) {
             self.merge_incompatibility(id);
         }
-        new_incompats_id_range
+        self.check_incompatibility_limit()?;
+        Ok(new_incompats_id_range)
+    }
+
+    /// Fail if there are more incompatibilities than [max_incompatibilities](Self::max_incompatibilities).
+    fn check_incompatibility_limit(&self) -> Result<(), PubGrubError<DP>> {
+        let incompatibilities = self.incompatibility_store.len();
+        if incompatibilities > self.max_incompatibilities {
+            return Err(PubGrubError::LimitExceeded {
+                incompatibilities,
+                decision_level: self.partial_solution.current_decision_level().0,
+            });
+        }
+        Ok(())
     }
 
     /// Unit propagation is the core mechanism of the solving algorithm.
     /// CF <https://github.com/dart-lang/pub/blob/master/doc/solver.md#unit-propagation>
-    pub(crate) fn unit_propagation(&mut self, package: DP::P) -> Result<(), NoSolutionError<DP>> {
+    ///
+    /// It fails with [PubGrubError::NoSolution] when there is no solution,
+    /// and with [PubGrubError::LimitExceeded] when conflict resolution derived
+    /// more incompatibilities than allowed.
+    pub(crate) fn unit_propagation(&mut self, package: Id<DP::P>) -> Result<(), PubGrubError<DP>> {
         self.unit_propagation_buffer.clear();
         self.unit_propagation_buffer.push(package);
         while let Some(current_package) = self.unit_propagation_buffer.pop() {
@@ -120,7 +222,7 @@ impl<DP: DependencyProvider> State<DP> {
                     Relation::Satisfied => {
                         log::info!(
                             "Start conflict resolution because incompat satisfied:\n   {}",
-                            current_incompat
+                            current_incompat.display(&self.package_store)
                         );
                         conflict_id = Some(incompat_id);
                         break;
@@ -131,7 +233,7 @@ impl<DP: DependencyProvider> State<DP> {
                         // but so does allocating a hash map and hashing each item.
                         // In practice `unit_propagation_buffer` is small enough that we can just do a linear scan.
                         if !self.unit_propagation_buffer.contains(&package_almost) {
-                            self.unit_propagation_buffer.push(package_almost.clone());
+                            self.unit_propagation_buffer.push(package_almost);
                         }
                         // Add (not term) to the partial solution with incompat as cause.
                         self.partial_solution.add_derivation(
@@ -151,13 +253,17 @@ impl<DP: DependencyProvider> State<DP> {
                 }
             }
             if let Some(incompat_id) = conflict_id {
+                self.conflict_count += 1;
                 let (package_almost, root_cause) =
                     self.conflict_resolution(incompat_id)
                         .map_err(|terminal_incompat_id| {
-                            self.build_derivation_tree(terminal_incompat_id)
+                            PubGrubError::NoSolution(
+                                self.build_derivation_tree(terminal_incompat_id),
+                            )
                         })?;
+                self.check_incompatibility_limit()?;
                 self.unit_propagation_buffer.clear();
-                self.unit_propagation_buffer.push(package_almost.clone());
+                self.unit_propagation_buffer.push(package_almost);
                 // Add to the partial solution with incompat as cause.
                 self.partial_solution.add_derivation(
                     package_almost,
@@ -180,7 +286,7 @@ impl<DP: DependencyProvider> State<DP> {
     fn conflict_resolution(
         &mut self,
         incompatibility: IncompDpId<DP>,
-    ) -> Result<(DP::P, IncompDpId<DP>), IncompDpId<DP>> {
+    ) -> Result<(Id<DP::P>, IncompDpId<DP>), IncompDpId<DP>> {
         let mut current_incompat_id = incompatibility;
         let mut current_incompat_changed = false;
         loop {
@@ -197,7 +303,7 @@ impl<DP: DependencyProvider> State<DP> {
                     SatisfierSearch::DifferentDecisionLevels {
                         previous_satisfier_level,
                     } => {
-                        let package = package.clone();
+                        let package = *package;
                         self.backtrack(
                             current_incompat_id,
                             current_incompat_changed,
@@ -207,15 +313,22 @@ impl<DP: DependencyProvider> State<DP> {
                         return Ok((package, current_incompat_id));
                     }
                     SatisfierSearch::SameDecisionLevels { satisfier_cause } => {
+                        let package = *package;
                         let prior_cause = Incompatibility::prior_cause(
                             current_incompat_id,
                             satisfier_cause,
-                            package,
+                            &package,
                             &self.incompatibility_store,
                         );
-                        log::info!("prior cause: {}", prior_cause);
+                        log::info!("prior cause: {}", prior_cause.display(&self.package_store));
                         current_incompat_id = self.incompatibility_store.alloc(prior_cause);
                         current_incompat_changed = true;
+                        self.stats.derived_incompatibilities += 1;
+                        if let Some(history) = &mut self.history {
+                            history.push(ResolveEvent::Derived {
+                                package: self.package_store[package].clone(),
+                            });
+                        }
                     }
                 }
             }
@@ -229,6 +342,12 @@ impl<DP: DependencyProvider> State<DP> {
         incompat_changed: bool,
         decision_level: DecisionLevel,
     ) {
+        self.stats.backtracks += 1;
+        if let Some(history) = &mut self.history {
+            history.push(ResolveEvent::Backtracked {
+                to_level: decision_level.0,
+            });
+        }
         self.partial_solution.backtrack(decision_level);
         // Remove contradicted incompatibilities that depend on decisions we just backtracked away.
         self.contradicted_incompatibilities
@@ -256,10 +375,7 @@ impl<DP: DependencyProvider> State<DP> {
     fn merge_incompatibility(&mut self, mut id: IncompDpId<DP>) {
         if let Some((p1, p2)) = self.incompatibility_store[id].as_dependency() {
             // If we are a dependency, there's a good chance we can be merged with a previous dependency
-            let deps_lookup = self
-                .merged_dependencies
-                .entry((p1.clone(), p2.clone()))
-                .or_default();
+            let deps_lookup = self.merged_dependencies.entry((*p1, *p2)).or_default();
             if let Some((past, merged)) = deps_lookup.as_mut_slice().iter_mut().find_map(|past| {
                 self.incompatibility_store[id]
                     .merge_dependents(&self.incompatibility_store[*past])
@@ -268,7 +384,7 @@ impl<DP: DependencyProvider> State<DP> {
                 let new = self.incompatibility_store.alloc(merged);
                 for (pkg, _) in self.incompatibility_store[new].iter() {
                     self.incompatibilities
-                        .entry(pkg.clone())
+                        .entry(*pkg)
                         .or_default()
                         .retain(|id| id != past);
                 }
@@ -282,10 +398,7 @@ impl<DP: DependencyProvider> State<DP> {
             if cfg!(debug_assertions) {
                 assert_ne!(term, &crate::term::Term::any());
             }
-            self.incompatibilities
-                .entry(pkg.clone())
-                .or_default()
-                .push(id);
+            self.incompatibilities.entry(*pkg).or_default().push(id);
         }
     }
 
@@ -320,6 +433,7 @@ impl<DP: DependencyProvider> State<DP> {
                 id,
                 &shared_ids,
                 &self.incompatibility_store,
+                &self.package_store,
                 &precomputed,
             );
             precomputed.insert(id, Arc::new(tree));
@@ -6,7 +6,7 @@
 use std::fmt::{self, Debug, Display};
 use std::sync::Arc;
 
-use crate::internal::{Arena, Id, SmallMap};
+use crate::internal::{Arena, HashArena, Id, SmallMap};
 use crate::{
     term, DefaultStringReportFormatter, DependencyProvider, DerivationTree, Derived, External, Map,
     Package, ReportFormatter, Set, Term, VersionSet,
@@ -36,12 +36,16 @@ pub(crate) struct Incompatibility<P: Package, VS: VersionSet, M: Eq + Clone + De
 /// Type alias of unique identifiers for incompatibilities.
 pub(crate) type IncompId<P, VS, M> = Id<Incompatibility<P, VS, M>>;
 
-pub(crate) type IncompDpId<DP> = IncompId<
-    <DP as DependencyProvider>::P,
+/// Incompatibility of the solver, over interned packages.
+pub(crate) type IncompDp<DP> = Incompatibility<
+    Id<<DP as DependencyProvider>::P>,
     <DP as DependencyProvider>::VS,
     <DP as DependencyProvider>::M,
 >;
 
+/// Identifier of an incompatibility of the solver.
+pub(crate) type IncompDpId<DP> = Id<IncompDp<DP>>;
+
 #[derive(Debug, Clone)]
 enum Kind<P: Package, VS: VersionSet, M: Eq + Clone + Debug + Display> {
     /// Initial incompatibility aiming at picking the root package for the first decision.
@@ -54,6 +58,8 @@ enum Kind<P: Package, VS: VersionSet, M: Eq + Clone + Debug + Display> {
     /// This incompatibility is used when we tried all versions in a range and no version
     /// worked, so we have to backtrack
     NoVersions(P, VS),
+    /// The package does not exist at all, so none of its versions can be picked.
+    PackageNotFound(P),
     /// Incompatibility coming from the dependencies of a given package.
     ///
     /// If a@1 depends on b>=1,<2, we create an incompatibility with terms `{a 1, b <1,>=2}` with
@@ -115,8 +121,15 @@ impl<P: Package, VS: VersionSet, M: Eq + Clone + Debug + Display> Incompatibilit
         }
     }
 
+    /// Create an incompatibility to remember that a package does not exist.
+    pub(crate) fn package_not_found(package: P) -> Self {
+        Self {
+            package_terms: SmallMap::One([(package.clone(), Term::Positive(VS::full()))]),
+            kind: Kind::PackageNotFound(package),
+        }
+    }
+
     /// Create an incompatibility for a reason outside pubgrub.
-    #[allow(dead_code)] // Used by uv
     pub(crate) fn custom_term(package: P, term: Term<VS>, metadata: M) -> Self {
         let set = match &term {
             Term::Positive(r) => r.clone(),
@@ -161,6 +174,18 @@ impl<P: Package, VS: VersionSet, M: Eq + Clone + Debug + Display> Incompatibilit
         }
     }
 
+    /// Package whose versions or dependencies this incompatibility was created from,
+    /// or [None] for the root incompatibility and derived incompatibilities.
+    pub(crate) fn source_package(&self) -> Option<&P> {
+        match &self.kind {
+            Kind::NotRoot(_, _) | Kind::DerivedFrom(_, _) => None,
+            Kind::NoVersions(p, _)
+            | Kind::PackageNotFound(p)
+            | Kind::FromDependencyOf(p, _, _, _)
+            | Kind::Custom(p, _, _) => Some(p),
+        }
+    }
+
     /// Merge dependant versions with the same dependency.
     ///
     /// When multiple versions of a package depend on the same range of another package,
@@ -186,7 +211,7 @@ impl<P: Package, VS: VersionSet, M: Eq + Clone + Debug + Display> Incompatibilit
         if dep_term != other.get(p2) {
             return None;
         }
-        return Some(Self::from_dependency(
+        Some(Self::from_dependency(
             p1.clone(),
             self.get(p1)
                 .unwrap()
@@ -196,7 +221,7 @@ impl<P: Package, VS: VersionSet, M: Eq + Clone + Debug + Display> Incompatibilit
                 p2.clone(),
                 dep_term.map_or(VS::empty(), |v| v.unwrap_negative().clone()),
             ),
-        ));
+        ))
     }
 
     /// Prior cause of two incompatibilities using the rule of resolution.
@@ -218,7 +243,7 @@ impl<P: Package, VS: VersionSet, M: Eq + Clone + Debug + Display> Incompatibilit
             |t1, t2| Some(t1.intersection(t2)),
         );
         let term = t1.union(satisfier_cause_terms.get(package).unwrap());
-        if term != Term::any() {
+        if !term.is_any() {
             package_terms.insert(package.clone(), term);
         }
         Self {
@@ -259,18 +284,26 @@ impl<P: Package, VS: VersionSet, M: Eq + Clone + Debug + Display> Incompatibilit
             _ => None,
         }
     }
+}
 
-    /// Build a derivation tree for error reporting.
+impl<P: Package, VS: VersionSet, M: Eq + Clone + Debug + Display> Incompatibility<Id<P>, VS, M> {
+    /// Build a derivation tree for error reporting,
+    /// with the interned packages replaced by the ones from the `package_store`.
     pub(crate) fn build_derivation_tree(
         self_id: Id<Self>,
         shared_ids: &Set<Id<Self>>,
         store: &Arena<Self>,
+        package_store: &HashArena<P>,
         precomputed: &Map<Id<Self>, Arc<DerivationTree<P, VS, M>>>,
     ) -> DerivationTree<P, VS, M> {
         match store[self_id].kind.clone() {
             Kind::DerivedFrom(id1, id2) => {
                 let derived = Derived {
-                    terms: store[self_id].package_terms.as_map(),
+                    terms: store[self_id]
+                        .package_terms
+                        .iter()
+                        .map(|(&p, t)| (package_store[p].clone(), t.clone()))
+                        .collect(),
                     shared_id: shared_ids.get(&self_id).map(|id| id.into_raw()),
                     cause1: precomputed
                         .get(&id1)
@@ -284,26 +317,60 @@ impl<P: Package, VS: VersionSet, M: Eq + Clone + Debug + Display> Incompatibilit
                 DerivationTree::Derived(derived)
             }
             Kind::NotRoot(package, version) => {
-                DerivationTree::External(External::NotRoot(package, version))
+                DerivationTree::External(External::NotRoot(package_store[package].clone(), version))
             }
             Kind::NoVersions(package, set) => {
-                DerivationTree::External(External::NoVersions(package.clone(), set.clone()))
+                DerivationTree::External(External::NoVersions(package_store[package].clone(), set))
+            }
+            Kind::PackageNotFound(package) => {
+                DerivationTree::External(External::PackageNotFound(package_store[package].clone()))
             }
             Kind::FromDependencyOf(package, set, dep_package, dep_set) => {
                 DerivationTree::External(External::FromDependencyOf(
-                    package.clone(),
-                    set.clone(),
-                    dep_package.clone(),
-                    dep_set.clone(),
+                    package_store[package].clone(),
+                    set,
+                    package_store[dep_package].clone(),
+                    dep_set,
                 ))
             }
             Kind::Custom(package, set, metadata) => DerivationTree::External(External::Custom(
-                package.clone(),
-                set.clone(),
-                metadata.clone(),
+                package_store[package].clone(),
+                set,
+                metadata,
             )),
         }
     }
+
+    /// Display the incompatibility with the packages from the `package_store`.
+    pub(crate) fn display<'a>(&'a self, package_store: &'a HashArena<P>) -> impl Display + 'a {
+        DisplayIncompatibility {
+            incompatibility: self,
+            package_store,
+        }
+    }
+}
+
+struct DisplayIncompatibility<'a, P: Package, VS: VersionSet, M: Eq + Clone + Debug + Display> {
+    incompatibility: &'a Incompatibility<Id<P>, VS, M>,
+    package_store: &'a HashArena<P>,
+}
+
+impl<P: Package, VS: VersionSet, M: Eq + Clone + Debug + Display> Display
+    for DisplayIncompatibility<'_, P, VS, M>
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let terms: Map<P, Term<VS>> = self
+            .incompatibility
+            .package_terms
+            .iter()
+            .map(|(&p, t)| (self.package_store[p].clone(), t.clone()))
+            .collect();
+        write!(
+            f,
+            "{}",
+            ReportFormatter::<P, VS, M>::format_terms(&DefaultStringReportFormatter, &terms)
+        )
+    }
 }
 
 impl<'a, P: Package, VS: VersionSet + 'a, M: Eq + Clone + Debug + Display + 'a>
@@ -314,11 +381,11 @@ impl<'a, P: Package, VS: VersionSet + 'a, M: Eq + Clone + Debug + Display + 'a>
         let mut relation = Relation::Satisfied;
         for (package, incompat_term) in self.package_terms.iter() {
             match terms(package).map(|term| incompat_term.relation_with(term)) {
-                Some(term::Relation::Satisfied) => {}
-                Some(term::Relation::Contradicted) => {
+                Some(term::TermRelation::Satisfied) => {}
+                Some(term::TermRelation::Contradicted) => {
                     return Relation::Contradicted(package.clone());
                 }
-                None | Some(term::Relation::Inconclusive) => {
+                None | Some(term::TermRelation::Inconclusive) => {
                     // If a package is not present, the intersection is the same as [Term::any].
                     // According to the rules of satisfactions, the relation would be inconclusive.
                     // It could also be satisfied if the incompatibility term was also [Term::any],
@@ -336,21 +403,6 @@ impl<'a, P: Package, VS: VersionSet + 'a, M: Eq + Clone + Debug + Display + 'a>
     }
 }
 
-impl<P: Package, VS: VersionSet, M: Eq + Clone + Debug + Display> Display
-    for Incompatibility<P, VS, M>
-{
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(
-            f,
-            "{}",
-            ReportFormatter::<P, VS, M>::format_terms(
-                &DefaultStringReportFormatter,
-                &self.package_terms.as_map()
-            )
-        )
-    }
-}
-
 // TESTS #######################################################################
 
 #[cfg(test)]
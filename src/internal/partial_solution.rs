@@ -3,6 +3,7 @@
 //! A Memory acts like a structured partial solution
 //! where terms are regrouped by package in a [Map](crate::type_aliases::Map).
 
+use std::cmp::Reverse;
 use std::fmt::{Debug, Display};
 use std::hash::BuildHasherDefault;
 
@@ -10,7 +11,7 @@ use priority_queue::PriorityQueue;
 use rustc_hash::FxHasher;
 
 use super::small_vec::SmallVec;
-use crate::internal::{Arena, IncompDpId, IncompId, Incompatibility, Relation, SmallMap};
+use crate::internal::{Arena, HashArena, Id, IncompDp, IncompDpId, IncompId, Relation, SmallMap};
 use crate::{DependencyProvider, Package, SelectedDependencies, Term, VersionSet};
 
 type FnvIndexMap<K, V> = indexmap::IndexMap<K, V, BuildHasherDefault<FxHasher>>;
@@ -41,28 +42,41 @@ pub(crate) struct PartialSolution<DP: DependencyProvider> {
     ///    the last time `prioritize` has been called. The inverse is not necessarily true, some packages in the range
     ///    did not have a change. Within this range there is no sorting.
     #[allow(clippy::type_complexity)]
-    package_assignments: FnvIndexMap<DP::P, PackageAssignments<DP::P, DP::VS, DP::M>>,
+    package_assignments: FnvIndexMap<Id<DP::P>, PackageAssignments<Id<DP::P>, DP::VS, DP::M>>,
     /// `prioritized_potential_packages` is primarily a HashMap from a package with no desition and a positive assignment
     /// to its `Priority`. But, it also maintains a max heap of packages by `Priority` order.
+    /// Ties are broken by package index, the package seen first being picked first,
+    /// so that the order does not depend on hashes nor on the layout of the heap.
+    #[allow(clippy::type_complexity)]
     prioritized_potential_packages:
-        PriorityQueue<DP::P, DP::Priority, BuildHasherDefault<FxHasher>>,
+        PriorityQueue<Id<DP::P>, (DP::Priority, Reverse<usize>), BuildHasherDefault<FxHasher>>,
     changed_this_decision_level: usize,
     has_ever_backtracked: bool,
+    /// Number of packages with a positive derivation but no decision yet,
+    /// kept up to date to not go through all packages to count them.
+    undecided_count: usize,
+}
+
+/// Display of a [PartialSolution] with the packages from the package store.
+pub(crate) struct DisplayPartialSolution<'a, DP: DependencyProvider> {
+    partial_solution: &'a PartialSolution<DP>,
+    package_store: &'a HashArena<DP::P>,
 }
 
-impl<DP: DependencyProvider> Display for PartialSolution<DP> {
+impl<DP: DependencyProvider> Display for DisplayPartialSolution<'_, DP> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let mut assignments: Vec<_> = self
+            .partial_solution
             .package_assignments
             .iter()
-            .map(|(p, pa)| format!("{}: {}", p, pa))
+            .map(|(p, pa)| format!("{}: {}", self.package_store[*p], pa))
             .collect();
         assignments.sort();
         write!(
             f,
             "next_global_index: {}\ncurrent_decision_level: {:?}\npackage_assignments:\n{}",
-            self.next_global_index,
-            self.current_decision_level,
+            self.partial_solution.next_global_index,
+            self.partial_solution.current_decision_level,
             assignments.join("\t\n")
         )
     }
@@ -154,11 +168,23 @@ impl<DP: DependencyProvider> PartialSolution<DP> {
             prioritized_potential_packages: PriorityQueue::default(),
             changed_this_decision_level: 0,
             has_ever_backtracked: false,
+            undecided_count: 0,
+        }
+    }
+
+    /// Display the partial solution with the packages from the `package_store`.
+    pub(crate) fn display<'a>(
+        &'a self,
+        package_store: &'a HashArena<DP::P>,
+    ) -> DisplayPartialSolution<'a, DP> {
+        DisplayPartialSolution {
+            partial_solution: self,
+            package_store,
         }
     }
 
     /// Add a decision.
-    pub(crate) fn add_decision(&mut self, package: DP::P, version: DP::V) {
+    pub(crate) fn add_decision(&mut self, package: Id<DP::P>, version: DP::V) {
         // Check that add_decision is never used in the wrong context.
         if cfg!(debug_assertions) {
             match self.package_assignments.get_mut(&package) {
@@ -190,6 +216,10 @@ impl<DP: DependencyProvider> PartialSolution<DP> {
             .get_full_mut(&package)
             .expect("Derivations must already exist");
         pa.highest_decision_level = self.current_decision_level;
+        if matches!(&pa.assignments_intersection, AssignmentsIntersection::Derivations(t) if t.is_positive())
+        {
+            self.undecided_count -= 1;
+        }
         pa.assignments_intersection = AssignmentsIntersection::Decision((
             self.next_global_index,
             version.clone(),
@@ -205,9 +235,9 @@ impl<DP: DependencyProvider> PartialSolution<DP> {
     /// Add a derivation.
     pub(crate) fn add_derivation(
         &mut self,
-        package: DP::P,
+        package: Id<DP::P>,
         cause: IncompDpId<DP>,
-        store: &Arena<Incompatibility<DP::P, DP::VS, DP::M>>,
+        store: &Arena<IncompDp<DP>>,
     ) {
         use indexmap::map::Entry;
         let mut dated_derivation = DatedDerivation {
@@ -229,9 +259,13 @@ impl<DP: DependencyProvider> PartialSolution<DP> {
                         panic!("add_derivation should not be called after a decision")
                     }
                     AssignmentsIntersection::Derivations(t) => {
+                        let was_positive = t.is_positive();
                         *t = t.intersection(&dated_derivation.accumulated_intersection);
                         dated_derivation.accumulated_intersection = t.clone();
                         if t.is_positive() {
+                            if !was_positive {
+                                self.undecided_count += 1;
+                            }
                             // we can use `swap_indices` to make `changed_this_decision_level` only go down by 1
                             // but the copying is slower then the larger search
                             self.changed_this_decision_level =
@@ -244,6 +278,7 @@ impl<DP: DependencyProvider> PartialSolution<DP> {
             Entry::Vacant(v) => {
                 let term = dated_derivation.accumulated_intersection.clone();
                 if term.is_positive() {
+                    self.undecided_count += 1;
                     self.changed_this_decision_level =
                         std::cmp::min(self.changed_this_decision_level, pa_last_index);
                 }
@@ -259,8 +294,8 @@ impl<DP: DependencyProvider> PartialSolution<DP> {
 
     pub(crate) fn pick_highest_priority_pkg(
         &mut self,
-        prioritizer: impl Fn(&DP::P, &DP::VS) -> DP::Priority,
-    ) -> Option<DP::P> {
+        prioritizer: impl Fn(Id<DP::P>, &DP::VS) -> DP::Priority,
+    ) -> Option<Id<DP::P>> {
         let check_all = self.changed_this_decision_level
             == self.current_decision_level.0.saturating_sub(1) as usize;
         let current_decision_level = self.current_decision_level;
@@ -278,8 +313,8 @@ impl<DP: DependencyProvider> PartialSolution<DP> {
             })
             .filter_map(|(p, pa)| pa.assignments_intersection.potential_package_filter(p))
             .for_each(|(p, r)| {
-                let priority = prioritizer(p, r);
-                prioritized_potential_packages.push(p.clone(), priority);
+                let priority = prioritizer(*p, r);
+                prioritized_potential_packages.push(*p, (priority, Reverse(p.into_raw())));
             });
         self.changed_this_decision_level = self.package_assignments.len();
         prioritized_potential_packages.pop().map(|(p, _)| p)
@@ -288,17 +323,36 @@ impl<DP: DependencyProvider> PartialSolution<DP> {
     /// If a partial solution has, for every positive derivation,
     /// a corresponding decision that satisfies that assignment,
     /// it's a total solution and version solving has succeeded.
-    pub(crate) fn extract_solution(&self) -> SelectedDependencies<DP> {
+    pub(crate) fn extract_solution(
+        &self,
+        package_store: &HashArena<DP::P>,
+    ) -> SelectedDependencies<DP> {
+        self.decided_packages()
+            .map(|(p, v)| (package_store[p].clone(), v.clone()))
+            .collect()
+    }
+
+    /// Packages with a decision, in the order they were decided.
+    pub(crate) fn decided_packages(&self) -> impl Iterator<Item = (Id<DP::P>, &DP::V)> {
         self.package_assignments
             .iter()
             .take(self.current_decision_level.0 as usize)
             .map(|(p, pa)| match &pa.assignments_intersection {
-                AssignmentsIntersection::Decision((_, v, _)) => (p.clone(), v.clone()),
+                AssignmentsIntersection::Decision((_, v, _)) => (*p, v),
                 AssignmentsIntersection::Derivations(_) => {
                     panic!("Derivations in the Decision part")
                 }
             })
-            .collect()
+    }
+
+    /// Packages with a positive derivation but no decision yet.
+    pub(crate) fn undecided_packages(&self) -> impl Iterator<Item = Id<DP::P>> + '_ {
+        self.package_assignments
+            .get_range(self.current_decision_level.0 as usize..)
+            .unwrap()
+            .iter()
+            .filter_map(|(p, pa)| pa.assignments_intersection.potential_package_filter(p))
+            .map(|(&p, _)| p)
     }
 
     /// Backtrack the partial solution to a given decision level.
@@ -337,6 +391,8 @@ impl<DP: DependencyProvider> PartialSolution<DP> {
                 true
             }
         });
+        // Backtracking already goes through all packages, count the undecided ones again.
+        self.undecided_count = self.undecided_packages().count();
         // Throw away all stored priority levels, And mark that they all need to be recomputed.
         self.prioritized_potential_packages.clear();
         self.changed_this_decision_level = self.current_decision_level.0.saturating_sub(1) as usize;
@@ -350,21 +406,26 @@ impl<DP: DependencyProvider> PartialSolution<DP> {
     /// is already in the partial solution with an incompatible version.
     pub(crate) fn add_version(
         &mut self,
-        package: DP::P,
+        package: Id<DP::P>,
         version: DP::V,
-        new_incompatibilities: std::ops::Range<IncompId<DP::P, DP::VS, DP::M>>,
-        store: &Arena<Incompatibility<DP::P, DP::VS, DP::M>>,
+        new_incompatibilities: std::ops::Range<IncompDpId<DP>>,
+        store: &Arena<IncompDp<DP>>,
+        package_store: &HashArena<DP::P>,
     ) {
         if !self.has_ever_backtracked {
             // Nothing has yet gone wrong during this resolution. This call is unlikely to be the first problem.
             // So let's live with a little bit of risk and add the decision without checking the dependencies.
             // The worst that can happen is we will have to do a full backtrack which only removes this one decision.
-            log::info!("add_decision: {package} @ {version} without checking dependencies");
+            log::info!(
+                "add_decision: {} @ {} without checking dependencies",
+                package_store[package],
+                version
+            );
             self.add_decision(package, version);
         } else {
             // Check if any of the new dependencies preclude deciding on this crate version.
             let exact = Term::exact(version.clone());
-            let not_satisfied = |incompat: &Incompatibility<DP::P, DP::VS, DP::M>| {
+            let not_satisfied = |incompat: &IncompDp<DP>| {
                 incompat.relation(|p| {
                     if p == &package {
                         Some(&exact)
@@ -377,12 +438,12 @@ impl<DP: DependencyProvider> PartialSolution<DP> {
             // Check none of the dependencies (new_incompatibilities)
             // would create a conflict (be satisfied).
             if store[new_incompatibilities].iter().all(not_satisfied) {
-                log::info!("add_decision: {} @ {}", package, version);
+                log::info!("add_decision: {} @ {}", package_store[package], version);
                 self.add_decision(package, version);
             } else {
                 log::info!(
                     "not adding {} @ {} because of its dependencies",
-                    package,
+                    package_store[package],
                     version
                 );
             }
@@ -390,15 +451,15 @@ impl<DP: DependencyProvider> PartialSolution<DP> {
     }
 
     /// Check if the terms in the partial solution satisfy the incompatibility.
-    pub(crate) fn relation(
-        &self,
-        incompat: &Incompatibility<DP::P, DP::VS, DP::M>,
-    ) -> Relation<DP::P> {
+    pub(crate) fn relation(&self, incompat: &IncompDp<DP>) -> Relation<Id<DP::P>> {
         incompat.relation(|package| self.term_intersection_for_package(package))
     }
 
     /// Retrieve intersection of terms related to package.
-    pub(crate) fn term_intersection_for_package(&self, package: &DP::P) -> Option<&Term<DP::VS>> {
+    pub(crate) fn term_intersection_for_package(
+        &self,
+        package: &Id<DP::P>,
+    ) -> Option<&Term<DP::VS>> {
         self.package_assignments
             .get(package)
             .map(|pa| pa.assignments_intersection.term())
@@ -408,9 +469,9 @@ impl<DP: DependencyProvider> PartialSolution<DP> {
     #[allow(clippy::type_complexity)]
     pub(crate) fn satisfier_search<'i>(
         &self,
-        incompat: &'i Incompatibility<DP::P, DP::VS, DP::M>,
-        store: &Arena<Incompatibility<DP::P, DP::VS, DP::M>>,
-    ) -> (&'i DP::P, SatisfierSearch<DP::P, DP::VS, DP::M>) {
+        incompat: &'i IncompDp<DP>,
+        store: &Arena<IncompDp<DP>>,
+    ) -> (&'i Id<DP::P>, SatisfierSearch<Id<DP::P>, DP::VS, DP::M>) {
         let satisfied_map = Self::find_satisfier(incompat, &self.package_assignments);
         let (&satisfier_package, &(satisfier_cause, _, satisfier_decision_level)) = satisfied_map
             .iter()
@@ -446,9 +507,9 @@ impl<DP: DependencyProvider> PartialSolution<DP> {
     /// to return a coherent previous_satisfier_level.
     #[allow(clippy::type_complexity)]
     fn find_satisfier<'i>(
-        incompat: &'i Incompatibility<DP::P, DP::VS, DP::M>,
-        package_assignments: &FnvIndexMap<DP::P, PackageAssignments<DP::P, DP::VS, DP::M>>,
-    ) -> SatisfiedMap<'i, DP::P, DP::VS, DP::M> {
+        incompat: &'i IncompDp<DP>,
+        package_assignments: &FnvIndexMap<Id<DP::P>, PackageAssignments<Id<DP::P>, DP::VS, DP::M>>,
+    ) -> SatisfiedMap<'i, Id<DP::P>, DP::VS, DP::M> {
         let mut satisfied = SmallMap::Empty;
         for (package, incompat_term) in incompat.iter() {
             let pa = package_assignments.get(package).expect("Must exist");
@@ -462,11 +523,11 @@ impl<DP: DependencyProvider> PartialSolution<DP> {
     /// and including that assignment plus satisfier.
     #[allow(clippy::type_complexity)]
     fn find_previous_satisfier<'i>(
-        incompat: &Incompatibility<DP::P, DP::VS, DP::M>,
-        satisfier_package: &'i DP::P,
-        mut satisfied_map: SatisfiedMap<'i, DP::P, DP::VS, DP::M>,
-        package_assignments: &FnvIndexMap<DP::P, PackageAssignments<DP::P, DP::VS, DP::M>>,
-        store: &Arena<Incompatibility<DP::P, DP::VS, DP::M>>,
+        incompat: &IncompDp<DP>,
+        satisfier_package: &'i Id<DP::P>,
+        mut satisfied_map: SatisfiedMap<'i, Id<DP::P>, DP::VS, DP::M>,
+        package_assignments: &FnvIndexMap<Id<DP::P>, PackageAssignments<Id<DP::P>, DP::VS, DP::M>>,
+        store: &Arena<IncompDp<DP>>,
     ) -> DecisionLevel {
         // First, let's retrieve the previous derivations and the initial accum_term.
         let satisfier_pa = package_assignments.get(satisfier_package).unwrap();
@@ -501,6 +562,18 @@ impl<DP: DependencyProvider> PartialSolution<DP> {
         decision_level.max(DecisionLevel(1))
     }
 
+    /// Number of packages with a positive derivation but no decision yet,
+    /// the length of [undecided_packages](Self::undecided_packages).
+    pub(crate) fn undecided_count(&self) -> usize {
+        debug_assert_eq!(self.undecided_count, self.undecided_packages().count());
+        self.undecided_count
+    }
+
+    /// Number of packages with a decision.
+    pub(crate) fn decided_count(&self) -> usize {
+        self.current_decision_level.0 as usize
+    }
+
     pub(crate) fn current_decision_level(&self) -> DecisionLevel {
         self.current_decision_level
     }
@@ -2,8 +2,9 @@ use std::hash::Hash;
 
 use crate::Map;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub(crate) enum SmallMap<K, V> {
+    #[default]
     Empty,
     One([(K, V); 1]),
     Two([(K, V); 2]),
@@ -173,12 +174,6 @@ impl<K: Clone + PartialEq + Eq + Hash, V: Clone> SmallMap<K, V> {
     }
 }
 
-impl<K, V> Default for SmallMap<K, V> {
-    fn default() -> Self {
-        Self::Empty
-    }
-}
-
 impl<K, V> SmallMap<K, V> {
     pub(crate) fn len(&self) -> usize {
         match self {
@@ -190,6 +185,7 @@ impl<K, V> SmallMap<K, V> {
     }
 }
 
+#[cfg(test)]
 impl<K: Eq + Hash + Clone, V: Clone> SmallMap<K, V> {
     pub(crate) fn as_map(&self) -> Map<K, V> {
         match self {
@@ -2,8 +2,9 @@ use std::fmt;
 use std::hash::{Hash, Hasher};
 use std::ops::Deref;
 
-#[derive(Clone)]
+#[derive(Clone, Default)]
 pub enum SmallVec<T> {
+    #[default]
     Empty,
     One([T; 1]),
     Two([T; 2]),
@@ -80,12 +81,6 @@ impl<T> SmallVec<T> {
     }
 }
 
-impl<T> Default for SmallVec<T> {
-    fn default() -> Self {
-        Self::Empty
-    }
-}
-
 impl<T> Deref for SmallVec<T> {
     type Target = [T];
 
@@ -23,7 +23,8 @@
 //! Our Version trait requires
 //! [Clone] + [Ord] + [Debug] + [Display](std::fmt::Display).
 //! For convenience, this library provides [SemanticVersion]
-//! that implements semantic versioning rules.
+//! that implements semantic versioning rules,
+//! and [CalendarVersion] for date based versions.
 //!
 //! # Basic example
 //!
@@ -123,8 +124,8 @@
 //! In a real scenario, these two methods may involve reading the file system
 //! or doing network request, so you may want to hold a cache in your
 //! [DependencyProvider] implementation.
-//! How exactly this could be achieved is shown in `CachingDependencyProvider`
-//! (see `examples/caching_dependency_provider.rs`).
+//! Wrapping it in a [CachingDependencyProvider] memoizes its answers,
+//! and `examples/caching_dependency_provider.rs` shows how such a cache can be written by hand.
 //! You could also use the [OfflineDependencyProvider]
 //! type defined by the crate as guidance,
 //! but you are free to use whatever approach makes sense in your situation.
@@ -169,8 +170,9 @@
 //! Implementing a [Reporter] may involve a lot of heuristics
 //! to make the output human-readable and natural.
 //! For convenience, we provide a default implementation
-//! [DefaultStringReporter] that outputs the report as a [String].
-//! You may use it as follows:
+//! [DefaultStringReporter] that outputs the report as a [String],
+//! as well as [IndentedTreeReporter] that outputs it as an indented outline.
+//! You may use them as follows:
 //! ```
 //! # use pubgrub::{resolve, OfflineDependencyProvider, DefaultStringReporter, Reporter, PubGrubError, Range};
 //! #
@@ -210,27 +212,61 @@
 
 #![warn(missing_docs)]
 
+mod cache;
+mod discrete;
+mod disjoint_union;
 mod error;
+mod feature;
+mod filter;
 mod package;
 mod range;
+mod replay;
 mod report;
 mod solver;
+#[cfg(feature = "async")]
+mod solver_async;
 mod term;
 mod type_aliases;
 mod version;
 mod version_set;
 
+pub use cache::CachingDependencyProvider;
+pub use discrete::DiscreteVersionSet;
+pub use disjoint_union::{DisjointUnionVersionSet, Either};
 pub use error::{NoSolutionError, PubGrubError};
+pub use feature::{Feature, FeatureDependencies, FeatureDependencyProvider};
+pub use filter::FilteredDependencyProvider;
 pub use package::Package;
-pub use range::Range;
+pub use range::{CheckedAdd, Range, RangeParseError};
+pub use replay::{
+    RecordingDependencyProvider, ReplayDependencyProvider, Trace, TraceOf, UnrecordedQuery,
+};
 pub use report::{
-    DefaultStringReportFormatter, DefaultStringReporter, DerivationTree, Derived, External,
-    ReportFormatter, Reporter,
+    to_dot, AbbreviatingReportFormatter, DefaultStringReportFormatter, DefaultStringReporter,
+    DerivationTree, Derived, External, IndentedTreeReporter, ReportFormatter, ReportPhrases,
+    Reporter, SegmentedVersionSet, Suggestion, SuggestionReporter,
+};
+pub use solver::{
+    resolve, resolve_all_conflicts, resolve_bounded, resolve_constraints, resolve_deterministic,
+    resolve_latest, resolve_multi, resolve_with_constraints, resolve_with_deprioritized,
+    resolve_with_history, resolve_with_inspector, resolve_with_limits, resolve_with_preferences,
+    resolve_with_stats, Dependencies, DependencyProvider, MergeConflict, OfflineDependencyProvider,
+    PartialSolutionSummary, PriorityStrategy, ProgressInfo, ResolutionState, ResolveEvent,
+    ResolveLimits, ResolveOutcome, ResolveStats, Resolver, VersionPreference,
+};
+#[cfg(feature = "async")]
+pub use solver_async::{
+    resolve_async, AsyncDependencyProvider, AsyncPubGrubError, AsyncSelectedDependencies,
+    BlockingDependencyProvider,
+};
+pub use term::{Term, TermRelation};
+pub use type_aliases::{
+    DependencyConstraints, Map, SelectedDependencies, SelectedDependenciesExt, Set,
+};
+pub use version::{
+    CalVerParseError, CalendarVersion, GenericVersion, GenericVersionParseError, SemanticVersion,
+    VersionParseError,
 };
-pub use solver::{resolve, Dependencies, DependencyProvider, OfflineDependencyProvider};
-pub use term::Term;
-pub use type_aliases::{DependencyConstraints, Map, SelectedDependencies, Set};
-pub use version::{SemanticVersion, VersionParseError};
 pub use version_set::VersionSet;
 
 mod internal;
@@ -35,11 +35,14 @@
 //! First, we can have both segments `S1 = (Unbounded, Included(42u32))` and `S2 = (Included(0), Included(42u32))`
 //! that represent the same segment but are structurally different.
 //! Thus, a derived equality check would answer `false` to `S1 == S2` while it's true.
+//! When the minimal and maximal versions are known, [Range::semantically_eq] compares
+//! ranges with their `Unbounded` values replaced by these versions.
 //!
 //! Second both segments `S1 = (Included(1), Included(5))` and `S2 = (Included(1), Included(3)) + (Included(4), Included(5))` are equal.
 //! But without asking the user to provide a `bump` function for discrete sets,
 //! the algorithm is not able to tell that the space between the right `Included(3)` bound and the left `Included(4)` bound is empty.
 //! Thus the algorithm is not able to reduce S2 to its canonical S1 form while computing sets operations like intersections in the generic code.
+//! When the previous and next versions are known, [Range::canonical] performs this reduction.
 //!
 //! This is likely to lead to user facing theoretically correct but practically nonsensical ranges,
 //! like (Unbounded, Excluded(0)) or (Excluded(6), Excluded(7)).
@@ -55,9 +58,17 @@ use std::cmp::Ordering;
 use std::fmt::{Debug, Display, Formatter};
 use std::ops::Bound::{self, Excluded, Included, Unbounded};
 use std::ops::RangeBounds;
+use std::str::FromStr;
+
+use thiserror::Error;
 
 use crate::internal::SmallVec;
-use crate::VersionSet;
+use crate::{SegmentedVersionSet, VersionSet};
+
+/// Minimum ratio between the segment counts of two ranges
+/// for their [intersection](Range::intersection) to binary search
+/// the segments of the larger one instead of walking all of them.
+const SPARSE_INTERSECTION_RATIO: usize = 16;
 
 /// A Range represents multiple intervals of a continuous range of monotone increasing
 /// values.
@@ -72,19 +83,31 @@ type Interval<V> = (Bound<V>, Bound<V>);
 
 impl<V> Range<V> {
     /// Empty set of versions.
+    #[doc(alias = "none")]
     pub fn empty() -> Self {
         Self {
             segments: SmallVec::empty(),
         }
     }
 
+    /// Empty set of versions, the former name of [Range::empty].
+    pub fn none() -> Self {
+        Self::empty()
+    }
+
     /// Set of all possible versions
+    #[doc(alias = "any")]
     pub fn full() -> Self {
         Self {
             segments: SmallVec::one((Unbounded, Unbounded)),
         }
     }
 
+    /// Set of all possible versions, the former name of [Range::full].
+    pub fn any() -> Self {
+        Self::full()
+    }
+
     /// Set of all versions higher or equal to some version
     pub fn higher_than(v: impl Into<V>) -> Self {
         Self {
@@ -113,6 +136,16 @@ impl<V> Range<V> {
         }
     }
 
+    /// Set of all versions lower or equal to some version, same as [Range::lower_than].
+    pub fn at_most(v: impl Into<V>) -> Self {
+        Self::lower_than(v)
+    }
+
+    /// Set of all versions higher or equal to some version, same as [Range::higher_than].
+    pub fn at_least(v: impl Into<V>) -> Self {
+        Self::higher_than(v)
+    }
+
     /// Set of versions greater or equal to `v1` but less than `v2`.
     pub fn between(v1: impl Into<V>, v2: impl Into<V>) -> Self {
         Self {
@@ -124,10 +157,36 @@ impl<V> Range<V> {
     pub fn is_empty(&self) -> bool {
         self.segments.is_empty()
     }
+
+    /// The number of disjoint segments of the set.
+    pub fn segment_count(&self) -> usize {
+        self.segments.len()
+    }
+
+    /// Whether the set contains all the versions lower than some version.
+    pub fn has_lower_unbounded(&self) -> bool {
+        self.segments
+            .first()
+            .is_some_and(|(start, _)| matches!(start, Unbounded))
+    }
+
+    /// Whether the set contains all the versions higher than some version.
+    pub fn has_upper_unbounded(&self) -> bool {
+        self.segments
+            .last()
+            .is_some_and(|(_, end)| matches!(end, Unbounded))
+    }
+
+    /// Whether the set has a lowest and a highest bound.
+    /// The empty set is bounded.
+    pub fn is_bounded(&self) -> bool {
+        !self.has_lower_unbounded() && !self.has_upper_unbounded()
+    }
 }
 
 impl<V: Clone> Range<V> {
     /// Set containing exactly one version
+    #[doc(alias = "exact")]
     pub fn singleton(v: impl Into<V>) -> Self {
         let v = v.into();
         Self {
@@ -135,7 +194,24 @@ impl<V: Clone> Range<V> {
         }
     }
 
+    /// Set containing exactly one version, the former name of [Range::singleton].
+    ///
+    /// ```
+    /// # use pubgrub::Range;
+    /// assert_eq!(Range::<u32>::exact(3u32), Range::singleton(3u32));
+    /// ```
+    pub fn exact(v: impl Into<V>) -> Self {
+        Self::singleton(v)
+    }
+
     /// Returns the complement of this Range.
+    ///
+    /// Each bound is flipped between included and excluded, without any other normalization,
+    /// so `range.complement().complement()` is structurally equal to `range`,
+    /// whatever its leading and trailing bounds.
+    ///
+    /// To only iterate over the segments of the complement,
+    /// [complement_ref](Range::complement_ref) avoids cloning the bounds.
     pub fn complement(&self) -> Self {
         match self.segments.first() {
             // Complement of ∅ is ∞
@@ -161,6 +237,47 @@ impl<V: Clone> Range<V> {
         }
     }
 
+    /// Iterate over the segments of the complement of this Range, in increasing order,
+    /// borrowing their bounds from `self`.
+    ///
+    /// The segments are the same as `self.complement().iter()`, without building the complement.
+    ///
+    /// ```
+    /// # use std::ops::Bound::{Excluded, Included, Unbounded};
+    /// # use pubgrub::Range;
+    /// let range = Range::<u32>::between(1u32, 3u32);
+    /// assert_eq!(
+    ///     range.complement_ref().collect::<Vec<_>>(),
+    ///     vec![(Unbounded, Excluded(&1)), (Included(&3), Unbounded)]
+    /// );
+    /// ```
+    pub fn complement_ref(&self) -> impl Iterator<Item = (Bound<&V>, Bound<&V>)> {
+        fn flip<V>(bound: Bound<&V>) -> Bound<&V> {
+            match bound {
+                Included(v) => Excluded(v),
+                Excluded(v) => Included(v),
+                Unbounded => Unbounded,
+            }
+        }
+        // The gap before each segment, and the gap after the last one.
+        let segments = &self.segments;
+        (0..=segments.len()).filter_map(move |idx| {
+            let start = match idx.checked_sub(1).map(|prev| segments[prev].1.as_ref()) {
+                None => Unbounded,
+                // No gap after a segment going to +∞.
+                Some(Unbounded) => return None,
+                Some(end) => flip(end),
+            };
+            let end = match segments.get(idx).map(|(start, _)| start.as_ref()) {
+                None => Unbounded,
+                // No gap before a segment coming from -∞.
+                Some(Unbounded) => return None,
+                Some(start) => flip(start),
+            };
+            Some((start, end))
+        })
+    }
+
     /// Helper function performing the negation of intervals in segments.
     fn negate_segments(start: Bound<V>, segments: &[Interval<V>]) -> Self {
         let mut complement_segments: SmallVec<Interval<V>> = SmallVec::empty();
@@ -221,16 +338,102 @@ impl<V: Ord> Range<V> {
         })
     }
 
+    /// Returns true if both ranges contain the same versions,
+    /// given the minimal and maximal versions that exist, if any.
+    ///
+    /// Unlike `==`, this considers the `Unbounded` edges equal to the included `min` and `max`
+    /// versions, so that `<=42` and `0 <= v <= 42` are equal for `u32` versions.
+    /// Adjacent bounds of discrete versions, like `<=3` and `4 <=`, are not merged.
+    ///
+    /// ```
+    /// # use pubgrub::Range;
+    /// let r1 = Range::<u32>::lower_than(42u32);
+    /// let r2 = Range::<u32>::from_range_bounds(0u32..=42);
+    /// assert_ne!(r1, r2);
+    /// assert!(r1.semantically_eq(&r2, Some(&0), None));
+    /// ```
+    pub fn semantically_eq(&self, other: &Self, min: Option<&V>, max: Option<&V>) -> bool {
+        // Only the first segment can start, and the last segment end, with `Unbounded`.
+        fn normalize<'a, V>(bound: Bound<&'a V>, edge: Option<&'a V>) -> Bound<&'a V> {
+            match (bound, edge) {
+                (Unbounded, Some(edge)) => Included(edge),
+                (bound, _) => bound,
+            }
+        }
+        self.segments.len() == other.segments.len()
+            && self.segments.iter().zip(other.segments.iter()).all(
+                |((start1, end1), (start2, end2))| {
+                    normalize(start1.as_ref(), min) == normalize(start2.as_ref(), min)
+                        && normalize(end1.as_ref(), max) == normalize(end2.as_ref(), max)
+                },
+            )
+    }
+
     /// Returns true if this Range contains the specified value.
     pub fn contains(&self, version: &V) -> bool {
-        self.segments
-            .binary_search_by(|segment| {
-                // We have to reverse because we need the segment wrt to the version, while
-                // within bounds tells us the version wrt to the segment.
-                within_bounds(version, segment).reverse()
-            })
-            // An equal interval is one that contains the version
-            .is_ok()
+        // An equal interval is one that contains the version
+        self.segment_containing(version).is_ok()
+    }
+
+    /// Binary search of the segment containing a version, as in [iter](Self::iter) order.
+    ///
+    /// Like [slice::binary_search], returns `Ok(i)` if the version is in segment `i`,
+    /// or `Err(i)` if it is in none of them,
+    /// where `i` is the index of the first segment above the version.
+    pub fn segment_containing(&self, version: &V) -> Result<usize, usize> {
+        self.segments.binary_search_by(|segment| {
+            // We have to reverse because we need the segment wrt to the version, while
+            // within bounds tells us the version wrt to the segment.
+            within_bounds(version, segment).reverse()
+        })
+    }
+
+    /// Returns a version of this range close to `version`, for "did you mean" suggestions.
+    ///
+    /// Without a distance between versions, this is an included edge of the segments
+    /// around `version`: the edges next to the gap holding `version` first,
+    /// below before above, then the far edges of these segments.
+    /// If `version` is in the range, this is an included edge of its own segment,
+    /// its start before its end.
+    /// Returns [None] if these segments have no included edge.
+    ///
+    /// ```
+    /// # use pubgrub::Range;
+    /// let range = Range::<u32>::from_range_bounds(1u32..=3).union(&Range::higher_than(7u32));
+    /// assert_eq!(range.nearest_contained(&5), Some(&3));
+    /// assert_eq!(range.nearest_contained(&0), Some(&1));
+    /// assert_eq!(range.nearest_contained(&8), Some(&7));
+    /// ```
+    pub fn nearest_contained(&self, version: &V) -> Option<&V> {
+        fn included<V>(bound: Bound<&V>) -> Option<&V> {
+            match bound {
+                Included(v) => Some(v),
+                Excluded(_) | Unbounded => None,
+            }
+        }
+        match self.segment_containing(version) {
+            Ok(i) => {
+                let (start, end) = &self.segments[i];
+                included(start.as_ref()).or(included(end.as_ref()))
+            }
+            Err(i) => {
+                let (below_start, below_end) = match i.checked_sub(1) {
+                    Some(below) => (
+                        self.segments[below].0.as_ref(),
+                        self.segments[below].1.as_ref(),
+                    ),
+                    None => (Unbounded, Unbounded),
+                };
+                let (above_start, above_end) = match self.segments.get(i) {
+                    Some((start, end)) => (start.as_ref(), end.as_ref()),
+                    None => (Unbounded, Unbounded),
+                };
+                included(below_end)
+                    .or(included(above_start))
+                    .or(included(below_start))
+                    .or(included(above_end))
+            }
+        }
     }
 
     /// Returns true if this Range contains the specified values.
@@ -270,6 +473,59 @@ impl<V: Ord> Range<V> {
         })
     }
 
+    /// Returns true if this Range contains any of the specified values.
+    ///
+    /// The `versions` iterator must be sorted.
+    /// Like [contains_many](Self::contains_many), in a single pass,
+    /// but it stops at the first version that is contained.
+    pub fn any_contained<I, BV>(&self, versions: I) -> bool
+    where
+        I: Iterator<Item = BV>,
+        BV: Borrow<V>,
+    {
+        self.contains_many(versions).any(|contained| contained)
+    }
+
+    /// Returns true if this Range contains all of the specified values.
+    ///
+    /// The `versions` iterator must be sorted.
+    /// Like [contains_many](Self::contains_many), in a single pass,
+    /// but it stops at the first version that is not contained.
+    pub fn all_contained<I, BV>(&self, versions: I) -> bool
+    where
+        I: Iterator<Item = BV>,
+        BV: Borrow<V>,
+    {
+        self.contains_many(versions).all(|contained| contained)
+    }
+
+    /// Returns the number of the specified values that this Range contains.
+    ///
+    /// The `versions` slice must be sorted.
+    /// Like [contains_many](Self::contains_many), in a single pass,
+    /// in `O(size_of_range + len_of_versions)`.
+    pub fn count_contained(&self, versions: &[V]) -> usize {
+        self.contains_many(versions.iter())
+            .filter(|&contained| contained)
+            .count()
+    }
+
+    /// Returns whether this Range contains each of the specified values, in the same order.
+    ///
+    /// Unlike [contains_many](Self::contains_many), the `versions` do not need to be sorted:
+    /// their indices are sorted instead, before the same single pass,
+    /// in `O(size_of_range + len_of_versions * log(len_of_versions))`.
+    pub fn contains_unsorted(&self, versions: &[V]) -> Vec<bool> {
+        let mut indices: Vec<usize> = (0..versions.len()).collect();
+        indices.sort_unstable_by(|&a, &b| versions[a].cmp(&versions[b]));
+        let mut contained = vec![false; versions.len()];
+        let sorted = indices.iter().map(|&i| &versions[i]);
+        for (&i, is_contained) in indices.iter().zip(self.contains_many(sorted)) {
+            contained[i] = is_contained;
+        }
+        contained
+    }
+
     /// Construct a simple range from anything that impls [RangeBounds] like `v1..v2`.
     pub fn from_range_bounds<R, IV>(bounds: R) -> Self
     where
@@ -544,6 +800,65 @@ fn left_end_is_smaller<V: PartialOrd>(left: Bound<V>, right: Bound<V>) -> bool {
     }
 }
 
+/// The start of the intersection of two segments.
+fn larger_start<V: PartialOrd>(left: Bound<V>, right: Bound<V>) -> Bound<V> {
+    match (left, right) {
+        (Included(l), Included(r)) => Included(if l < r { r } else { l }),
+        (Excluded(l), Excluded(r)) => Excluded(if l < r { r } else { l }),
+
+        (Included(i), Excluded(e)) | (Excluded(e), Included(i)) => {
+            if i <= e {
+                Excluded(e)
+            } else {
+                Included(i)
+            }
+        }
+        (s, Unbounded) | (Unbounded, s) => s,
+    }
+}
+
+/// Versions that can be shifted by a constant, see [Range::offset_by].
+///
+/// Adding the same value to two versions must keep their order.
+pub trait CheckedAdd: Sized {
+    /// Returns `self + delta`, or [None] if it overflows.
+    fn checked_add(&self, delta: &Self) -> Option<Self>;
+}
+
+macro_rules! impl_checked_add {
+    ($($t:ty),*) => {
+        $(
+            impl CheckedAdd for $t {
+                fn checked_add(&self, delta: &Self) -> Option<Self> {
+                    <$t>::checked_add(*self, *delta)
+                }
+            }
+        )*
+    };
+}
+
+impl_checked_add!(u8, u16, u32, u64, u128, usize, i8, i16, i32, i64, i128, isize);
+
+/// A segment of either operand, in the owned set operations.
+#[derive(Clone, Copy)]
+enum Side {
+    Left(usize),
+    Right(usize),
+}
+
+impl Side {
+    fn bounds<'a, V>(
+        self,
+        left: &'a [Interval<V>],
+        right: &'a [Interval<V>],
+    ) -> (Bound<&'a V>, Bound<&'a V>) {
+        match self {
+            Self::Left(i) => (left[i].0.as_ref(), left[i].1.as_ref()),
+            Self::Right(i) => (right[i].0.as_ref(), right[i].1.as_ref()),
+        }
+    }
+}
+
 /// Group adjacent versions locations.
 ///
 /// ```text
@@ -575,16 +890,101 @@ fn group_adjacent_locations(
 }
 
 impl<V: Ord + Clone> Range<V> {
+    /// Set containing exactly the given versions, in any order and possibly repeated.
+    ///
+    /// The versions are sorted once and each one gets its own segment,
+    /// instead of the `O(n²)` union of singletons.
+    /// Versions that follow each other are kept in separate segments,
+    /// see [from_versions_merging](Self::from_versions_merging) to merge them.
+    pub fn from_versions(versions: impl IntoIterator<Item = V>) -> Self {
+        Self::from_versions_merging(versions, |_| None)
+    }
+
+    /// Same as [from_versions](Self::from_versions),
+    /// with `succ` giving the version following another, for discrete versions.
+    ///
+    /// Versions that follow each other are merged in one segment.
+    ///
+    /// ```
+    /// # use pubgrub::Range;
+    /// let range = Range::from_versions_merging([5u32, 1, 2, 3], |v| v.checked_add(1));
+    /// assert_eq!(range.segment_count(), 2);
+    /// assert_eq!(range, Range::from_range_bounds(1u32..=3).union(&Range::singleton(5u32)));
+    /// ```
+    pub fn from_versions_merging(
+        versions: impl IntoIterator<Item = V>,
+        succ: impl Fn(&V) -> Option<V>,
+    ) -> Self {
+        let mut versions: Vec<V> = versions.into_iter().collect();
+        versions.sort_unstable();
+        versions.dedup();
+        let mut segments = SmallVec::empty();
+        let mut versions = versions.into_iter();
+        let Some(mut start) = versions.next() else {
+            return Self::empty();
+        };
+        let mut end = start.clone();
+        for v in versions {
+            if succ(&end).as_ref() == Some(&v) {
+                end = v;
+            } else {
+                segments.push((Included(start), Included(end)));
+                start = v.clone();
+                end = v;
+            }
+        }
+        segments.push((Included(start), Included(end)));
+        Self { segments }.check_invariants()
+    }
+
     /// Computes the union of this `Range` and another.
     pub fn union(&self, other: &Self) -> Self {
         let mut output: SmallVec<Interval<V>> = SmallVec::empty();
-        let mut accumulator: Option<(&Bound<_>, &Bound<_>)> = None;
-        let mut left_iter = self.segments.iter().peekable();
-        let mut right_iter = other.segments.iter().peekable();
+        Self::emit_union(&self.segments, &other.segments, |start, end| {
+            output.push((start.cloned(), end.cloned()))
+        });
+        Self { segments: output }.check_invariants()
+    }
+
+    /// Same as [union](Self::union), writing the segments into `out` instead of a new range.
+    ///
+    /// `out` is cleared first, then filled with the segments as in [iter](Self::iter).
+    /// Reusing the same buffer across calls only allocates when a result is longer
+    /// than the capacity left by the previous ones.
+    ///
+    /// ```
+    /// # use pubgrub::Range;
+    /// # use std::ops::Bound::{Excluded, Included};
+    /// let mut out = Vec::new();
+    /// Range::<u32>::between(1u32, 3u32).union_into(&Range::between(2u32, 5u32), &mut out);
+    /// assert_eq!(out, vec![(Included(1), Excluded(5))]);
+    /// ```
+    pub fn union_into(&self, other: &Self, out: &mut Vec<(Bound<V>, Bound<V>)>) {
+        out.clear();
+        Self::emit_union(&self.segments, &other.segments, |start, end| {
+            out.push((start.cloned(), end.cloned()))
+        });
+    }
+
+    /// Calls `emit` with the segments of the union of two ranges, given their sorted segments.
+    fn emit_union<'a>(
+        left: &'a [Interval<V>],
+        right: &'a [Interval<V>],
+        mut emit: impl FnMut(Bound<&'a V>, Bound<&'a V>),
+    ) {
+        let mut accumulator: Option<(Bound<&V>, Bound<&V>)> = None;
+        let mut left_iter = left
+            .iter()
+            .map(|(start, end)| (start.as_ref(), end.as_ref()))
+            .peekable();
+        let mut right_iter = right
+            .iter()
+            .map(|(start, end)| (start.as_ref(), end.as_ref()))
+            .peekable();
         loop {
             let smaller_interval = match (left_iter.peek(), right_iter.peek()) {
-                (Some((left_start, left_end)), Some((right_start, right_end))) => {
-                    if left_start_is_smaller(left_start.as_ref(), right_start.as_ref()) {
+                (Some(&(left_start, left_end)), Some(&(right_start, right_end))) => {
+                    if left_start_is_smaller(left_start, right_start) {
                         left_iter.next();
                         (left_start, left_end)
                     } else {
@@ -592,11 +992,11 @@ impl<V: Ord + Clone> Range<V> {
                         (right_start, right_end)
                     }
                 }
-                (Some((left_start, left_end)), None) => {
+                (Some(&(left_start, left_end)), None) => {
                     left_iter.next();
                     (left_start, left_end)
                 }
-                (None, Some((right_start, right_end))) => {
+                (None, Some(&(right_start, right_end))) => {
                     right_iter.next();
                     (right_start, right_end)
                 }
@@ -604,12 +1004,12 @@ impl<V: Ord + Clone> Range<V> {
             };
 
             if let Some(accumulator_) = accumulator {
-                if end_before_start_with_gap(accumulator_.1, smaller_interval.0) {
-                    output.push((accumulator_.0.clone(), accumulator_.1.clone()));
+                if end_before_start_with_gap(&accumulator_.1, &smaller_interval.0) {
+                    emit(accumulator_.0, accumulator_.1);
                     accumulator = Some(smaller_interval);
                 } else {
                     let accumulator_end = match (accumulator_.1, smaller_interval.1) {
-                        (_, Unbounded) | (Unbounded, _) => &Unbounded,
+                        (_, Unbounded) | (Unbounded, _) => Unbounded,
                         (Included(l), Excluded(r) | Included(r)) if l == r => accumulator_.1,
                         (Included(l) | Excluded(l), Included(r) | Excluded(r)) => {
                             if l > r {
@@ -627,28 +1027,274 @@ impl<V: Ord + Clone> Range<V> {
         }
 
         if let Some(accumulator) = accumulator {
-            output.push((accumulator.0.clone(), accumulator.1.clone()));
+            emit(accumulator.0, accumulator.1);
         }
-
-        Self { segments: output }.check_invariants()
     }
 
     /// Computes the intersection of two sets of versions.
     pub fn intersection(&self, other: &Self) -> Self {
+        // Intersecting with a single version only needs a binary search for it.
+        if let Some(v) = self.as_singleton() {
+            return if other.contains(v) {
+                self.clone()
+            } else {
+                Self::empty()
+            };
+        }
+        if let Some(v) = other.as_singleton() {
+            return if self.contains(v) {
+                other.clone()
+            } else {
+                Self::empty()
+            };
+        }
+        let mut segments: SmallVec<Interval<V>> = SmallVec::empty();
+        self.emit_intersection(other, |start, end| {
+            segments.push((start.cloned(), end.cloned()))
+        });
+        Self { segments }.check_invariants()
+    }
+
+    /// Same as [intersection](Self::intersection),
+    /// writing the segments into `out` instead of a new range.
+    ///
+    /// `out` is cleared first, then filled with the segments as in [iter](Self::iter).
+    /// Reusing the same buffer across calls only allocates when a result is longer
+    /// than the capacity left by the previous ones.
+    ///
+    /// ```
+    /// # use pubgrub::Range;
+    /// # use std::ops::Bound::{Excluded, Included};
+    /// let mut out = Vec::new();
+    /// Range::<u32>::between(1u32, 3u32).intersection_into(&Range::between(2u32, 5u32), &mut out);
+    /// assert_eq!(out, vec![(Included(2), Excluded(3))]);
+    /// ```
+    pub fn intersection_into(&self, other: &Self, out: &mut Vec<(Bound<V>, Bound<V>)>) {
+        out.clear();
+        self.emit_intersection(other, |start, end| out.push((start.cloned(), end.cloned())));
+    }
+
+    /// Calls `emit` with the segments of the intersection of two ranges.
+    fn emit_intersection<'a>(
+        &'a self,
+        other: &'a Self,
+        emit: impl FnMut(Bound<&'a V>, Bound<&'a V>),
+    ) {
+        // Intersecting with a range of many more segments, like an enumeration of versions,
+        // only needs to look at the segments overlapping the other range.
+        let (many, few) = if self.segments.len() >= other.segments.len() {
+            (&self.segments, &other.segments)
+        } else {
+            (&other.segments, &self.segments)
+        };
+        if many.len() >= SPARSE_INTERSECTION_RATIO * few.len() {
+            Self::emit_sparse_intersection(many, few, emit)
+        } else {
+            Self::emit_intersection_segments(&self.segments, &other.segments, emit)
+        }
+    }
+
+    /// Computes the intersection of `self` with each of the `others`.
+    ///
+    /// This gives the same results as calling [intersection](Self::intersection) in a loop,
+    /// but is faster when `self` has many segments:
+    /// instead of walking all of them for every other range,
+    /// the segments outside of the bounding range of the other range are skipped
+    /// with a binary search.
+    pub fn intersection_each<'a>(
+        &'a self,
+        others: impl Iterator<Item = &'a Self> + 'a,
+    ) -> impl Iterator<Item = Self> + 'a {
+        others.map(move |other| {
+            let Some((start, end)) = other.bounding_range() else {
+                return Self::empty();
+            };
+            let segments = self.segments.as_slice();
+            // Segments ending before the start of `other`.
+            let first = segments.partition_point(|s| !valid_segment(&start, &s.1.as_ref()));
+            // Segments starting before the end of `other`.
+            let last = segments.partition_point(|s| valid_segment(&s.0.as_ref(), &end));
+            let segments = &segments[first..last.max(first)];
+            Self {
+                segments: Self::intersect_segments(segments, &other.segments),
+            }
+            .check_invariants()
+        })
+    }
+
+    /// Computes the union of two sets of versions, reusing the allocation of `self`.
+    ///
+    /// This gives the same result as [union](Self::union),
+    /// but when `self` is a temporary with its segments on the heap,
+    /// the output is written into that buffer instead of a new one.
+    pub fn into_union(self, other: &Self) -> Self {
+        let mut segments = match self.segments {
+            SmallVec::Flexible(segments) => segments,
+            segments => return Self { segments }.union(other),
+        };
+        // The output is written after the segments of `self`, which are removed at the end.
+        let len = segments.len();
+        segments.reserve(other.segments.len());
+        let (mut left, mut right) = (0, 0);
+        // Where the start and the end of the accumulated segment come from.
+        let mut accumulator: Option<(Side, Side)> = None;
+        loop {
+            let smaller = match (segments[left..len].first(), other.segments.get(right)) {
+                (Some(l), Some(r)) if !left_start_is_smaller(l.0.as_ref(), r.0.as_ref()) => {
+                    right += 1;
+                    Side::Right(right - 1)
+                }
+                (Some(_), _) => {
+                    left += 1;
+                    Side::Left(left - 1)
+                }
+                (None, Some(_)) => {
+                    right += 1;
+                    Side::Right(right - 1)
+                }
+                (None, None) => break,
+            };
+            let Some((start, end)) = accumulator else {
+                accumulator = Some((smaller, smaller));
+                continue;
+            };
+            let accumulator_end = end.bounds(&segments, &other.segments).1;
+            let (smaller_start, smaller_end) = smaller.bounds(&segments, &other.segments);
+            if end_before_start_with_gap(&accumulator_end, &smaller_start) {
+                let segment = (
+                    start.bounds(&segments, &other.segments).0.cloned(),
+                    accumulator_end.cloned(),
+                );
+                segments.push(segment);
+                accumulator = Some((smaller, smaller));
+            } else if left_end_is_smaller(accumulator_end, smaller_end) {
+                accumulator = Some((start, smaller));
+            }
+        }
+        if let Some((start, end)) = accumulator {
+            let segment = (
+                start.bounds(&segments, &other.segments).0.cloned(),
+                end.bounds(&segments, &other.segments).1.cloned(),
+            );
+            segments.push(segment);
+        }
+        segments.drain(..len);
+        Self {
+            segments: SmallVec::Flexible(segments),
+        }
+        .check_invariants()
+    }
+
+    /// Computes the intersection of two sets of versions, reusing the allocation of `self`.
+    ///
+    /// This gives the same result as [intersection](Self::intersection),
+    /// but when `self` is a temporary with its segments on the heap,
+    /// the output is written into that buffer instead of a new one.
+    pub fn into_intersection(self, other: &Self) -> Self {
+        let mut segments = match self.segments {
+            SmallVec::Flexible(segments) => segments,
+            segments => return Self { segments }.intersection(other),
+        };
+        // The output is written after the segments of `self`, which are removed at the end.
+        let len = segments.len();
+        segments.reserve(other.segments.len());
+        let (mut left, mut right) = (0, 0);
+        while left < len && right < other.segments.len() {
+            // Same steps as in `intersect_segments`.
+            let (left_start, left_end) = (segments[left].0.as_ref(), segments[left].1.as_ref());
+            let (right_start, right_end) = (
+                other.segments[right].0.as_ref(),
+                other.segments[right].1.as_ref(),
+            );
+            let (other_start, end) = if left_end_is_smaller(left_end, right_end) {
+                left += 1;
+                (right_start, left_end)
+            } else {
+                right += 1;
+                (left_start, right_end)
+            };
+            if !valid_segment(&other_start, &end) {
+                continue;
+            }
+            let start = larger_start(left_start, right_start);
+            let segment = (start.cloned(), end.cloned());
+            segments.push(segment);
+        }
+        segments.drain(..len);
+        Self {
+            segments: SmallVec::Flexible(segments),
+        }
+        .check_invariants()
+    }
+
+    /// Same as [emit_intersection_segments](Self::emit_intersection_segments),
+    /// when `many` has a lot more segments.
+    ///
+    /// For each segment of `few`, the overlapping segments of `many` are found
+    /// with a binary search, and only the first and the last of them need to be cut,
+    /// the others being inside of the segment of `few`.
+    fn emit_sparse_intersection<'a>(
+        many: &'a [Interval<V>],
+        few: &'a [Interval<V>],
+        mut emit: impl FnMut(Bound<&'a V>, Bound<&'a V>),
+    ) {
+        for segment in few {
+            let (start, end) = (segment.0.as_ref(), segment.1.as_ref());
+            // Segments ending before the start of `segment`.
+            let first = many.partition_point(|s| !valid_segment(&start, &s.1.as_ref()));
+            // Segments starting before the end of `segment`.
+            let last = many.partition_point(|s| valid_segment(&s.0.as_ref(), &end));
+            let overlapping = &many[first..last.max(first)];
+            for (i, s) in overlapping.iter().enumerate() {
+                if i == 0 || i + 1 == overlapping.len() {
+                    // The first and the last segments may extend outside of `segment`.
+                    Self::emit_intersection_segments(
+                        std::slice::from_ref(s),
+                        std::slice::from_ref(segment),
+                        &mut emit,
+                    );
+                } else {
+                    let (start, end) = (s.0.as_ref(), s.1.as_ref());
+                    emit(start, end);
+                }
+            }
+        }
+    }
+
+    /// The segments of the intersection of two ranges, given their sorted segments.
+    fn intersect_segments(left: &[Interval<V>], right: &[Interval<V>]) -> SmallVec<Interval<V>> {
         let mut output: SmallVec<Interval<V>> = SmallVec::empty();
-        let mut left_iter = self.segments.iter().peekable();
-        let mut right_iter = other.segments.iter().peekable();
+        Self::emit_intersection_segments(left, right, |start, end| {
+            output.push((start.cloned(), end.cloned()))
+        });
+        output
+    }
+
+    /// Calls `emit` with the segments of [intersect_segments](Self::intersect_segments).
+    fn emit_intersection_segments<'a>(
+        left: &'a [Interval<V>],
+        right: &'a [Interval<V>],
+        mut emit: impl FnMut(Bound<&'a V>, Bound<&'a V>),
+    ) {
+        let mut left_iter = left
+            .iter()
+            .map(|(start, end)| (start.as_ref(), end.as_ref()))
+            .peekable();
+        let mut right_iter = right
+            .iter()
+            .map(|(start, end)| (start.as_ref(), end.as_ref()))
+            .peekable();
         // By the definition of intersection any point that is matched by the output
         // must have a segment in each of the inputs that it matches.
         // Therefore, every segment in the output must be the intersection of a segment from each of the inputs.
         // It would be correct to do the "O(n^2)" thing, by computing the intersection of every segment from one input
         // with every segment of the other input, and sorting the result.
         // We can avoid the sorting by generating our candidate segments with an increasing `end` value.
-        while let Some(((left_start, left_end), (right_start, right_end))) =
+        while let Some((&(left_start, left_end), &(right_start, right_end))) =
             left_iter.peek().zip(right_iter.peek())
         {
             // The next smallest `end` value is going to come from one of the inputs.
-            let left_end_is_smaller = left_end_is_smaller(left_end.as_ref(), right_end.as_ref());
+            let left_end_is_smaller = left_end_is_smaller(left_end, right_end);
             // Now that we are processing `end` we will never have to process any segment smaller than that.
             // We can ensure that the input that `end` came from is larger than `end` by advancing it one step.
             // `end` is the smaller available input, so we know the other input is already larger than `end`.
@@ -666,30 +1312,16 @@ impl<V: Ord + Clone> Range<V> {
             // But, we already know that the segments in our input are valid.
             // So we do not need to check if the `start` from the input `end` came from is smaller than `end`.
             // If the `other_start` is larger than end, then the intersection will be invalid.
-            if !valid_segment(other_start, end) {
+            if !valid_segment(&other_start, &end) {
                 // Note: We can call `this_iter.next_if(!valid_segment(other_start, this_end))` in a loop.
                 // But the checks make it slower for the benchmarked inputs.
                 continue;
             }
-            let start = match (left_start, right_start) {
-                (Included(l), Included(r)) => Included(std::cmp::max(l, r)),
-                (Excluded(l), Excluded(r)) => Excluded(std::cmp::max(l, r)),
-
-                (Included(i), Excluded(e)) | (Excluded(e), Included(i)) => {
-                    if i <= e {
-                        Excluded(e)
-                    } else {
-                        Included(i)
-                    }
-                }
-                (s, Unbounded) | (Unbounded, s) => s.as_ref(),
-            };
-            // Now we clone and push a new segment.
+            let start = larger_start(left_start, right_start);
+            // Now we emit a new segment.
             // By dealing with references until now we ensure that NO cloning happens when we reject the segment.
-            output.push((start.cloned(), end.clone()))
+            emit(start, end)
         }
-
-        Self { segments: output }.check_invariants()
     }
 
     /// Return true if there can be no `V` so that `V` is contained in both `self` and `other`.
@@ -702,9 +1334,9 @@ impl<V: Ord + Clone> Range<V> {
         let mut right_iter = other.segments.iter().peekable();
 
         while let Some((left, right)) = left_iter.peek().zip(right_iter.peek()) {
-            if !valid_segment(&right.start_bound(), &left.end_bound()) {
+            if !valid_segment(&right.0, &left.1) {
                 left_iter.next();
-            } else if !valid_segment(&left.start_bound(), &right.end_bound()) {
+            } else if !valid_segment(&left.0, &right.1) {
                 right_iter.next();
             } else {
                 return false;
@@ -730,7 +1362,7 @@ impl<V: Ord + Clone> Range<V> {
         for subset_elem in subset_iter {
             // Check if the current containing element ends before the subset element.
             // There needs to be another containing element for our subset element in this case.
-            while !valid_segment(&subset_elem.start_bound(), &containing_elem.end_bound()) {
+            while !valid_segment(&subset_elem.0, &containing_elem.1) {
                 if let Some(containing_elem_) = containing_iter.next() {
                     containing_elem = containing_elem_;
                 } else {
@@ -739,7 +1371,7 @@ impl<V: Ord + Clone> Range<V> {
             }
 
             let start_contained =
-                left_start_is_smaller(containing_elem.start_bound(), subset_elem.start_bound());
+                left_start_is_smaller(containing_elem.0.as_ref(), subset_elem.0.as_ref());
 
             if !start_contained {
                 // The start element is not contained
@@ -747,7 +1379,7 @@ impl<V: Ord + Clone> Range<V> {
             }
 
             let end_contained =
-                left_end_is_smaller(subset_elem.end_bound(), containing_elem.end_bound());
+                left_end_is_smaller(subset_elem.1.as_ref(), containing_elem.1.as_ref());
 
             if !end_contained {
                 // The end element is not contained
@@ -758,6 +1390,129 @@ impl<V: Ord + Clone> Range<V> {
         true
     }
 
+    /// Returns the versions just inside and just outside of each bound,
+    /// in increasing order and without duplicates.
+    ///
+    /// For discrete versions, with `pred` and `succ` giving the previous and next versions,
+    /// these are the versions telling this range apart from a simpler one,
+    /// so they can be used as the `versions` of [simplify](Self::simplify).
+    /// Unbounded edges have no probes, nor do versions without a predecessor or successor.
+    ///
+    /// ```
+    /// # use pubgrub::Range;
+    /// let range = Range::<u32>::between(2u32, 5u32);
+    /// let probes: Vec<u32> = range
+    ///     .boundary_probes(|v| v.checked_sub(1), |v| v.checked_add(1))
+    ///     .collect();
+    /// assert_eq!(probes, vec![1, 2, 4, 5]);
+    /// ```
+    pub fn boundary_probes(
+        &self,
+        pred: impl Fn(&V) -> Option<V>,
+        succ: impl Fn(&V) -> Option<V>,
+    ) -> impl Iterator<Item = V> {
+        let mut probes: Vec<V> = Vec::with_capacity(4 * self.segments.len());
+        for segment in self.segments.iter() {
+            let (start, end) = (segment.0.as_ref(), segment.1.as_ref());
+            // Outside, then inside.
+            match start {
+                Included(v) => probes.extend(pred(v).into_iter().chain([v.clone()])),
+                Excluded(v) => probes.extend([v.clone()].into_iter().chain(succ(v))),
+                Unbounded => {}
+            }
+            // Inside, then outside.
+            match end {
+                Included(v) => probes.extend([v.clone()].into_iter().chain(succ(v))),
+                Excluded(v) => probes.extend(pred(v).into_iter().chain([v.clone()])),
+                Unbounded => {}
+            }
+        }
+        // Adjacent or degenerate segments can give probes out of order, or the same probe twice.
+        probes.sort();
+        probes.dedup();
+        probes.into_iter()
+    }
+
+    /// Returns the canonical form of this range over a discrete domain,
+    /// with `pred` and `succ` giving the previous and next versions.
+    ///
+    /// Two ranges containing the same versions of the domain have equal canonical forms,
+    /// while they can be structurally different, and thus not `==`, before normalization.
+    /// In the canonical form:
+    ///  - excluded bounds are replaced by the included next or previous version,
+    ///  - bounds on the first or last version of the domain are replaced by `Unbounded`,
+    ///  - segments holding no version are removed,
+    ///  - segments with no version between them are merged.
+    ///
+    /// Of the constructors, [empty](Self::empty), [full](Self::full),
+    /// [singleton](Self::singleton), [higher_than](Self::higher_than), [lower_than](Self::lower_than)
+    /// and [from_versions_merging](Self::from_versions_merging) with the same `succ`
+    /// are canonical, as long as their versions are not the first or last of the domain.
+    /// The other constructors use excluded bounds, and set operations keep the bounds
+    /// of their operands, so their results usually are not.
+    ///
+    /// ```
+    /// # use pubgrub::Range;
+    /// let pred = |v: &u32| v.checked_sub(1);
+    /// let succ = |v: &u32| v.checked_add(1);
+    /// let r1 = Range::<u32>::between(3u32, 4u32);
+    /// let r2 = Range::from_range_bounds(2u32..5).intersection(&Range::strictly_higher_than(2u32));
+    /// assert_ne!(r1, r2);
+    /// assert_eq!(r1.canonical(pred, succ), Range::singleton(3u32));
+    /// assert_eq!(r2.canonical(pred, succ), Range::from_range_bounds(3u32..=4));
+    /// ```
+    pub fn canonical(
+        &self,
+        pred: impl Fn(&V) -> Option<V>,
+        succ: impl Fn(&V) -> Option<V>,
+    ) -> Self {
+        let mut segments: SmallVec<Interval<V>> = SmallVec::empty();
+        for segment in self.segments.iter() {
+            let (start, end) = (segment.0.as_ref(), segment.1.as_ref());
+            let start = match start {
+                Included(v) if pred(v).is_none() => Unbounded,
+                Included(v) => Included(v.clone()),
+                Excluded(v) => match succ(v) {
+                    Some(next) => Included(next),
+                    None => continue,
+                },
+                Unbounded => Unbounded,
+            };
+            let end = match end {
+                Included(v) if succ(v).is_none() => Unbounded,
+                Included(v) => Included(v.clone()),
+                Excluded(v) => match pred(v) {
+                    Some(previous) => Included(previous),
+                    None => continue,
+                },
+                Unbounded => Unbounded,
+            };
+            if !valid_segment(&start, &end) {
+                continue;
+            }
+            // Merge with the previous segment if no version is between them.
+            let start = match (segments.pop(), start) {
+                (Some(previous), Included(v)) => {
+                    let (previous_start, previous_end) = (previous.0.as_ref(), previous.1.as_ref());
+                    match previous_end {
+                        Included(e) if succ(e).as_ref() == Some(&v) => previous_start.cloned(),
+                        _ => {
+                            segments.push(previous);
+                            Included(v)
+                        }
+                    }
+                }
+                (Some(previous), start) => {
+                    segments.push(previous);
+                    start
+                }
+                (None, start) => start,
+            };
+            segments.push((start, end));
+        }
+        Self { segments }.check_invariants()
+    }
+
     /// Returns a simpler Range that contains the same versions.
     ///
     /// For every one of the Versions provided in versions the existing range and the simplified range will agree on whether it is contained.
@@ -814,6 +1569,33 @@ impl<V: Ord + Clone> Range<V> {
         self.keep_segments(kept_segments)
     }
 
+    /// Returns a simpler Range that contains the same versions,
+    /// when `all_versions` are all the versions that exist.
+    ///
+    /// Unlike [simplify](Self::simplify), the given versions are a complete list,
+    /// not a sample, so the result is only defined by which of them it contains:
+    ///
+    /// If none of the versions are contained in the original, the result is `empty`.
+    /// If all the versions are contained in the original, the result is `full`,
+    /// even when the original is a singleton.
+    /// Otherwise, the result is the same as with [simplify](Self::simplify).
+    ///
+    /// If the given versions are not sorted the correctness of this function is not guaranteed.
+    pub fn simplify_within<I, BV>(&self, all_versions: I) -> Self
+    where
+        I: Iterator<Item = BV>,
+        BV: Borrow<V>,
+    {
+        let all_versions: Vec<BV> = all_versions.collect();
+        if !self.any_contained(all_versions.iter().map(Borrow::borrow)) {
+            Self::empty()
+        } else if self.all_contained(all_versions.iter().map(Borrow::borrow)) {
+            Self::full()
+        } else {
+            self.simplify(all_versions.iter().map(Borrow::borrow))
+        }
+    }
+
     /// Create a new range with a subset of segments at given location bounds.
     ///
     /// Each new segment is constructed from a pair of segments, taking the
@@ -833,9 +1615,106 @@ impl<V: Ord + Clone> Range<V> {
     }
 
     /// Iterate over the parts of the range.
-    pub fn iter(&self) -> impl Iterator<Item = (&Bound<V>, &Bound<V>)> {
+    ///
+    /// The parts are in increasing order, use `iter().rev()` to start from the highest versions.
+    pub fn iter(
+        &self,
+    ) -> impl DoubleEndedIterator<Item = (&Bound<V>, &Bound<V>)> + ExactSizeIterator {
         self.segments.iter().map(|(start, end)| (start, end))
     }
+
+    /// The `i`-th part of the range, in the order of [iter](Self::iter),
+    /// or [None] if there are not that many.
+    pub fn get_segment(&self, i: usize) -> Option<(&Bound<V>, &Bound<V>)> {
+        self.segments.get(i).map(|(start, end)| (start, end))
+    }
+
+    /// Returns the range with `delta` added to every bound, or [None] if any addition overflows.
+    ///
+    /// Unbounded sides stay unbounded.
+    /// For signed versions, a negative `delta` shifts the range down.
+    pub fn offset_by(&self, delta: V) -> Option<Self>
+    where
+        V: CheckedAdd,
+    {
+        let shift = |bound: Bound<&V>| {
+            Some(match bound {
+                Included(v) => Included(v.checked_add(&delta)?),
+                Excluded(v) => Excluded(v.checked_add(&delta)?),
+                Unbounded => Unbounded,
+            })
+        };
+        let mut segments = SmallVec::empty();
+        for segment in self.segments.iter() {
+            let (start, end) = (segment.0.as_ref(), segment.1.as_ref());
+            segments.push((shift(start)?, shift(end)?));
+        }
+        Some(Self { segments }.check_invariants())
+    }
+
+    /// Returns the range made of the segments for which `keep` returns true.
+    ///
+    /// The segments are given as in [iter](Self::iter).
+    /// Removing segments keeps the others apart, so the result is always a valid range.
+    pub fn retain(&self, mut keep: impl FnMut((Bound<&V>, Bound<&V>)) -> bool) -> Self {
+        let mut segments = SmallVec::empty();
+        for segment in self.segments.iter() {
+            if keep((segment.0.as_ref(), segment.1.as_ref())) {
+                segments.push(segment.clone());
+            }
+        }
+        Self { segments }.check_invariants()
+    }
+
+    /// Returns the versions of this range higher or equal to `v`,
+    /// the intersection with [higher_than](Self::higher_than).
+    ///
+    /// The segments below `v` are skipped with a binary search,
+    /// and only the first segment left is cut.
+    pub fn truncate_below(&self, v: &V) -> Self {
+        let cut = Included(v);
+        // Segments ending before `v`.
+        let first = self
+            .segments
+            .partition_point(|s| !valid_segment(&cut, &s.1.as_ref()));
+        let mut segments = SmallVec::empty();
+        let mut kept = self.segments[first..].iter();
+        if let Some(segment) = kept.next() {
+            let (start, end) = (segment.0.as_ref(), segment.1.as_ref());
+            segments.push((larger_start(start, cut).cloned(), end.cloned()));
+        }
+        for segment in kept {
+            segments.push(segment.clone());
+        }
+        Self { segments }.check_invariants()
+    }
+
+    /// Returns the versions of this range lower or equal to `v`,
+    /// the intersection with [lower_than](Self::lower_than).
+    ///
+    /// The segments above `v` are skipped with a binary search,
+    /// and only the last segment left is cut.
+    pub fn truncate_above(&self, v: &V) -> Self {
+        let cut = Included(v);
+        // Segments starting before `v`.
+        let last = self
+            .segments
+            .partition_point(|s| valid_segment(&s.0.as_ref(), &cut));
+        let mut segments = SmallVec::empty();
+        if let Some((segment, kept)) = self.segments[..last].split_last() {
+            for segment in kept {
+                segments.push(segment.clone());
+            }
+            let (start, end) = (segment.0.as_ref(), segment.1.as_ref());
+            let end = if left_end_is_smaller(end, cut) {
+                end
+            } else {
+                cut
+            };
+            segments.push((start.cloned(), end.cloned()));
+        }
+        Self { segments }.check_invariants()
+    }
 }
 
 impl<T: Debug + Display + Clone + Eq + Ord> VersionSet for Range<T> {
@@ -876,14 +1755,43 @@ impl<T: Debug + Display + Clone + Eq + Ord> VersionSet for Range<T> {
     fn subset_of(&self, other: &Self) -> bool {
         Range::subset_of(self, other)
     }
+
+    fn as_singleton(&self) -> Option<&Self::V> {
+        Range::as_singleton(self)
+    }
+
+    /// The number of segments, since the number of versions in them is not known.
+    fn approximate_len(&self) -> usize {
+        self.segment_count()
+    }
 }
 
 // REPORT ######################################################################
 
+impl<T: Debug + Display + Clone + Eq + Ord> SegmentedVersionSet for Range<T> {
+    fn first_segments(&self, max_segments: usize) -> (Self, usize) {
+        let mut segments = SmallVec::empty();
+        for segment in self.segments.iter().take(max_segments) {
+            segments.push(segment.clone());
+        }
+        let more = self.segments.len().saturating_sub(max_segments);
+        (Self { segments }, more)
+    }
+}
+
 impl<V: Display + Eq> Display for Range<V> {
+    /// Segments are separated by `|`, and the complement of a singleton is written `!= v`.
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         if self.segments.is_empty() {
             write!(f, "∅")?;
+        } else if let [(Unbounded, Excluded(v1)), (Excluded(v2), Unbounded)] =
+            self.segments.as_slice()
+        {
+            if v1 == v2 {
+                write!(f, "!= {v1}")?;
+            } else {
+                write!(f, "<{v1} | >{v2}")?;
+            }
         } else {
             for (idx, segment) in self.segments.iter().enumerate() {
                 if idx > 0 {
@@ -912,6 +1820,117 @@ impl<V: Display + Eq> Display for Range<V> {
     }
 }
 
+// PARSING #####################################################################
+
+/// Error parsing a [Range] from its [Display] format.
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum RangeParseError {
+    /// A segment between `|` is not a single version, a single bound,
+    /// a lower bound followed by an upper bound, or `*`.
+    #[error("invalid segment '{segment}' in range '{full_range}'")]
+    InvalidSegment {
+        /// The range that was being parsed.
+        full_range: String,
+        /// The segment that could not be parsed.
+        segment: String,
+    },
+    /// A version in a bound could not be parsed.
+    #[error("cannot parse version '{version}' in range '{full_range}': {parse_error}")]
+    InvalidVersion {
+        /// The range that was being parsed.
+        full_range: String,
+        /// The version that could not be parsed.
+        version: String,
+        /// The error returned when parsing the version.
+        parse_error: String,
+    },
+}
+
+impl<V> FromStr for Range<V>
+where
+    V: FromStr + Ord + Clone,
+    V::Err: Display,
+{
+    type Err = RangeParseError;
+
+    /// Parse the format produced by [Display]: segments separated by `|`,
+    /// each being `*`, a version, or bounds such as `>=1`, `<2` or `>1, <=2`.
+    /// The empty range is written `∅`, and `!= v` is the complement of a version.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.trim() == "∅" {
+            return Ok(Self::empty());
+        }
+        let invalid_segment = |segment: &str| RangeParseError::InvalidSegment {
+            full_range: s.to_string(),
+            segment: segment.to_string(),
+        };
+        let parse_version = |version: &str| {
+            version
+                .trim()
+                .parse::<V>()
+                .map_err(|e| RangeParseError::InvalidVersion {
+                    full_range: s.to_string(),
+                    version: version.trim().to_string(),
+                    parse_error: e.to_string(),
+                })
+        };
+        let parse_lower = |bound: &str| -> Result<Option<Bound<V>>, RangeParseError> {
+            Ok(if let Some(v) = bound.strip_prefix(">=") {
+                Some(Included(parse_version(v)?))
+            } else if let Some(v) = bound.strip_prefix('>') {
+                Some(Excluded(parse_version(v)?))
+            } else {
+                None
+            })
+        };
+        let parse_upper = |bound: &str| -> Result<Option<Bound<V>>, RangeParseError> {
+            Ok(if let Some(v) = bound.strip_prefix("<=") {
+                Some(Included(parse_version(v)?))
+            } else if let Some(v) = bound.strip_prefix('<') {
+                Some(Excluded(parse_version(v)?))
+            } else {
+                None
+            })
+        };
+
+        let mut range = Self::empty();
+        for segment in s.split('|') {
+            let segment = segment.trim();
+            let (start, end) = match segment.split_once(',') {
+                Some((lower, upper)) => {
+                    let start = parse_lower(lower.trim())?;
+                    let end = parse_upper(upper.trim())?;
+                    match (start, end) {
+                        (Some(start), Some(end)) => (start, end),
+                        _ => return Err(invalid_segment(segment)),
+                    }
+                }
+                None if segment == "*" => (Unbounded, Unbounded),
+                None if segment.is_empty() => return Err(invalid_segment(segment)),
+                None => {
+                    if let Some(v) = segment.strip_prefix("!=") {
+                        let v = parse_version(v)?;
+                        range = range.union(&Self::singleton(v).complement());
+                        continue;
+                    } else if let Some(start) = parse_lower(segment)? {
+                        (start, Unbounded)
+                    } else if let Some(end) = parse_upper(segment)? {
+                        (Unbounded, end)
+                    } else {
+                        let v = parse_version(segment)?;
+                        (Included(v.clone()), Included(v))
+                    }
+                }
+            };
+            if !valid_segment(&start, &end) {
+                return Err(invalid_segment(segment));
+            }
+            range = range.union(&Self::from_range_bounds::<_, V>((start, end)));
+        }
+        Ok(range)
+    }
+}
+
 // SERIALIZATION ###############################################################
 
 #[cfg(feature = "serde")]
@@ -956,7 +1975,8 @@ pub mod tests {
     pub fn strategy() -> impl Strategy<Value = Range<u32>> {
         (
             any::<bool>(),
-            prop::collection::vec(any::<(u32, bool)>(), 1..10),
+            // Without any delta, the range is full if it starts unbounded and empty otherwise.
+            prop::collection::vec(any::<(u32, bool)>(), 0..10),
         )
             .prop_map(|(start_unbounded, deltas)| {
                 let mut start = if start_unbounded {
@@ -1031,6 +2051,13 @@ pub mod tests {
             assert_eq!(range, r);
         }
 
+        // Testing parsing ---------------------------------
+
+        #[test]
+        fn from_str_round_trip(range in strategy()) {
+            assert_eq!(range.to_string().parse::<Range<u32>>(), Ok(range));
+        }
+
         // Testing negate ----------------------------------
 
         #[test]
@@ -1038,6 +2065,14 @@ pub mod tests {
             assert_ne!(range.complement(), range);
         }
 
+        #[test]
+        fn complement_ref_is_complement(range in strategy()) {
+            let complement = range.complement();
+            assert!(range
+                .complement_ref()
+                .eq(complement.iter().map(|(s, e)| (s.as_ref(), e.as_ref()))));
+        }
+
         #[test]
         fn double_negate_is_identity(range in strategy()) {
             assert_eq!(range.complement().complement(), range);
@@ -1065,6 +2100,35 @@ pub mod tests {
             assert_eq!(Range::empty().intersection(&range), Range::empty());
         }
 
+        #[test]
+        fn intersection_each_is_intersection(range in strategy(), others in prop::collection::vec(strategy(), 0..10)) {
+            let naive: Vec<_> = others.iter().map(|other| range.intersection(other)).collect();
+            let batched: Vec<_> = range.intersection_each(others.iter()).collect();
+            assert_eq!(batched, naive);
+        }
+
+        #[test]
+        fn into_intersection_is_intersection(r1 in strategy(), r2 in strategy()) {
+            assert_eq!(r1.clone().into_intersection(&r2), r1.intersection(&r2));
+        }
+
+        #[test]
+        fn into_union_is_union(r1 in strategy(), r2 in strategy()) {
+            assert_eq!(r1.clone().into_union(&r2), r1.union(&r2));
+        }
+
+        #[test]
+        fn buffer_operations(r1 in strategy(), r2 in strategy()) {
+            let segments = |range: Range<u32>| -> Vec<_> {
+                range.iter().map(|(s, e)| (*s, *e)).collect()
+            };
+            let mut out = vec![(Unbounded, Unbounded)];
+            r1.union_into(&r2, &mut out);
+            assert_eq!(out, segments(r1.union(&r2)));
+            r1.intersection_into(&r2, &mut out);
+            assert_eq!(out, segments(r1.intersection(&r2)));
+        }
+
         #[test]
         fn intersection_is_idempotent(r1 in strategy(), r2 in strategy()) {
             assert_eq!(r1.intersection(&r2).intersection(&r2), r1.intersection(&r2));
@@ -1136,6 +2200,43 @@ pub mod tests {
             assert_eq!(range.contains(&version), range.intersection(&Range::singleton(version)) != Range::empty());
         }
 
+        #[test]
+        fn singleton_intersection_is_general_intersection(range in strategy(), version in version_strat()) {
+            let singleton = Range::singleton(version);
+            let general = Range {
+                segments: Range::intersect_segments(&range.segments, &singleton.segments),
+            };
+            assert_eq!(range.intersection(&singleton), general);
+            assert_eq!(singleton.intersection(&range), general);
+        }
+
+        #[test]
+        fn sparse_intersection_is_general_intersection(r1 in strategy(), r2 in strategy()) {
+            let general = Range::intersect_segments(&r1.segments, &r2.segments);
+            let general: Vec<_> = general.iter().map(|(start, end)| (start.as_ref(), end.as_ref())).collect();
+            for (many, few) in [(&r1, &r2), (&r2, &r1)] {
+                let mut sparse = Vec::new();
+                Range::emit_sparse_intersection(&many.segments, &few.segments, |start, end| {
+                    sparse.push((start, end))
+                });
+                assert_eq!(sparse, general);
+            }
+        }
+
+        #[test]
+        fn truncate_below(range in strategy(), v in version_strat(), x in version_strat()) {
+            let truncated = range.truncate_below(&v);
+            assert_eq!(truncated.contains(&x), range.contains(&x) && x >= v);
+            assert_eq!(truncated, range.intersection(&Range::higher_than(v)));
+        }
+
+        #[test]
+        fn truncate_above(range in strategy(), v in version_strat(), x in version_strat()) {
+            let truncated = range.truncate_above(&v);
+            assert_eq!(truncated.contains(&x), range.contains(&x) && x <= v);
+            assert_eq!(truncated, range.intersection(&Range::lower_than(v)));
+        }
+
         #[test]
         fn contains_bounding_range(range in strategy(), version in version_strat()) {
             if range.contains(&version) {
@@ -1172,6 +2273,69 @@ pub mod tests {
             }
         }
 
+        #[test]
+        fn any_and_all_contained(range in strategy(), mut versions in proptest::collection::vec(version_strat(), ..30)) {
+            versions.sort();
+            assert_eq!(range.any_contained(versions.iter()), range.contains_many(versions.iter()).any(|b| b));
+            assert_eq!(range.all_contained(versions.iter()), range.contains_many(versions.iter()).all(|b| b));
+        }
+
+        #[test]
+        fn count_contained(range in strategy(), mut versions in proptest::collection::vec(version_strat(), ..30)) {
+            versions.sort();
+            let count = versions.iter().filter(|v| range.contains(v)).count();
+            assert_eq!(range.count_contained(&versions), count);
+        }
+
+        #[test]
+        fn contains_unsorted(range in strategy(), versions in proptest::collection::vec(version_strat(), ..30).prop_shuffle()) {
+            let expected: Vec<bool> = versions.iter().map(|v| range.contains(v)).collect();
+            assert_eq!(range.contains_unsorted(&versions), expected);
+        }
+
+        #[test]
+        fn simplify_with_boundary_probes(range in strategy()) {
+            let probes: Vec<u32> = range
+                .boundary_probes(|&v| v.checked_sub(1), |&v| u32::checked_add(v, 1))
+                .collect();
+            assert!(probes.windows(2).all(|w| w[0] < w[1]));
+            let simp = range.simplify(probes.iter());
+            for v in &probes {
+                assert_eq!(range.contains(v), simp.contains(v));
+            }
+        }
+
+        #[test]
+        fn canonical_is_semantic(r1 in strategy(), r2 in strategy()) {
+            let pred = |&v: &u32| v.checked_sub(1);
+            let succ = |&v: &u32| u32::checked_add(v, 1);
+            // The same union computed two ways, with different structures.
+            let union = r1.union(&r2);
+            let de_morgan = r1.complement().intersection(&r2.complement()).complement();
+            let canonical = union.canonical(pred, succ);
+            assert_eq!(canonical, de_morgan.canonical(pred, succ));
+            assert_eq!(canonical, canonical.canonical(pred, succ));
+            for v in union.boundary_probes(pred, succ) {
+                assert_eq!(union.contains(&v), canonical.contains(&v));
+            }
+        }
+
+        #[test]
+        fn simplify_within(range in strategy(), mut versions in proptest::collection::vec(version_strat(), ..30)) {
+            versions.sort();
+            let simp = range.simplify_within(versions.iter());
+
+            for v in &versions {
+                assert_eq!(range.contains(v), simp.contains(v));
+            }
+            if !versions.is_empty() && range.all_contained(versions.iter()) {
+                assert_eq!(simp, Range::full());
+            }
+            if !range.any_contained(versions.iter()) {
+                assert_eq!(simp, Range::empty());
+            }
+        }
+
         #[test]
         fn simplify(range in strategy(), mut versions in proptest::collection::vec(version_strat(), ..30)) {
             versions.sort();
@@ -1202,6 +2366,410 @@ pub mod tests {
         );
     }
 
+    #[test]
+    fn intersection_each_many_segments() {
+        // Every version but the multiples of 10, as in a package with many yanked versions.
+        let range: Range<u32> = (0..100u32)
+            .map(|v| Range::singleton(v * 10))
+            .fold(Range::empty(), |acc, r| acc.union(&r))
+            .complement();
+        let mut others: Vec<Range<u32>> = (0..100u32)
+            .map(|v| Range::between(v * 7, v * 7 + 25))
+            .collect();
+        others.push(Range::higher_than(950u32));
+        others.push(Range::strictly_lower_than(5u32));
+        others.push(Range::singleton(40u32));
+        others.push(Range::full());
+        others.push(Range::empty());
+        let naive: Vec<_> = others
+            .iter()
+            .map(|other| range.intersection(other))
+            .collect();
+        let batched: Vec<_> = range.intersection_each(others.iter()).collect();
+        assert_eq!(batched, naive);
+    }
+
+    #[test]
+    fn owned_operations_reuse_allocation() {
+        let heap_range = || {
+            let mut range: Range<u32> = (0..10u32)
+                .map(|v| Range::singleton(v * 10))
+                .fold(Range::empty(), |acc, r| acc.union(&r));
+            match &mut range.segments {
+                SmallVec::Flexible(segments) => segments.reserve(64),
+                _ => unreachable!("ten segments are on the heap"),
+            }
+            range
+        };
+        let other: Range<u32> = Range::between(15u32, 55u32).union(&Range::higher_than(80u32));
+
+        let range = heap_range();
+        let buffer = range.segments.as_ptr();
+        let expected = range.intersection(&other);
+        let intersection = range.into_intersection(&other);
+        assert_eq!(intersection, expected);
+        assert_eq!(intersection.segments.as_ptr(), buffer);
+
+        let range = heap_range();
+        let buffer = range.segments.as_ptr();
+        let expected = range.union(&other);
+        let union = range.into_union(&other);
+        assert_eq!(union, expected);
+        assert_eq!(union.segments.as_ptr(), buffer);
+    }
+
+    #[test]
+    fn retain_bounded_below() {
+        let range: Range<u32> = Range::strictly_lower_than(3u32)
+            .union(&Range::between(5u32, 8u32))
+            .union(&Range::singleton(10u32))
+            .union(&Range::higher_than(20u32));
+        let retained = range.retain(|(start, _)| start != Unbounded);
+        assert_eq!(
+            retained,
+            Range::between(5u32, 8u32)
+                .union(&Range::singleton(10u32))
+                .union(&Range::higher_than(20u32))
+        );
+        for v in 0..30 {
+            let expected = range.contains(&v) && v >= 5;
+            assert_eq!(retained.contains(&v), expected, "{v}");
+        }
+        // Dropping the singletons.
+        let retained = range
+            .retain(|(start, end)| !matches!((start, end), (Included(s), Included(e)) if s == e));
+        assert!(!retained.contains(&10));
+        assert!(retained.contains(&6));
+        assert_eq!(range.retain(|_| false), Range::empty());
+        assert_eq!(range.retain(|_| true), range);
+    }
+
+    #[test]
+    fn offset_by() {
+        let range: Range<u64> = Range::strictly_lower_than(3u64)
+            .union(&Range::between(5u64, 8u64))
+            .union(&Range::singleton(10u64))
+            .union(&Range::strictly_higher_than(20u64));
+        let shifted = range.offset_by(1000).unwrap();
+        assert_eq!(
+            shifted,
+            Range::strictly_lower_than(1003u64)
+                .union(&Range::between(1005u64, 1008u64))
+                .union(&Range::singleton(1010u64))
+                .union(&Range::strictly_higher_than(1020u64))
+        );
+        for v in 0..30 {
+            assert_eq!(range.contains(&v), shifted.contains(&(v + 1000)));
+        }
+        assert_eq!(
+            Range::<u64>::full().offset_by(u64::MAX),
+            Some(Range::full())
+        );
+        assert_eq!(
+            Range::<u64>::empty().offset_by(u64::MAX),
+            Some(Range::empty())
+        );
+
+        // Up to the type max, but not past it.
+        let near_max: Range<u64> = Range::singleton(u64::MAX - 1);
+        assert_eq!(near_max.offset_by(1), Some(Range::singleton(u64::MAX)));
+        assert_eq!(near_max.offset_by(2), None);
+        assert_eq!(Range::<u64>::higher_than(u64::MAX - 1).offset_by(2), None);
+
+        let signed: Range<i32> = Range::between(-5, 5);
+        assert_eq!(signed.offset_by(-10), Some(Range::between(-15, -5)));
+        assert_eq!(Range::<i32>::higher_than(i32::MIN).offset_by(-1), None);
+    }
+
+    #[test]
+    fn intersection_with_many_singletons() {
+        let versions = Range::from_versions((0..500u32).map(|v| 3 * v));
+        let broad = Range::between(10u32, 1000u32);
+        let expected = Range::from_versions((4..334u32).map(|v| 3 * v));
+        assert_eq!(versions.intersection(&broad), expected);
+        assert_eq!(broad.intersection(&versions), expected);
+        assert_eq!(
+            versions.intersection(&broad),
+            Range {
+                segments: Range::intersect_segments(&versions.segments, &broad.segments)
+            }
+        );
+        assert_eq!(versions.intersection(&Range::full()), versions);
+        assert!(versions
+            .intersection(&Range::higher_than(2000u32))
+            .is_empty());
+    }
+
+    #[test]
+    fn double_complement_of_all_edges() {
+        let starts = [Unbounded, Included(1u32), Excluded(1u32)];
+        let ends = [Included(9u32), Excluded(9u32), Unbounded];
+        let middles = [
+            None,
+            Some((Included(4u32), Included(4u32))),
+            Some((Excluded(3u32), Excluded(5u32))),
+            Some((Included(3u32), Excluded(5u32))),
+            Some((Excluded(3u32), Included(5u32))),
+        ];
+        for start in starts {
+            for end in ends {
+                for middle in middles {
+                    let range = match middle {
+                        None => Range::<u32>::from_range_bounds((start, end)),
+                        Some((middle_start, middle_end)) => {
+                            let flip = |bound: Bound<u32>| match bound {
+                                Included(v) => Excluded(v),
+                                Excluded(v) => Included(v),
+                                Unbounded => Unbounded,
+                            };
+                            Range::from_range_bounds((start, flip(middle_start)))
+                                .union(&Range::from_range_bounds((flip(middle_end), end)))
+                        }
+                    };
+                    assert_eq!(range.complement().complement(), range, "{range}");
+                    assert_eq!(
+                        range.complement().complement().segments,
+                        range.segments,
+                        "{range}"
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn iter_both_ways() {
+        let range = Range::<u32>::between(1u32, 3u32)
+            .union(&Range::singleton(5u32))
+            .union(&Range::strictly_higher_than(7u32));
+        let forward: Vec<_> = range.iter().collect();
+        assert_eq!(
+            forward,
+            vec![
+                (&Included(1), &Excluded(3)),
+                (&Included(5), &Included(5)),
+                (&Excluded(7), &Unbounded)
+            ]
+        );
+        assert_eq!(range.iter().len(), 3);
+        let mut backward: Vec<_> = range.iter().rev().collect();
+        backward.reverse();
+        assert_eq!(backward, forward);
+        for (i, segment) in forward.iter().enumerate() {
+            assert_eq!(range.get_segment(i), Some(*segment));
+        }
+        assert_eq!(range.get_segment(3), None);
+    }
+
+    #[test]
+    fn from_versions() {
+        let range = Range::from_versions([3u32, 1, 1, 2]);
+        assert_eq!(range.segment_count(), 3);
+        for v in 0..5u32 {
+            assert_eq!(range.contains(&v), (1..=3).contains(&v), "{v}");
+        }
+        assert_eq!(
+            range,
+            Range::singleton(1u32)
+                .union(&Range::singleton(2u32))
+                .union(&Range::singleton(3u32))
+        );
+        assert_eq!(Range::<u32>::from_versions([]), Range::empty());
+
+        let merged = Range::from_versions_merging([3u32, 1, 1, 2], |&v| u32::checked_add(v, 1));
+        assert_eq!(merged, Range::from_range_bounds(1u32..=3));
+    }
+
+    #[test]
+    fn canonical() {
+        let pred = |&v: &u32| v.checked_sub(1);
+        let succ = |&v: &u32| u32::checked_add(v, 1);
+        let singletons = [
+            Range::singleton(3u32),
+            Range::between(3u32, 4u32),
+            Range::from_range_bounds((Excluded(2u32), Excluded(4u32))),
+            Range::higher_than(3u32).intersection(&Range::strictly_lower_than(4u32)),
+        ];
+        for range in &singletons {
+            assert_eq!(
+                range.canonical(pred, succ),
+                Range::singleton(3u32),
+                "{range}"
+            );
+        }
+
+        // Adjacent segments are merged.
+        let adjacent =
+            Range::from_range_bounds(1u32..=3).union(&Range::from_range_bounds(4u32..=5));
+        assert_eq!(adjacent.segment_count(), 2);
+        assert_eq!(
+            adjacent.canonical(pred, succ),
+            Range::from_range_bounds(1u32..=5)
+        );
+
+        // Bounds on the edges of the domain are unbounded.
+        assert_eq!(
+            Range::from_range_bounds(0u32..=42).canonical(pred, succ),
+            Range::lower_than(42u32)
+        );
+        assert_eq!(
+            Range::higher_than(u32::MAX).canonical(pred, succ),
+            Range::singleton(u32::MAX).canonical(pred, succ)
+        );
+        assert_eq!(
+            Range::<u32>::strictly_lower_than(0u32).canonical(pred, succ),
+            Range::empty()
+        );
+        assert_eq!(
+            Range::from_range_bounds(0u32..=u32::MAX).canonical(pred, succ),
+            Range::full()
+        );
+    }
+
+    #[test]
+    fn inclusive_range_bounds() {
+        let range = Range::<u32>::from_range_bounds(1u32..=5);
+        assert!(!range.contains(&0));
+        assert!(range.contains(&1));
+        assert!(range.contains(&5));
+        assert!(!range.contains(&6));
+
+        let range = Range::<u32>::from_range_bounds(..=5u32);
+        assert_eq!(range, Range::at_most(5u32));
+        assert!(range.contains(&0));
+        assert!(range.contains(&5));
+        assert!(!range.contains(&6));
+
+        let range = Range::<u32>::from_range_bounds(3u32..);
+        assert_eq!(range, Range::at_least(3u32));
+        assert!(!range.contains(&2));
+        assert!(range.contains(&3));
+        assert!(range.contains(&u32::MAX));
+    }
+
+    #[test]
+    fn is_bounded() {
+        let empty = Range::<u32>::empty();
+        assert!(empty.is_bounded());
+        assert!(!empty.has_lower_unbounded() && !empty.has_upper_unbounded());
+
+        let full = Range::<u32>::full();
+        assert!(!full.is_bounded());
+        assert!(full.has_lower_unbounded() && full.has_upper_unbounded());
+
+        let higher = Range::<u32>::higher_than(3u32);
+        assert!(!higher.is_bounded());
+        assert!(!higher.has_lower_unbounded() && higher.has_upper_unbounded());
+
+        let lower = Range::<u32>::strictly_lower_than(3u32).union(&Range::singleton(5u32));
+        assert!(!lower.is_bounded());
+        assert!(lower.has_lower_unbounded() && !lower.has_upper_unbounded());
+
+        let bounded = Range::<u32>::between(1u32, 3u32).union(&Range::singleton(5u32));
+        assert!(bounded.is_bounded());
+        assert!(!bounded.has_lower_unbounded() && !bounded.has_upper_unbounded());
+    }
+
+    #[test]
+    fn semantically_eq() {
+        let r1 = Range::<u32>::lower_than(42u32);
+        let r2 = Range::<u32>::from_range_bounds(0u32..=42);
+        assert_ne!(r1, r2);
+        assert!(r1.semantically_eq(&r2, Some(&0), None));
+        assert!(r2.semantically_eq(&r1, Some(&0), Some(&u32::MAX)));
+        assert!(!r1.semantically_eq(&r2, None, None));
+        // Only the unbounded edges are normalized.
+        let r3 = Range::<u32>::from_range_bounds(1u32..=42);
+        assert!(!r1.semantically_eq(&r3, Some(&0), None));
+
+        let r1 = Range::<u32>::singleton(1u32).union(&Range::higher_than(5u32));
+        let r2 = Range::<u32>::singleton(1u32).union(&Range::from_range_bounds(5u32..=u32::MAX));
+        assert!(r1.semantically_eq(&r2, None, Some(&u32::MAX)));
+        assert!(!r1.semantically_eq(&r2, Some(&0), None));
+        assert!(Range::<u32>::empty().semantically_eq(&Range::empty(), Some(&0), Some(&9)));
+        assert!(Range::<u32>::full().semantically_eq(
+            &Range::from_range_bounds(0u32..=9),
+            Some(&0),
+            Some(&9)
+        ));
+    }
+
+    #[test]
+    fn segment_containing() {
+        // <3 | [5, 8[ | 10 | >20
+        let range: Range<u32> = Range::strictly_lower_than(3u32)
+            .union(&Range::between(5u32, 8u32))
+            .union(&Range::singleton(10u32))
+            .union(&Range::strictly_higher_than(20u32));
+        let expected = [
+            (0, Ok(0)),
+            (2, Ok(0)),
+            (3, Err(1)),
+            (4, Err(1)),
+            (5, Ok(1)),
+            (7, Ok(1)),
+            (8, Err(2)),
+            (10, Ok(2)),
+            (11, Err(3)),
+            (20, Err(3)),
+            (21, Ok(3)),
+        ];
+        for (v, index) in expected {
+            assert_eq!(range.segment_containing(&v), index, "{v}");
+        }
+        assert_eq!(Range::<u32>::empty().segment_containing(&1), Err(0));
+        assert_eq!(Range::<u32>::full().segment_containing(&1), Ok(0));
+    }
+
+    #[test]
+    fn any_and_all_contained_stop_early() {
+        let range: Range<u32> = Range::between(1u32, 3u32);
+        let unreachable = || std::iter::from_fn(|| -> Option<u32> { panic!("consumed") });
+        assert!(range.any_contained([0, 2].into_iter().chain(unreachable())));
+        assert!(!range.all_contained([1, 3].into_iter().chain(unreachable())));
+    }
+
+    #[test]
+    fn simplify_within_universe() {
+        let all_versions = [1u32, 4, 7];
+        // Sparse versions all in the range, the range collapses to full.
+        let range: Range<u32> = Range::between(0u32, 5u32).union(&Range::singleton(7u32));
+        assert_eq!(range.simplify_within(all_versions.iter()), Range::full());
+        assert_eq!(
+            Range::<u32>::singleton(4u32).simplify_within([4u32].iter()),
+            Range::full()
+        );
+        // No version in the range, the range collapses to empty.
+        let range: Range<u32> = Range::between(2u32, 4u32);
+        assert_eq!(range.simplify(all_versions.iter()), range);
+        assert_eq!(range.simplify_within(all_versions.iter()), Range::empty());
+        // Otherwise, like simplify.
+        let range: Range<u32> = Range::between(0u32, 5u32);
+        assert_eq!(
+            range.simplify_within(all_versions.iter()),
+            Range::strictly_lower_than(5u32)
+        );
+    }
+
+    #[test]
+    fn boundary_probes_of_degenerate_segments() {
+        let pred = |&v: &u32| v.checked_sub(1);
+        let succ = |&v: &u32| u32::checked_add(v, 1);
+        // No integer between the excluded bounds, both bounds give the same probes.
+        let range: Range<u32> =
+            Range::strictly_higher_than(3u32).intersection(&Range::strictly_lower_than(4u32));
+        assert_eq!(
+            range.boundary_probes(pred, succ).collect::<Vec<_>>(),
+            [3, 4]
+        );
+        // A singleton gives its version once.
+        let range: Range<u32> = Range::singleton(3u32);
+        assert_eq!(
+            range.boundary_probes(pred, succ).collect::<Vec<_>>(),
+            [2, 3, 4]
+        );
+    }
+
     #[test]
     fn simplify_can_take_owned() {
         let range: Range<u8> = Range::singleton(1);
@@ -1218,6 +2786,24 @@ pub mod tests {
         );
     }
 
+    #[test]
+    fn as_singleton_through_version_set() {
+        fn pinned<VS: VersionSet>(set: &VS) -> Option<&VS::V> {
+            set.as_singleton()
+        }
+        assert_eq!(pinned(&Range::<u32>::singleton(3u32)), Some(&3));
+        assert_eq!(pinned(&Range::<u32>::empty()), None);
+        assert_eq!(pinned(&Range::<u32>::between(1u32, 3u32)), None);
+        assert_eq!(
+            <Range<u32> as VersionSet>::singleton_or_empty(Some(3)),
+            Range::singleton(3u32)
+        );
+        assert_eq!(
+            <Range<u32> as VersionSet>::singleton_or_empty(None),
+            Range::empty()
+        );
+    }
+
     #[test]
     fn version_ord() {
         let versions: &[Range<u32>] = &[
@@ -1246,4 +2832,100 @@ pub mod tests {
         version_reverse_sorted.sort();
         assert_eq!(version_reverse_sorted, versions);
     }
+
+    #[test]
+    fn buffer_reused() {
+        let evens = (0..20u32).fold(Range::<u32>::empty(), |acc, v| {
+            acc.union(&Range::singleton(2 * v))
+        });
+        let mut out = Vec::new();
+        evens.intersection_into(&Range::full(), &mut out);
+        assert_eq!(out.len(), 20);
+        let (capacity, ptr) = (out.capacity(), out.as_ptr());
+        for v in 0..20u32 {
+            evens.union_into(&Range::singleton(2 * v), &mut out);
+            assert_eq!(out.len(), 20);
+            evens.intersection_into(&Range::between(v, 3 * v), &mut out);
+            assert_eq!(
+                out.len(),
+                (v..3 * v).filter(|x| x % 2 == 0 && *x < 40).count()
+            );
+        }
+        assert_eq!((out.capacity(), out.as_ptr()), (capacity, ptr));
+    }
+
+    #[test]
+    fn nearest_contained() {
+        let range = Range::<u32>::between(1u32, 3u32).union(&Range::between(7u32, 9u32));
+        // The edges next to the gap are preferred, below before above.
+        assert_eq!(range.nearest_contained(&5), Some(&7));
+        assert_eq!(range.nearest_contained(&3), Some(&7));
+        assert_eq!(range.nearest_contained(&0), Some(&1));
+        assert_eq!(range.nearest_contained(&10), Some(&7));
+        assert_eq!(range.nearest_contained(&2), Some(&1));
+
+        let range = Range::<u32>::from_range_bounds(1u32..=3).union(&Range::singleton(7u32));
+        assert_eq!(range.nearest_contained(&5), Some(&3));
+        assert_eq!(range.nearest_contained(&8), Some(&7));
+
+        assert_eq!(Range::<u32>::empty().nearest_contained(&5), None);
+        assert_eq!(Range::<u32>::full().nearest_contained(&5), None);
+        assert_eq!(
+            Range::<u32>::strictly_higher_than(3u32).nearest_contained(&1),
+            None
+        );
+    }
+
+    #[test]
+    fn display_singleton_complement() {
+        assert_eq!(
+            Range::<u32>::singleton(5u32).complement().to_string(),
+            "!= 5"
+        );
+        assert_eq!(
+            Range::<u32>::strictly_lower_than(5u32)
+                .union(&Range::strictly_higher_than(6u32))
+                .to_string(),
+            "<5 | >6"
+        );
+        assert_eq!(
+            Range::<u32>::singleton(5u32)
+                .union(&Range::singleton(7u32))
+                .complement()
+                .to_string(),
+            "<5 | >5, <7 | >7"
+        );
+    }
+
+    #[test]
+    fn from_str_for_range() {
+        let parse = |s: &str| s.parse::<Range<u32>>();
+        assert_eq!(parse("∅"), Ok(Range::empty()));
+        assert_eq!(parse("*"), Ok(Range::full()));
+        assert_eq!(parse("3"), Ok(Range::singleton(3u32)));
+        assert_eq!(parse(">=1, <3"), Ok(Range::between(1u32, 3u32)));
+        assert_eq!(
+            parse("<1 | 2 | >4"),
+            Ok(Range::strictly_lower_than(1u32)
+                .union(&Range::singleton(2u32))
+                .union(&Range::strictly_higher_than(4u32)))
+        );
+        assert_eq!(parse("!= 5"), Ok(Range::singleton(5u32).complement()));
+        assert!(matches!(
+            parse(">=3, <1"),
+            Err(RangeParseError::InvalidSegment { .. })
+        ));
+        assert!(matches!(
+            parse("<1, >3"),
+            Err(RangeParseError::InvalidSegment { .. })
+        ));
+        assert!(matches!(
+            parse("1 |"),
+            Err(RangeParseError::InvalidSegment { .. })
+        ));
+        assert!(matches!(
+            parse(">=a"),
+            Err(RangeParseError::InvalidVersion { .. })
+        ));
+    }
 }
@@ -0,0 +1,348 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! [DependencyProvider] wrappers recording the answers of another one,
+//! and replaying them later, for example to reproduce a resolution offline.
+
+use std::cell::{Cell, RefCell};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::{Debug, Display};
+
+use thiserror::Error;
+
+use crate::solver::forward_to_dependency_provider;
+use crate::{
+    Dependencies, DependencyProvider, Map, Package, ProgressInfo, Set, VersionPreference,
+    VersionSet,
+};
+
+/// Answers given by a [DependencyProvider] during resolutions,
+/// recorded by [RecordingDependencyProvider] and replayed by [ReplayDependencyProvider].
+///
+/// With the `serde` feature, it can be saved and loaded,
+/// for example to reproduce a resolution of a production registry in a test.
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(
+    feature = "serde",
+    serde(bound(
+        serialize = "P: serde::Serialize, VS: serde::Serialize, VS::V: serde::Serialize, M: serde::Serialize, Priority: serde::Serialize",
+        deserialize = "P: serde::Deserialize<'de>, VS: serde::Deserialize<'de>, VS::V: serde::Deserialize<'de>, M: serde::Deserialize<'de>, Priority: serde::Deserialize<'de>"
+    ))
+)]
+pub struct Trace<P: Package, VS: VersionSet, M: Eq + Clone + Debug + Display, Priority> {
+    priorities: Vec<(P, VS, Priority)>,
+    versions: Vec<(P, VS, Option<VS::V>)>,
+    #[allow(clippy::type_complexity)]
+    dependencies: Vec<(P, VS::V, Dependencies<P, VS, M>)>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    missing_packages: Vec<P>,
+}
+
+/// The [Trace] of the answers of a [DependencyProvider].
+pub type TraceOf<DP> = Trace<
+    <DP as DependencyProvider>::P,
+    <DP as DependencyProvider>::VS,
+    <DP as DependencyProvider>::M,
+    <DP as DependencyProvider>::Priority,
+>;
+
+/// Answers of a dependency provider, indexed by package.
+struct Answers<P: Package, VS: VersionSet, M: Eq + Clone + Debug + Display, Priority> {
+    priorities: Map<P, Vec<(VS, Priority)>>,
+    #[allow(clippy::type_complexity)]
+    versions: Map<P, Vec<(VS, Option<VS::V>)>>,
+    #[allow(clippy::type_complexity)]
+    dependencies: Map<P, BTreeMap<VS::V, Dependencies<P, VS, M>>>,
+    missing_packages: Set<P>,
+}
+
+impl<P: Package, VS: VersionSet, M: Eq + Clone + Debug + Display, Priority> Default
+    for Answers<P, VS, M, Priority>
+{
+    fn default() -> Self {
+        Self {
+            priorities: Map::default(),
+            versions: Map::default(),
+            dependencies: Map::default(),
+            missing_packages: Set::default(),
+        }
+    }
+}
+
+impl<P: Package, VS: VersionSet, M: Eq + Clone + Debug + Display, Priority: Clone>
+    Answers<P, VS, M, Priority>
+{
+    fn from_trace(trace: Trace<P, VS, M, Priority>) -> Self {
+        let mut answers = Self::default();
+        for (package, range, priority) in trace.priorities {
+            answers.record_priority(&package, range, priority);
+        }
+        for (package, range, version) in trace.versions {
+            answers.record_version(&package, range, version);
+        }
+        for (package, version, dependencies) in trace.dependencies {
+            answers
+                .dependencies
+                .entry(package)
+                .or_default()
+                .insert(version, dependencies);
+        }
+        answers.missing_packages.extend(trace.missing_packages);
+        answers
+    }
+
+    fn to_trace(&self) -> Trace<P, VS, M, Priority> {
+        Trace {
+            priorities: self
+                .priorities
+                .iter()
+                .flat_map(|(package, answers)| {
+                    answers
+                        .iter()
+                        .map(|(range, priority)| (package.clone(), range.clone(), priority.clone()))
+                })
+                .collect(),
+            versions: self
+                .versions
+                .iter()
+                .flat_map(|(package, answers)| {
+                    answers
+                        .iter()
+                        .map(|(range, version)| (package.clone(), range.clone(), version.clone()))
+                })
+                .collect(),
+            dependencies: self
+                .dependencies
+                .iter()
+                .flat_map(|(package, answers)| {
+                    answers.iter().map(|(version, dependencies)| {
+                        (package.clone(), version.clone(), dependencies.clone())
+                    })
+                })
+                .collect(),
+            missing_packages: self.missing_packages.iter().cloned().collect(),
+        }
+    }
+
+    fn priority(&self, package: &P, range: &VS) -> Option<&Priority> {
+        let answers = self.priorities.get(package)?;
+        answers.iter().find(|(r, _)| r == range).map(|(_, p)| p)
+    }
+
+    fn version(&self, package: &P, range: &VS) -> Option<&Option<VS::V>> {
+        let answers = self.versions.get(package)?;
+        answers.iter().find(|(r, _)| r == range).map(|(_, v)| v)
+    }
+
+    fn record_priority(&mut self, package: &P, range: VS, priority: Priority) {
+        if self.priority(package, &range).is_none() {
+            self.priorities
+                .entry(package.clone())
+                .or_default()
+                .push((range, priority));
+        }
+    }
+
+    fn record_version(&mut self, package: &P, range: VS, version: Option<VS::V>) {
+        if self.version(package, &range).is_none() {
+            self.versions
+                .entry(package.clone())
+                .or_default()
+                .push((range, version));
+        }
+    }
+}
+
+/// Records the answers of another [DependencyProvider] in a [Trace].
+///
+/// The priorities, the chosen versions, the dependencies and the missing packages are recorded,
+/// the first answer for each question being kept.
+/// Errors are not recorded, and the other methods are delegated to the inner provider.
+pub struct RecordingDependencyProvider<DP: DependencyProvider> {
+    inner: DP,
+    #[allow(clippy::type_complexity)]
+    answers: RefCell<Answers<DP::P, DP::VS, DP::M, DP::Priority>>,
+}
+
+impl<DP: DependencyProvider> RecordingDependencyProvider<DP> {
+    /// Wrap a dependency provider with an empty trace.
+    pub fn new(inner: DP) -> Self {
+        Self {
+            inner,
+            answers: RefCell::new(Answers::default()),
+        }
+    }
+
+    /// The wrapped dependency provider.
+    pub fn inner(&self) -> &DP {
+        &self.inner
+    }
+
+    /// The answers recorded so far.
+    pub fn trace(&self) -> TraceOf<DP> {
+        self.answers.borrow().to_trace()
+    }
+}
+
+impl<DP: DependencyProvider> DependencyProvider for RecordingDependencyProvider<DP> {
+    type P = DP::P;
+    type V = DP::V;
+    type VS = DP::VS;
+    type M = DP::M;
+
+    fn prioritize(&self, package: &DP::P, range: &DP::VS) -> DP::Priority {
+        let priority = self.inner.prioritize(package, range);
+        self.answers
+            .borrow_mut()
+            .record_priority(package, range.clone(), priority.clone());
+        priority
+    }
+
+    type Priority = DP::Priority;
+
+    type Err = DP::Err;
+
+    fn choose_version(&self, package: &DP::P, range: &DP::VS) -> Result<Option<DP::V>, DP::Err> {
+        let version = self.inner.choose_version(package, range)?;
+        self.answers
+            .borrow_mut()
+            .record_version(package, range.clone(), version.clone());
+        Ok(version)
+    }
+
+    fn choose_version_with_tried(
+        &self,
+        package: &DP::P,
+        range: &DP::VS,
+        already_tried: &BTreeSet<DP::V>,
+    ) -> Result<Option<DP::V>, DP::Err> {
+        let version = self
+            .inner
+            .choose_version_with_tried(package, range, already_tried)?;
+        self.answers
+            .borrow_mut()
+            .record_version(package, range.clone(), version.clone());
+        Ok(version)
+    }
+
+    fn package_exists(&self, package: &DP::P) -> bool {
+        let exists = self.inner.package_exists(package);
+        if !exists {
+            self.answers
+                .borrow_mut()
+                .missing_packages
+                .insert(package.clone());
+        }
+        exists
+    }
+
+    fn get_dependencies(
+        &self,
+        package: &DP::P,
+        version: &DP::V,
+    ) -> Result<Dependencies<DP::P, DP::VS, DP::M>, DP::Err> {
+        let dependencies = self.inner.get_dependencies(package, version)?;
+        self.answers
+            .borrow_mut()
+            .dependencies
+            .entry(package.clone())
+            .or_default()
+            .entry(version.clone())
+            .or_insert_with(|| dependencies.clone());
+        Ok(dependencies)
+    }
+
+    forward_to_dependency_provider!(
+        inner: is_deprioritized, version_preference, prefetch, should_cancel, on_progress
+    );
+}
+
+/// Error of [ReplayDependencyProvider] when asked a question that is not in its [Trace].
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+#[error("{0} was not recorded")]
+pub struct UnrecordedQuery(pub String);
+
+/// Answers with the recorded answers of another [DependencyProvider],
+/// given by a [Trace] of a [RecordingDependencyProvider].
+///
+/// Resolving again the same package and version gives the same result as the recorded resolution.
+/// A question that is not in the trace fails with an [UnrecordedQuery] error.
+/// Since [prioritize](DependencyProvider::prioritize) cannot fail,
+/// an unrecorded priority makes the next call to
+/// [should_cancel](DependencyProvider::should_cancel) fail instead.
+pub struct ReplayDependencyProvider<
+    P: Package,
+    VS: VersionSet,
+    M: Eq + Clone + Debug + Display,
+    Priority,
+> {
+    answers: Answers<P, VS, M, Priority>,
+    unrecorded: Cell<Option<UnrecordedQuery>>,
+}
+
+impl<P: Package, VS: VersionSet, M: Eq + Clone + Debug + Display, Priority: Clone>
+    ReplayDependencyProvider<P, VS, M, Priority>
+{
+    /// Answer from the given trace.
+    pub fn new(trace: Trace<P, VS, M, Priority>) -> Self {
+        Self {
+            answers: Answers::from_trace(trace),
+            unrecorded: Cell::new(None),
+        }
+    }
+}
+
+impl<P: Package, VS: VersionSet, M: Eq + Clone + Debug + Display, Priority: Ord + Clone>
+    DependencyProvider for ReplayDependencyProvider<P, VS, M, Priority>
+{
+    type P = P;
+    type V = VS::V;
+    type VS = VS;
+    type M = M;
+
+    fn prioritize(&self, package: &P, range: &VS) -> Self::Priority {
+        let priority = self.answers.priority(package, range).cloned();
+        if priority.is_none() {
+            self.unrecorded.set(Some(UnrecordedQuery(format!(
+                "priority of {package} {range}"
+            ))));
+        }
+        priority
+    }
+
+    /// The recorded priority, [None] if it was not recorded.
+    type Priority = Option<Priority>;
+
+    type Err = UnrecordedQuery;
+
+    fn choose_version(&self, package: &P, range: &VS) -> Result<Option<VS::V>, UnrecordedQuery> {
+        self.answers
+            .version(package, range)
+            .cloned()
+            .ok_or_else(|| UnrecordedQuery(format!("version of {package} {range}")))
+    }
+
+    fn package_exists(&self, package: &P) -> bool {
+        !self.answers.missing_packages.contains(package)
+    }
+
+    fn get_dependencies(
+        &self,
+        package: &P,
+        version: &VS::V,
+    ) -> Result<Dependencies<P, VS, M>, UnrecordedQuery> {
+        self.answers
+            .dependencies
+            .get(package)
+            .and_then(|versions| versions.get(version))
+            .cloned()
+            .ok_or_else(|| UnrecordedQuery(format!("dependencies of {package} {version}")))
+    }
+
+    fn should_cancel(&self) -> Result<(), UnrecordedQuery> {
+        match self.unrecorded.take() {
+            Some(err) => Err(err),
+            None => Ok(()),
+        }
+    }
+}
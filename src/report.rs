@@ -29,6 +29,13 @@ pub trait Reporter<P: Package, VS: VersionSet, M: Eq + Clone + Debug + Display>
 /// Derivation tree resulting in the impossibility
 /// to solve the dependencies of our root package.
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[cfg_attr(
+    feature = "serde",
+    serde(bound(
+        serialize = "P: serde::Serialize, VS: serde::Serialize, VS::V: serde::Serialize, M: serde::Serialize"
+    ))
+)]
 pub enum DerivationTree<P: Package, VS: VersionSet, M: Eq + Clone + Debug + Display> {
     /// External incompatibility.
     External(External<P, VS, M>),
@@ -39,11 +46,21 @@ pub enum DerivationTree<P: Package, VS: VersionSet, M: Eq + Clone + Debug + Disp
 /// Incompatibilities that are not derived from others,
 /// they have their own reason.
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[cfg_attr(
+    feature = "serde",
+    serde(bound(
+        serialize = "P: serde::Serialize, VS: serde::Serialize, VS::V: serde::Serialize, M: serde::Serialize"
+    ))
+)]
 pub enum External<P: Package, VS: VersionSet, M: Eq + Clone + Debug + Display> {
     /// Initial incompatibility aiming at picking the root package for the first decision.
     NotRoot(P, VS::V),
     /// There are no versions in the given set for this package.
     NoVersions(P, VS),
+    /// The package does not exist at all,
+    /// as told by [package_exists](crate::DependencyProvider::package_exists).
+    PackageNotFound(P),
     /// Incompatibility coming from the dependencies of a given package.
     FromDependencyOf(P, VS, P, VS),
     /// The package is unusable for reasons outside pubgrub.
@@ -52,6 +69,13 @@ pub enum External<P: Package, VS: VersionSet, M: Eq + Clone + Debug + Display> {
 
 /// Incompatibility derived from two others.
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[cfg_attr(
+    feature = "serde",
+    serde(bound(
+        serialize = "P: serde::Serialize, VS: serde::Serialize, VS::V: serde::Serialize, M: serde::Serialize"
+    ))
+)]
 pub struct Derived<P: Package, VS: VersionSet, M: Eq + Clone + Debug + Display> {
     /// Terms of the incompatibility.
     pub terms: Map<P, Term<VS>>,
@@ -78,6 +102,7 @@ impl<P: Package, VS: VersionSet, M: Eq + Clone + Debug + Display> DerivationTree
                     packages.insert(p2);
                 }
                 External::NoVersions(p, _)
+                | External::PackageNotFound(p)
                 | External::NotRoot(p, _)
                 | External::Custom(p, _, _) => {
                     packages.insert(p);
@@ -97,6 +122,8 @@ impl<P: Package, VS: VersionSet, M: Eq + Clone + Debug + Display> DerivationTree
     /// Merge the [NoVersions](External::NoVersions) external incompatibilities
     /// with the other one they are matched with
     /// in a derived incompatibility.
+    /// A [PackageNotFound](External::PackageNotFound) incompatibility is merged the same way,
+    /// as there are no versions of the package at all.
     /// This cleans up quite nicely the generated report.
     /// You might want to do this if you know that the
     /// [DependencyProvider](crate::solver::DependencyProvider)
@@ -106,44 +133,184 @@ impl<P: Package, VS: VersionSet, M: Eq + Clone + Debug + Display> DerivationTree
         match self {
             DerivationTree::External(_) => {}
             DerivationTree::Derived(derived) => {
-                match (
-                    Arc::make_mut(&mut derived.cause1),
-                    Arc::make_mut(&mut derived.cause2),
-                ) {
-                    (DerivationTree::External(External::NoVersions(p, r)), ref mut cause2) => {
-                        cause2.collapse_no_versions();
-                        *self = cause2
-                            .clone()
-                            .merge_no_versions(p.to_owned(), r.to_owned())
-                            .unwrap_or_else(|| self.to_owned());
-                    }
-                    (ref mut cause1, DerivationTree::External(External::NoVersions(p, r))) => {
-                        cause1.collapse_no_versions();
-                        *self = cause1
-                            .clone()
-                            .merge_no_versions(p.to_owned(), r.to_owned())
-                            .unwrap_or_else(|| self.to_owned());
-                    }
-                    _ => {
-                        Arc::make_mut(&mut derived.cause1).collapse_no_versions();
-                        Arc::make_mut(&mut derived.cause2).collapse_no_versions();
-                    }
+                let cause1 = Arc::make_mut(&mut derived.cause1);
+                let cause2 = Arc::make_mut(&mut derived.cause2);
+                if let Some((p, r)) = cause1.no_versions() {
+                    cause2.collapse_no_versions();
+                    *self = cause2
+                        .clone()
+                        .merge_no_versions(p, r)
+                        .unwrap_or_else(|| self.to_owned());
+                } else if let Some((p, r)) = cause2.no_versions() {
+                    cause1.collapse_no_versions();
+                    *self = cause1
+                        .clone()
+                        .merge_no_versions(p, r)
+                        .unwrap_or_else(|| self.to_owned());
+                } else {
+                    cause1.collapse_no_versions();
+                    cause2.collapse_no_versions();
+                }
+            }
+        }
+    }
+
+    /// The package and versions of an external incompatibility saying there are no such versions.
+    fn no_versions(&self) -> Option<(P, VS)> {
+        match self {
+            DerivationTree::External(External::NoVersions(p, r)) => Some((p.clone(), r.clone())),
+            DerivationTree::External(External::PackageNotFound(p)) => Some((p.clone(), VS::full())),
+            _ => None,
+        }
+    }
+
+    /// Merge the [FromDependencyOf](External::FromDependencyOf) external incompatibilities
+    /// of different versions of the same package, with the same dependency,
+    /// when they follow each other along an explanation chain.
+    ///
+    /// Like [collapse_no_versions](Self::collapse_no_versions),
+    /// this removes repetitive lines from the generated report:
+    /// "a 1 depends on b 1" and "a 2 depends on b 1" become "a 1 | 2 depends on b 1".
+    /// Such repetitions typically appear after collapsing the
+    /// [NoVersions](External::NoVersions) incompatibilities,
+    /// since the solver already merges them when it is sound to do so.
+    ///
+    /// A derived incompatibility with a dependency as one cause,
+    /// and as other cause a derived incompatibility whose own causes include a matching dependency,
+    /// skips the intermediate incompatibility and keeps the union of both dependencies.
+    /// A derived incompatibility whose two causes are matching dependencies
+    /// is only replaced by their union when it states exactly the same terms.
+    pub fn merge_dependents(&mut self) {
+        let DerivationTree::Derived(derived) = self else {
+            return;
+        };
+        Arc::make_mut(&mut derived.cause1).merge_dependents();
+        Arc::make_mut(&mut derived.cause2).merge_dependents();
+        if let Some(merged) = Self::merge_dependencies(&derived.cause1, &derived.cause2) {
+            if let External::FromDependencyOf(p, set, dep, dep_set) = &merged {
+                let mut terms = Map::from_iter([(p.clone(), Term::Positive(set.clone()))]);
+                if dep_set != &VS::empty() {
+                    terms.insert(dep.clone(), Term::Negative(dep_set.clone()));
                 }
+                if terms == derived.terms {
+                    *self = DerivationTree::External(merged);
+                }
+            }
+            return;
+        }
+        while let Some((other_cause, merged)) = Self::merge_along_chain(derived) {
+            derived.cause1 = other_cause;
+            derived.cause2 = Arc::new(DerivationTree::External(merged));
+        }
+    }
+
+    /// The union of two dependencies of the same package on the same dependency set.
+    fn merge_dependencies(cause1: &Self, cause2: &Self) -> Option<External<P, VS, M>> {
+        match (cause1, cause2) {
+            (
+                DerivationTree::External(External::FromDependencyOf(p1, set1, dep1, dep_set1)),
+                DerivationTree::External(External::FromDependencyOf(p2, set2, dep2, dep_set2)),
+            ) if p1 == p2 && dep1 == dep2 && dep_set1 == dep_set2 => {
+                Some(External::FromDependencyOf(
+                    p1.clone(),
+                    set1.union(set2),
+                    dep1.clone(),
+                    dep_set1.clone(),
+                ))
+            }
+            _ => None,
+        }
+    }
+
+    /// Find a dependency cause of `derived` matching a dependency cause of its derived cause,
+    /// returning the other cause of the latter with the union of both dependencies.
+    fn merge_along_chain(derived: &Derived<P, VS, M>) -> Option<(Arc<Self>, External<P, VS, M>)> {
+        [
+            (&derived.cause1, &derived.cause2),
+            (&derived.cause2, &derived.cause1),
+        ]
+        .into_iter()
+        .find_map(|(chain, dependency)| match chain.as_ref() {
+            // A shared incompatibility is referred to from elsewhere in the report,
+            // so it cannot be skipped.
+            DerivationTree::Derived(inner) if inner.shared_id.is_none() => [
+                (&inner.cause1, &inner.cause2),
+                (&inner.cause2, &inner.cause1),
+            ]
+            .into_iter()
+            .find_map(|(inner_dependency, other_cause)| {
+                let merged = Self::merge_dependencies(inner_dependency, dependency)?;
+                Some((Arc::clone(other_cause), merged))
+            }),
+            _ => None,
+        })
+    }
+
+    /// Remove the [NotRoot](External::NotRoot) external incompatibilities of the root package,
+    /// replacing the derived incompatibility they are a cause of by its other cause.
+    ///
+    /// They only state that the root version is the one being solved,
+    /// so this removes the "we are solving dependencies of root 1" line from the report,
+    /// which ends with the root package being forbidden instead.
+    /// A derived incompatibility with two [NotRoot](External::NotRoot) causes is kept as is.
+    pub fn strip_root(&mut self, root: &P) {
+        let DerivationTree::Derived(derived) = self else {
+            return;
+        };
+        let is_not_root = |cause: &Self| matches!(cause, DerivationTree::External(External::NotRoot(p, _)) if p == root);
+        let other_cause = match (is_not_root(&derived.cause1), is_not_root(&derived.cause2)) {
+            (true, false) => Some(derived.cause2.as_ref().clone()),
+            (false, true) => Some(derived.cause1.as_ref().clone()),
+            (true, true) | (false, false) => None,
+        };
+        match other_cause {
+            Some(cause) => {
+                *self = cause;
+                self.strip_root(root);
+            }
+            None => {
+                Arc::make_mut(&mut derived.cause1).strip_root(root);
+                Arc::make_mut(&mut derived.cause2).strip_root(root);
             }
         }
     }
 
     fn merge_no_versions(self, package: P, set: VS) -> Option<Self> {
         match self {
-            // TODO: take care of the Derived case.
-            // Once done, we can remove the Option.
-            DerivationTree::Derived(_) => Some(self),
+            // The union is pushed into the term of the derived incompatibility
+            // and into every cause that this term came from,
+            // so that the derivation stays consistent all the way down to the external leaves.
+            DerivationTree::Derived(mut derived) => {
+                let term = derived
+                    .terms
+                    .get(&package)?
+                    .union(&Term::Positive(set.clone()));
+                for cause in [&mut derived.cause1, &mut derived.cause2] {
+                    if cause.has_term_for(&package) {
+                        let merged = cause
+                            .as_ref()
+                            .clone()
+                            .merge_no_versions(package.clone(), set.clone())?;
+                        *cause = Arc::new(merged);
+                    }
+                }
+                if term.is_any() {
+                    derived.terms.remove(&package);
+                } else {
+                    derived.terms.insert(package, term);
+                }
+                // The merged node is not identical anymore to its other occurrences in the tree.
+                derived.shared_id = None;
+                Some(DerivationTree::Derived(derived))
+            }
             DerivationTree::External(External::NotRoot(_, _)) => {
                 panic!("How did we end up with a NoVersions merged with a NotRoot?")
             }
             //
             // Cannot be merged because the reason may not match
-            DerivationTree::External(External::NoVersions(_, _)) => None,
+            DerivationTree::External(External::NoVersions(_, _) | External::PackageNotFound(_)) => {
+                None
+            }
             DerivationTree::External(External::FromDependencyOf(p1, r1, p2, r2)) => {
                 if p1 == package {
                     Some(DerivationTree::External(External::FromDependencyOf(
@@ -165,48 +332,186 @@ impl<P: Package, VS: VersionSet, M: Eq + Clone + Debug + Display> DerivationTree
             DerivationTree::External(External::Custom(_, _, _)) => None,
         }
     }
-}
 
-impl<P: Package, VS: VersionSet, M: Eq + Clone + Debug + Display> Display for External<P, VS, M> {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        match self {
-            Self::NotRoot(package, version) => {
-                write!(f, "we are solving dependencies of {} {}", package, version)
-            }
-            Self::NoVersions(package, set) => {
-                if set == &VS::full() {
-                    write!(f, "there is no available version for {}", package)
-                } else {
-                    write!(f, "there is no version of {} in {}", package, set)
+    /// Visit every node of the tree in depth-first order,
+    /// calling `f` on each derived incompatibility before its causes.
+    ///
+    /// Shared incompatibilities (with a [shared_id](Derived::shared_id))
+    /// and their causes are only visited once.
+    pub fn visit<'a>(&'a self, mut f: impl FnMut(&'a Self)) {
+        self.visit_helper(&mut f, &mut Set::default());
+    }
+
+    fn visit_helper<'a>(&'a self, f: &mut impl FnMut(&'a Self), visited: &mut Set<usize>) {
+        if let Self::Derived(derived) = self {
+            if let Some(id) = derived.shared_id {
+                if !visited.insert(id) {
+                    return;
                 }
             }
-            Self::Custom(package, set, metadata) => {
-                if set == &VS::full() {
-                    write!(
-                        f,
-                        "dependencies of {} are unavailable {}",
-                        package, metadata
-                    )
-                } else {
-                    write!(
-                        f,
-                        "dependencies of {} at version {} are unavailable {}",
-                        package, set, metadata
-                    )
+        }
+        f(self);
+        if let Self::Derived(derived) = self {
+            derived.cause1.visit_helper(f, visited);
+            derived.cause2.visit_helper(f, visited);
+        }
+    }
+
+    /// Iterate over the external incompatibilities at the leaves of the tree,
+    /// in depth-first order.
+    ///
+    /// The leaves of shared incompatibilities are only yielded once.
+    pub fn externals(&self) -> impl Iterator<Item = &External<P, VS, M>> {
+        let mut externals = Vec::new();
+        self.visit(|node| {
+            if let Self::External(external) = node {
+                externals.push(external);
+            }
+        });
+        externals.into_iter()
+    }
+
+    /// Constraints of the root package on its direct dependencies that are part of the conflict.
+    ///
+    /// They are the dependencies of the [FromDependencyOf](External::FromDependencyOf)
+    /// external incompatibilities of `root`, in depth-first order of their first occurrence,
+    /// with the version sets of the same package merged into their union.
+    pub fn root_causes(&self, root: &P) -> Vec<(P, VS)> {
+        let mut causes: Vec<(P, VS)> = Vec::new();
+        for external in self.externals() {
+            if let External::FromDependencyOf(p, _, dep, set) = external {
+                if p != root {
+                    continue;
+                }
+                match causes.iter_mut().find(|(p, _)| p == dep) {
+                    Some((_, existing)) => *existing = existing.union(set),
+                    None => causes.push((dep.clone(), set.clone())),
                 }
             }
-            Self::FromDependencyOf(p, set_p, dep, set_dep) => {
-                if set_p == &VS::full() && set_dep == &VS::full() {
-                    write!(f, "{} depends on {}", p, dep)
-                } else if set_p == &VS::full() {
-                    write!(f, "{} depends on {} {}", p, dep, set_dep)
-                } else if set_dep == &VS::full() {
-                    write!(f, "{} {} depends on {}", p, set_p, dep)
-                } else {
-                    write!(f, "{} {} depends on {} {}", p, set_p, dep, set_dep)
+        }
+        causes
+    }
+
+    /// Packages whose dependencies are [unavailable](crate::Dependencies::Unavailable)
+    /// in the conflict, with their version set and the reason given by the provider.
+    ///
+    /// They are the [Custom](External::Custom) external incompatibilities, in depth-first order.
+    /// Unlike missing versions, they are not necessarily a hard conflict:
+    /// with an offline cache for example, they may be cache misses worth fetching.
+    pub fn unavailable_packages(&self) -> Vec<(&P, &VS, &M)> {
+        self.externals()
+            .filter_map(|external| match external {
+                External::Custom(package, set, reason) => Some((package, set, reason)),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Length of the longest path from the root to a leaf, counting both ends.
+    ///
+    /// A tree made of a single external incompatibility has a depth of 1.
+    pub fn depth(&self) -> usize {
+        match self {
+            Self::External(_) => 1,
+            Self::Derived(derived) => 1 + derived.cause1.depth().max(derived.cause2.depth()),
+        }
+    }
+
+    /// Number of incompatibilities in the tree.
+    ///
+    /// Shared incompatibilities (with a [shared_id](Derived::shared_id))
+    /// and their causes are only counted once.
+    pub fn node_count(&self) -> usize {
+        let mut count = 0;
+        self.visit(|_| count += 1);
+        count
+    }
+
+    /// Export the derivation tree in the [Graphviz](https://graphviz.org/) DOT format.
+    ///
+    /// There is one node per incompatibility, with an edge from each derived incompatibility
+    /// to both of its causes.
+    /// External incompatibilities are labeled with their explanation,
+    /// and derived incompatibilities with their terms.
+    /// Shared incompatibilities (with a [shared_id](Derived::shared_id))
+    /// are rendered as a single node with multiple incoming edges.
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::from("digraph derivation_tree {\n");
+        let mut shared_nodes = Map::default();
+        let mut node_count = 0;
+        self.write_dot_node(&mut dot, &mut shared_nodes, &mut node_count);
+        dot.push_str("}\n");
+        dot
+    }
+
+    /// Write this node and its causes in DOT format, and return the id of this node.
+    fn write_dot_node(
+        &self,
+        dot: &mut String,
+        shared_nodes: &mut Map<usize, usize>,
+        node_count: &mut usize,
+    ) -> usize {
+        let escape = |label: String| label.replace('\\', "\\\\").replace('"', "\\\"");
+        let node = *node_count;
+        match self {
+            Self::External(external) => {
+                *node_count += 1;
+                let label = escape(DefaultStringReportFormatter.format_external(external));
+                dot.push_str(&format!(
+                    "    n{} [shape=box, label=\"{}\"];\n",
+                    node, label
+                ));
+            }
+            Self::Derived(derived) => {
+                if let Some(id) = derived.shared_id {
+                    if let Some(&shared_node) = shared_nodes.get(&id) {
+                        return shared_node;
+                    }
+                    shared_nodes.insert(id, node);
+                }
+                *node_count += 1;
+                let label = escape(ReportFormatter::<P, VS, M>::format_terms(
+                    &DefaultStringReportFormatter,
+                    &derived.terms,
+                ));
+                dot.push_str(&format!("    n{} [label=\"{}\"];\n", node, label));
+                for cause in [&derived.cause1, &derived.cause2] {
+                    let cause_node = cause.write_dot_node(dot, shared_nodes, node_count);
+                    dot.push_str(&format!("    n{} -> n{};\n", node, cause_node));
                 }
             }
         }
+        node
+    }
+
+    /// Whether the incompatibility at the root of this tree has a term for that package.
+    fn has_term_for(&self, package: &P) -> bool {
+        match self {
+            DerivationTree::External(External::FromDependencyOf(p1, _, p2, _)) => {
+                p1 == package || p2 == package
+            }
+            DerivationTree::External(
+                External::NotRoot(p, _)
+                | External::NoVersions(p, _)
+                | External::PackageNotFound(p)
+                | External::Custom(p, _, _),
+            ) => p == package,
+            DerivationTree::Derived(derived) => derived.terms.contains_key(package),
+        }
+    }
+}
+
+/// Export a derivation tree in the [Graphviz](https://graphviz.org/) DOT format,
+/// same as [DerivationTree::to_dot].
+pub fn to_dot<P: Package, VS: VersionSet, M: Eq + Clone + Debug + Display>(
+    tree: &DerivationTree<P, VS, M>,
+) -> String {
+    tree.to_dot()
+}
+
+impl<P: Package, VS: VersionSet, M: Eq + Clone + Debug + Display> Display for External<P, VS, M> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&DefaultStringReportFormatter.format_external(self))
     }
 }
 
@@ -219,6 +524,10 @@ pub trait ReportFormatter<P: Package, VS: VersionSet, M: Eq + Clone + Debug + Di
     fn format_external(&self, external: &External<P, VS, M>) -> Self::Output;
 
     /// Format terms of an incompatibility.
+    ///
+    /// There is a single term per package: constraints on the same package
+    /// are combined with [Term::intersection] when the incompatibility is built,
+    /// so each package is rendered in a single phrase without any merging.
     fn format_terms(&self, terms: &Map<P, Term<VS>>) -> Self::Output;
 
     /// Simplest case, we just combine two external incompatibilities.
@@ -274,26 +583,178 @@ pub trait ReportFormatter<P: Package, VS: VersionSet, M: Eq + Clone + Debug + Di
     ) -> Self::Output;
 }
 
-/// Default formatter for the default reporter.
-#[derive(Default, Debug)]
-pub struct DefaultStringReportFormatter;
+/// Phrases used to build a [String] report.
+///
+/// Every type implementing this trait is a [ReportFormatter] with a [String] output.
+/// The default implementations are in English,
+/// so a localized formatter only needs to override the phrases.
+/// Packages, versions and version sets are already formatted when given to these methods.
+pub trait ReportPhrases {
+    /// The first segments of a version set, already displayed,
+    /// followed by the number of segments left out,
+    /// used by the [AbbreviatingReportFormatter].
+    fn abbreviated_set_phrase(&self, set: &str, more: usize) -> String {
+        match more {
+            1 => format!("{} (and 1 more range)", set),
+            _ => format!("{} (and {} more ranges)", set, more),
+        }
+    }
+
+    /// The root package at that version must be chosen.
+    fn not_root_phrase(&self, package: &str, version: &str) -> String {
+        format!("we are solving dependencies of {} {}", package, version)
+    }
+
+    /// There is no version of the package in the set,
+    /// or no version at all if the set is `None`.
+    fn no_version_phrase(&self, package: &str, set: Option<&str>) -> String {
+        match set {
+            None => format!("there is no available version for {}", package),
+            Some(set) => format!("there is no version of {} in {}", package, set),
+        }
+    }
+
+    /// The package does not exist at all.
+    fn package_not_found_phrase(&self, package: &str) -> String {
+        format!("package {} does not exist", package)
+    }
+
+    /// The dependencies of the package in the set are unavailable,
+    /// for all versions if the set is `None`.
+    fn unavailable_phrase(&self, package: &str, set: Option<&str>, reason: &str) -> String {
+        match set {
+            None => format!("dependencies of {} are unavailable {}", package, reason),
+            Some(set) => format!(
+                "dependencies of {} at version {} are unavailable {}",
+                package, set, reason
+            ),
+        }
+    }
+
+    /// A package depends on another, both possibly followed by their version set.
+    fn depends_on_phrase(&self, package: &str, dependency: &str) -> String {
+        format!("{} depends on {}", package, dependency)
+    }
+
+    /// A package depends on an empty set of versions of another,
+    /// usually because it requires incompatible versions of it.
+    fn conflicting_requirements_phrase(&self, package: &str, dependency: &str) -> String {
+        format!(
+            "{} has conflicting requirements on {}, no version can satisfy all of them",
+            package, dependency
+        )
+    }
+
+    /// Conclusion when there are no terms left.
+    fn version_solving_failed_phrase(&self) -> String {
+        "version solving failed".into()
+    }
+
+    /// A negative term among several ones, the package not being in the set.
+    fn negated_term_phrase(&self, package: &str, set: &str) -> String {
+        format!("{} not ( {} )", package, set)
+    }
+
+    /// A single positive term.
+    fn forbidden_phrase(&self, term: &str) -> String {
+        format!("{} is forbidden", term)
+    }
+
+    /// A single negative term.
+    fn mandatory_phrase(&self, term: &str) -> String {
+        format!("{} is mandatory", term)
+    }
 
-impl<P: Package, VS: VersionSet, M: Eq + Clone + Debug + Display> ReportFormatter<P, VS, M>
-    for DefaultStringReportFormatter
+    /// Any other combination of terms.
+    fn incompatible_phrase(&self, terms: &[String]) -> String {
+        match terms.split_last() {
+            Some((last, rest)) if !rest.is_empty() => {
+                format!("{} and {} are incompatible", rest.join(", "), last)
+            }
+            _ => terms.join(", ") + " are incompatible",
+        }
+    }
+
+    /// Two causes explained together.
+    fn and_phrase(&self, cause1: &str, cause2: &str) -> String {
+        format!("{} and {}", cause1, cause2)
+    }
+
+    /// First sentence of a chain of explanations.
+    fn because_phrase(&self, cause: &str, conclusion: &str) -> String {
+        format!("Because {}, {}.", cause, conclusion)
+    }
+
+    /// Following sentences of a chain of explanations.
+    fn and_because_phrase(&self, cause: &str, conclusion: &str) -> String {
+        format!("And because {}, {}.", cause, conclusion)
+    }
+}
+
+/// [ReportFormatter] building a [String] report from some [ReportPhrases],
+/// with version sets displayed by `set_phrase`.
+///
+/// It is shared by the formatters displaying version sets in full or abbreviated.
+struct PhrasesFormatter<'a, T, F> {
+    phrases: &'a T,
+    set_phrase: F,
+}
+
+impl<P, VS, M, T, F> ReportFormatter<P, VS, M> for PhrasesFormatter<'_, T, F>
+where
+    P: Package,
+    VS: VersionSet,
+    M: Eq + Clone + Debug + Display,
+    T: ReportPhrases,
+    F: Fn(&VS) -> String,
 {
     type Output = String;
 
     fn format_external(&self, external: &External<P, VS, M>) -> String {
-        external.to_string()
+        let optional_set = |set: &VS| (set != &VS::full()).then(|| (self.set_phrase)(set));
+        let with_set = |package: &P, set: &VS| match optional_set(set) {
+            None => package.to_string(),
+            Some(set) => format!("{} {}", package, set),
+        };
+        match external {
+            External::NotRoot(package, version) => self
+                .phrases
+                .not_root_phrase(&package.to_string(), &version.to_string()),
+            External::NoVersions(package, set) => self
+                .phrases
+                .no_version_phrase(&package.to_string(), optional_set(set).as_deref()),
+            External::PackageNotFound(package) => {
+                self.phrases.package_not_found_phrase(&package.to_string())
+            }
+            External::Custom(package, set, metadata) => self.phrases.unavailable_phrase(
+                &package.to_string(),
+                optional_set(set).as_deref(),
+                &metadata.to_string(),
+            ),
+            External::FromDependencyOf(p, set_p, dep, set_dep) if set_dep == &VS::empty() => self
+                .phrases
+                .conflicting_requirements_phrase(&with_set(p, set_p), &dep.to_string()),
+            External::FromDependencyOf(p, set_p, dep, set_dep) => self
+                .phrases
+                .depends_on_phrase(&with_set(p, set_p), &with_set(dep, set_dep)),
+        }
     }
 
     fn format_terms(&self, terms: &Map<P, Term<VS>>) -> Self::Output {
-        let terms_vec: Vec<_> = terms.iter().collect();
+        // Sorted by package name, to not depend on the iteration order of the map.
+        let mut terms_vec: Vec<_> = terms.iter().collect();
+        terms_vec.sort_by_cached_key(|(p, _)| p.to_string());
         match terms_vec.as_slice() {
-            [] => "version solving failed".into(),
+            [] => self.phrases.version_solving_failed_phrase(),
             // TODO: special case when that unique package is root.
-            [(package, Term::Positive(range))] => format!("{} {} is forbidden", package, range),
-            [(package, Term::Negative(range))] => format!("{} {} is mandatory", package, range),
+            [(package, Term::Positive(range))] => {
+                self.phrases
+                    .forbidden_phrase(&format!("{} {}", package, (self.set_phrase)(range)))
+            }
+            [(package, Term::Negative(range))] => {
+                self.phrases
+                    .mandatory_phrase(&format!("{} {}", package, (self.set_phrase)(range)))
+            }
             [(p1, Term::Positive(r1)), (p2, Term::Negative(r2))] => self.format_external(
                 &External::<_, _, M>::FromDependencyOf(p1, r1.clone(), p2, r2.clone()),
             ),
@@ -301,8 +762,22 @@ impl<P: Package, VS: VersionSet, M: Eq + Clone + Debug + Display> ReportFormatte
                 &External::<_, _, M>::FromDependencyOf(p2, r2.clone(), p1, r1.clone()),
             ),
             slice => {
-                let str_terms: Vec<_> = slice.iter().map(|(p, t)| format!("{} {}", p, t)).collect();
-                str_terms.join(", ") + " are incompatible"
+                // Positive terms first, so that the negated ones read as exceptions.
+                let (positive, negative): (Vec<_>, Vec<_>) =
+                    slice.iter().partition(|(_, t)| t.is_positive());
+                let str_terms: Vec<_> = positive
+                    .iter()
+                    .chain(negative.iter())
+                    .map(|(p, t)| match t {
+                        Term::Positive(set) => {
+                            format!("{} {}", p, (self.set_phrase)(set))
+                        }
+                        Term::Negative(set) => self
+                            .phrases
+                            .negated_term_phrase(&p.to_string(), &(self.set_phrase)(set)),
+                    })
+                    .collect();
+                self.phrases.incompatible_phrase(&str_terms)
             }
         }
     }
@@ -315,11 +790,12 @@ impl<P: Package, VS: VersionSet, M: Eq + Clone + Debug + Display> ReportFormatte
         current_terms: &Map<P, Term<VS>>,
     ) -> String {
         // TODO: order should be chosen to make it more logical.
-        format!(
-            "Because {} and {}, {}.",
-            self.format_external(external1),
-            self.format_external(external2),
-            ReportFormatter::<P, VS, M>::format_terms(self, current_terms)
+        self.phrases.because_phrase(
+            &self.phrases.and_phrase(
+                &self.format_external(external1),
+                &self.format_external(external2),
+            ),
+            &self.format_conclusion(&[external1, external2], current_terms),
         )
     }
 
@@ -333,13 +809,20 @@ impl<P: Package, VS: VersionSet, M: Eq + Clone + Debug + Display> ReportFormatte
         current_terms: &Map<P, Term<VS>>,
     ) -> String {
         // TODO: order should be chosen to make it more logical.
-        format!(
-            "Because {} ({}) and {} ({}), {}.",
-            ReportFormatter::<P, VS, M>::format_terms(self, &derived1.terms),
-            ref_id1,
-            ReportFormatter::<P, VS, M>::format_terms(self, &derived2.terms),
-            ref_id2,
-            ReportFormatter::<P, VS, M>::format_terms(self, current_terms)
+        self.phrases.because_phrase(
+            &self.phrases.and_phrase(
+                &format!(
+                    "{} ({})",
+                    ReportFormatter::<P, VS, M>::format_terms(self, &derived1.terms),
+                    ref_id1
+                ),
+                &format!(
+                    "{} ({})",
+                    ReportFormatter::<P, VS, M>::format_terms(self, &derived2.terms),
+                    ref_id2
+                ),
+            ),
+            &ReportFormatter::<P, VS, M>::format_terms(self, current_terms),
         )
     }
 
@@ -354,12 +837,16 @@ impl<P: Package, VS: VersionSet, M: Eq + Clone + Debug + Display> ReportFormatte
         current_terms: &Map<P, Term<VS>>,
     ) -> String {
         // TODO: order should be chosen to make it more logical.
-        format!(
-            "Because {} ({}) and {}, {}.",
-            ReportFormatter::<P, VS, M>::format_terms(self, &derived.terms),
-            ref_id,
-            self.format_external(external),
-            ReportFormatter::<P, VS, M>::format_terms(self, current_terms)
+        self.phrases.because_phrase(
+            &self.phrases.and_phrase(
+                &format!(
+                    "{} ({})",
+                    ReportFormatter::<P, VS, M>::format_terms(self, &derived.terms),
+                    ref_id
+                ),
+                &self.format_external(external),
+            ),
+            &self.format_conclusion(&[external], current_terms),
         )
     }
 
@@ -369,10 +856,9 @@ impl<P: Package, VS: VersionSet, M: Eq + Clone + Debug + Display> ReportFormatte
         external: &External<P, VS, M>,
         current_terms: &Map<P, Term<VS>>,
     ) -> String {
-        format!(
-            "And because {}, {}.",
-            self.format_external(external),
-            ReportFormatter::<P, VS, M>::format_terms(self, current_terms)
+        self.phrases.and_because_phrase(
+            &self.format_external(external),
+            &self.format_conclusion(&[external], current_terms),
         )
     }
 
@@ -383,11 +869,13 @@ impl<P: Package, VS: VersionSet, M: Eq + Clone + Debug + Display> ReportFormatte
         derived: &Derived<P, VS, M>,
         current_terms: &Map<P, Term<VS>>,
     ) -> String {
-        format!(
-            "And because {} ({}), {}.",
-            ReportFormatter::<P, VS, M>::format_terms(self, &derived.terms),
-            ref_id,
-            ReportFormatter::<P, VS, M>::format_terms(self, current_terms)
+        self.phrases.and_because_phrase(
+            &format!(
+                "{} ({})",
+                ReportFormatter::<P, VS, M>::format_terms(self, &derived.terms),
+                ref_id
+            ),
+            &ReportFormatter::<P, VS, M>::format_terms(self, current_terms),
         )
     }
 
@@ -398,15 +886,232 @@ impl<P: Package, VS: VersionSet, M: Eq + Clone + Debug + Display> ReportFormatte
         external: &External<P, VS, M>,
         current_terms: &Map<P, Term<VS>>,
     ) -> String {
-        format!(
-            "And because {} and {}, {}.",
-            self.format_external(prior_external),
-            self.format_external(external),
-            ReportFormatter::<P, VS, M>::format_terms(self, current_terms)
+        self.phrases.and_because_phrase(
+            &self.phrases.and_phrase(
+                &self.format_external(prior_external),
+                &self.format_external(external),
+            ),
+            &self.format_conclusion(&[prior_external, external], current_terms),
         )
     }
 }
 
+impl<T: ReportPhrases, F> PhrasesFormatter<'_, T, F> {
+    /// Format the terms concluding an explanation.
+    ///
+    /// When they only forbid a package made unavailable by one of the external causes,
+    /// the reason of that [Custom](External::Custom) incompatibility is kept in the conclusion.
+    fn format_conclusion<P, VS, M>(
+        &self,
+        externals: &[&External<P, VS, M>],
+        current_terms: &Map<P, Term<VS>>,
+    ) -> String
+    where
+        P: Package,
+        VS: VersionSet,
+        M: Eq + Clone + Debug + Display,
+        F: Fn(&VS) -> String,
+    {
+        let terms_vec: Vec<_> = current_terms.iter().collect();
+        if let [(package, Term::Positive(set))] = terms_vec.as_slice() {
+            let reason = externals.iter().find_map(|external| match external {
+                External::Custom(p, _, reason) if &p == package => Some(reason),
+                _ => None,
+            });
+            if let Some(reason) = reason {
+                let set = (set != &VS::full()).then(|| (self.set_phrase)(set));
+                return self.phrases.unavailable_phrase(
+                    &package.to_string(),
+                    set.as_deref(),
+                    &reason.to_string(),
+                );
+            }
+        }
+        ReportFormatter::<P, VS, M>::format_terms(self, current_terms)
+    }
+}
+
+/// Implements [ReportFormatter] by forwarding every method
+/// to the [PhrasesFormatter] returned by the given function of `&self`.
+macro_rules! forward_to_phrases_formatter {
+    ($phrases_formatter:expr) => {
+        type Output = String;
+
+        fn format_external(&self, external: &External<P, VS, M>) -> String {
+            ReportFormatter::<P, VS, M>::format_external(&$phrases_formatter(self), external)
+        }
+
+        fn format_terms(&self, terms: &Map<P, Term<VS>>) -> String {
+            ReportFormatter::<P, VS, M>::format_terms(&$phrases_formatter(self), terms)
+        }
+
+        fn explain_both_external(
+            &self,
+            external1: &External<P, VS, M>,
+            external2: &External<P, VS, M>,
+            current_terms: &Map<P, Term<VS>>,
+        ) -> String {
+            ReportFormatter::<P, VS, M>::explain_both_external(
+                &$phrases_formatter(self),
+                external1,
+                external2,
+                current_terms,
+            )
+        }
+
+        fn explain_both_ref(
+            &self,
+            ref_id1: usize,
+            derived1: &Derived<P, VS, M>,
+            ref_id2: usize,
+            derived2: &Derived<P, VS, M>,
+            current_terms: &Map<P, Term<VS>>,
+        ) -> String {
+            ReportFormatter::<P, VS, M>::explain_both_ref(
+                &$phrases_formatter(self),
+                ref_id1,
+                derived1,
+                ref_id2,
+                derived2,
+                current_terms,
+            )
+        }
+
+        fn explain_ref_and_external(
+            &self,
+            ref_id: usize,
+            derived: &Derived<P, VS, M>,
+            external: &External<P, VS, M>,
+            current_terms: &Map<P, Term<VS>>,
+        ) -> String {
+            ReportFormatter::<P, VS, M>::explain_ref_and_external(
+                &$phrases_formatter(self),
+                ref_id,
+                derived,
+                external,
+                current_terms,
+            )
+        }
+
+        fn and_explain_external(
+            &self,
+            external: &External<P, VS, M>,
+            current_terms: &Map<P, Term<VS>>,
+        ) -> String {
+            ReportFormatter::<P, VS, M>::and_explain_external(
+                &$phrases_formatter(self),
+                external,
+                current_terms,
+            )
+        }
+
+        fn and_explain_ref(
+            &self,
+            ref_id: usize,
+            derived: &Derived<P, VS, M>,
+            current_terms: &Map<P, Term<VS>>,
+        ) -> String {
+            ReportFormatter::<P, VS, M>::and_explain_ref(
+                &$phrases_formatter(self),
+                ref_id,
+                derived,
+                current_terms,
+            )
+        }
+
+        fn and_explain_prior_and_external(
+            &self,
+            prior_external: &External<P, VS, M>,
+            external: &External<P, VS, M>,
+            current_terms: &Map<P, Term<VS>>,
+        ) -> String {
+            ReportFormatter::<P, VS, M>::and_explain_prior_and_external(
+                &$phrases_formatter(self),
+                prior_external,
+                external,
+                current_terms,
+            )
+        }
+    };
+}
+
+/// Every type implementing [ReportPhrases] is a [ReportFormatter],
+/// displaying version sets in full.
+impl<P, VS, M, T> ReportFormatter<P, VS, M> for T
+where
+    P: Package,
+    VS: VersionSet,
+    M: Eq + Clone + Debug + Display,
+    T: ReportPhrases,
+{
+    forward_to_phrases_formatter!(|phrases| PhrasesFormatter {
+        phrases,
+        set_phrase: VS::to_string,
+    });
+}
+
+/// Default formatter for the default reporter.
+#[derive(Default, Debug)]
+pub struct DefaultStringReportFormatter;
+
+impl DefaultStringReportFormatter {
+    /// A default formatter abbreviating the version sets with more than `max_segments` segments,
+    /// such as `>=1.0.0, <1.2.0 | >1.2.0, <1.4.0 (and 12 more ranges)`.
+    ///
+    /// At least one segment is always displayed, so a `max_segments` of 0 is the same as 1.
+    /// Only the version sets implementing [SegmentedVersionSet] can be abbreviated.
+    pub fn with_max_segments(max_segments: usize) -> AbbreviatingReportFormatter {
+        AbbreviatingReportFormatter {
+            max_segments: max_segments.max(1),
+        }
+    }
+}
+
+impl ReportPhrases for DefaultStringReportFormatter {}
+
+/// Version sets made of disjoint segments, such as a [Range](crate::Range),
+/// that the [AbbreviatingReportFormatter] can shorten.
+pub trait SegmentedVersionSet: VersionSet {
+    /// Keep the first `max_segments` segments of the set,
+    /// along with the number of segments left out.
+    fn first_segments(&self, max_segments: usize) -> (Self, usize);
+}
+
+/// Default formatter abbreviating long version sets,
+/// built with [DefaultStringReportFormatter::with_max_segments].
+#[derive(Debug, Clone, Copy)]
+pub struct AbbreviatingReportFormatter {
+    max_segments: usize,
+}
+
+impl AbbreviatingReportFormatter {
+    /// Display the version sets with more than `max_segments` segments
+    /// with [abbreviated_set_phrase](ReportPhrases::abbreviated_set_phrase).
+    fn phrases_formatter<VS: SegmentedVersionSet>(
+        &self,
+    ) -> PhrasesFormatter<'static, DefaultStringReportFormatter, impl Fn(&VS) -> String> {
+        let max_segments = self.max_segments;
+        PhrasesFormatter {
+            phrases: &DefaultStringReportFormatter,
+            set_phrase: move |set: &VS| match set.first_segments(max_segments) {
+                (_, 0) => set.to_string(),
+                (first, more) => {
+                    DefaultStringReportFormatter.abbreviated_set_phrase(&first.to_string(), more)
+                }
+            },
+        }
+    }
+}
+
+impl<P, VS, M> ReportFormatter<P, VS, M> for AbbreviatingReportFormatter
+where
+    P: Package,
+    VS: SegmentedVersionSet,
+    M: Eq + Clone + Debug + Display,
+{
+    forward_to_phrases_formatter!(Self::phrases_formatter);
+}
+
 /// Default reporter able to generate an explanation as a [String].
 pub struct DefaultStringReporter {
     /// Number of explanations already with a line reference.
@@ -648,3 +1353,484 @@ impl<P: Package, VS: VersionSet, M: Eq + Clone + Debug + Display> Reporter<P, VS
         }
     }
 }
+
+/// Reporter generating an indented outline of the derivation tree as a [String].
+///
+/// Each derived incompatibility is followed by its two causes, indented one level deeper.
+/// An incompatibility shared by multiple branches of the tree is only explained once,
+/// where it gets marked with `[N]`, and later occurrences just refer to it with `[see N]`.
+pub struct IndentedTreeReporter {
+    /// Number of shared incompatibilities already marked with a reference.
+    ref_count: usize,
+    /// Shared nodes that have already been marked with a reference.
+    /// The incompatibility ids are the keys, and the references are the values.
+    shared_with_ref: Map<usize, usize>,
+    /// Accumulated lines of the report already generated.
+    lines: Vec<String>,
+}
+
+impl IndentedTreeReporter {
+    /// Initialize the reporter.
+    fn new() -> Self {
+        Self {
+            ref_count: 0,
+            shared_with_ref: Map::default(),
+            lines: Vec::new(),
+        }
+    }
+
+    fn build_recursive<
+        P: Package,
+        VS: VersionSet,
+        M: Eq + Clone + Debug + Display,
+        F: ReportFormatter<P, VS, M, Output = String>,
+    >(
+        &mut self,
+        tree: &DerivationTree<P, VS, M>,
+        depth: usize,
+        formatter: &F,
+    ) {
+        let indent = "  ".repeat(depth);
+        match tree {
+            DerivationTree::External(external) => {
+                self.lines
+                    .push(format!("{}{}", indent, formatter.format_external(external)));
+            }
+            DerivationTree::Derived(derived) => {
+                let terms = formatter.format_terms(&derived.terms);
+                match derived.shared_id {
+                    Some(id) => {
+                        if let Some(ref_id) = self.shared_with_ref.get(&id) {
+                            // Already explained, only refer to it.
+                            self.lines
+                                .push(format!("{}{} [see {}]", indent, terms, ref_id));
+                            return;
+                        }
+                        self.ref_count += 1;
+                        self.shared_with_ref.insert(id, self.ref_count);
+                        self.lines
+                            .push(format!("{}{} [{}]", indent, terms, self.ref_count));
+                    }
+                    None => self.lines.push(format!("{}{}", indent, terms)),
+                }
+                self.build_recursive(&derived.cause1, depth + 1, formatter);
+                self.build_recursive(&derived.cause2, depth + 1, formatter);
+            }
+        }
+    }
+}
+
+impl<P: Package, VS: VersionSet, M: Eq + Clone + Debug + Display> Reporter<P, VS, M>
+    for IndentedTreeReporter
+{
+    type Output = String;
+
+    fn report(derivation_tree: &DerivationTree<P, VS, M>) -> Self::Output {
+        Self::report_with_formatter(derivation_tree, &DefaultStringReportFormatter)
+    }
+
+    fn report_with_formatter(
+        derivation_tree: &DerivationTree<P, VS, M>,
+        formatter: &impl ReportFormatter<P, VS, M, Output = Self::Output>,
+    ) -> Self::Output {
+        let mut reporter = Self::new();
+        reporter.build_recursive(derivation_tree, 0, formatter);
+        reporter.lines.join("\n")
+    }
+}
+
+/// A change to the dependencies of the root package, suggested by [SuggestionReporter].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Suggestion<P: Package, VS: VersionSet> {
+    /// Widen the constraint of the root package on `package`, from `current` to `suggested`.
+    RelaxConstraint {
+        /// The dependency of the root package.
+        package: P,
+        /// The versions allowed by the root package.
+        current: VS,
+        /// The versions allowed by the root package and required by the other packages.
+        suggested: VS,
+    },
+    /// Remove the dependency of `dependent` on `dependency`.
+    RemoveDependency {
+        /// The package depending on `dependency`.
+        dependent: P,
+        /// The dependency to remove.
+        dependency: P,
+    },
+}
+
+/// Reporter suggesting changes to the dependencies of the root package
+/// that could make the resolution succeed, instead of explaining why it failed.
+///
+/// The suggestions are heuristics based on the external incompatibilities of the tree.
+/// For each direct dependency of the root package in the conflict,
+/// the other packages depending on it in the conflict require some of its versions.
+/// If the root package allows none of them, relaxing its constraint is suggested.
+/// If the dependency has no version at all, removing it is suggested.
+///
+/// Following a suggestion does not guarantee that the resolution succeeds,
+/// another conflict may appear.
+pub struct SuggestionReporter;
+
+impl SuggestionReporter {
+    /// The root package of the resolution that failed,
+    /// forbidden by the incompatibility at the root of the tree.
+    fn root_package<P: Package, VS: VersionSet, M: Eq + Clone + Debug + Display>(
+        derivation_tree: &DerivationTree<P, VS, M>,
+    ) -> Option<&P> {
+        match derivation_tree {
+            DerivationTree::External(
+                External::NotRoot(p, _)
+                | External::FromDependencyOf(p, _, _, _)
+                | External::NoVersions(p, _)
+                | External::PackageNotFound(p)
+                | External::Custom(p, _, _),
+            ) => Some(p),
+            DerivationTree::Derived(derived) => match derived.terms.len() {
+                1 => derived.terms.keys().next(),
+                _ => None,
+            },
+        }
+    }
+}
+
+impl<P: Package, VS: VersionSet, M: Eq + Clone + Debug + Display> Reporter<P, VS, M>
+    for SuggestionReporter
+{
+    type Output = Vec<Suggestion<P, VS>>;
+
+    fn report(derivation_tree: &DerivationTree<P, VS, M>) -> Self::Output {
+        let Some(root) = Self::root_package(derivation_tree) else {
+            return Vec::new();
+        };
+        let mut suggestions = Vec::new();
+        for (package, current) in derivation_tree.root_causes(root) {
+            let no_version_at_all = derivation_tree.externals().any(|external| match external {
+                External::NoVersions(p, set) => p == &package && set == &VS::full(),
+                External::PackageNotFound(p) => p == &package,
+                _ => false,
+            });
+            if no_version_at_all {
+                suggestions.push(Suggestion::RemoveDependency {
+                    dependent: root.clone(),
+                    dependency: package,
+                });
+                continue;
+            }
+            // The versions allowed by each other dependent, for any of its versions.
+            let mut by_dependent: Vec<(&P, VS)> = Vec::new();
+            for external in derivation_tree.externals() {
+                let External::FromDependencyOf(dependent, _, dependency, set) = external else {
+                    continue;
+                };
+                if dependent == root || dependency != &package {
+                    continue;
+                }
+                match by_dependent.iter_mut().find(|(p, _)| p == &dependent) {
+                    Some((_, existing)) => *existing = existing.union(set),
+                    None => by_dependent.push((dependent, set.clone())),
+                }
+            }
+            if by_dependent.is_empty() {
+                continue;
+            }
+            // The versions allowed by all of them.
+            // If there are none, the conflict is between them and the root cannot fix it.
+            let required = by_dependent
+                .into_iter()
+                .fold(VS::full(), |acc, (_, set)| acc.intersection(&set));
+            if required != VS::empty() && current.is_disjoint(&required) {
+                suggestions.push(Suggestion::RelaxConstraint {
+                    suggested: current.union(&required),
+                    package,
+                    current,
+                });
+            }
+        }
+        suggestions
+    }
+
+    /// Suggestions are not text, so the formatter is not used.
+    fn report_with_formatter(
+        derivation_tree: &DerivationTree<P, VS, M>,
+        _formatter: &impl ReportFormatter<P, VS, M, Output = Self::Output>,
+    ) -> Self::Output {
+        Self::report(derivation_tree)
+    }
+}
+
+// TESTS #######################################################################
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{OfflineDependencyProvider, PubGrubError, Range};
+
+    type NumVS = Range<u32>;
+
+    fn dependency(
+        p1: &'static str,
+        p2: &'static str,
+    ) -> Arc<DerivationTree<&'static str, NumVS, String>> {
+        Arc::new(DerivationTree::External(External::FromDependencyOf(
+            p1,
+            NumVS::full(),
+            p2,
+            NumVS::full(),
+        )))
+    }
+
+    /// "a depends on c" is derived from "a depends on b" and "b depends on c",
+    /// and is shared by both causes of the root.
+    fn shared_tree() -> DerivationTree<&'static str, NumVS, String> {
+        let a_c = Arc::new(DerivationTree::Derived(Derived {
+            terms: Map::from_iter([
+                ("a", Term::Positive(NumVS::full())),
+                ("c", Term::Negative(NumVS::full())),
+            ]),
+            shared_id: Some(3),
+            cause1: dependency("a", "b"),
+            cause2: dependency("b", "c"),
+        }));
+        DerivationTree::Derived(Derived {
+            terms: Map::default(),
+            shared_id: None,
+            cause1: a_c.clone(),
+            cause2: a_c,
+        })
+    }
+
+    #[test]
+    fn depth_and_node_count_of_external() {
+        let tree = dependency("a", "b");
+        assert_eq!(tree.depth(), 1);
+        assert_eq!(tree.node_count(), 1);
+    }
+
+    #[test]
+    fn depth_and_node_count_of_unbalanced() {
+        // ((a -> b, b -> c), c -> d)
+        let tree = DerivationTree::Derived(Derived {
+            terms: Map::default(),
+            shared_id: None,
+            cause1: Arc::new(DerivationTree::Derived(Derived {
+                terms: Map::default(),
+                shared_id: None,
+                cause1: dependency("a", "b"),
+                cause2: dependency("b", "c"),
+            })),
+            cause2: dependency("c", "d"),
+        });
+        assert_eq!(tree.depth(), 3);
+        assert_eq!(tree.node_count(), 5);
+    }
+
+    #[test]
+    fn depth_and_node_count_of_shared() {
+        let tree = shared_tree();
+        assert_eq!(tree.depth(), 3);
+        // The shared node and its two causes are only counted once.
+        assert_eq!(tree.node_count(), 4);
+    }
+
+    #[test]
+    fn to_dot() {
+        let dot = shared_tree().to_dot();
+        assert_eq!(super::to_dot(&shared_tree()), dot);
+        assert_eq!(
+            dot,
+            r#"digraph derivation_tree {
+    n0 [label="version solving failed"];
+    n1 [label="a depends on c"];
+    n2 [shape=box, label="a depends on b"];
+    n1 -> n2;
+    n3 [shape=box, label="b depends on c"];
+    n1 -> n3;
+    n0 -> n1;
+    n0 -> n1;
+}
+"#
+        );
+        // The shared node is declared exactly once.
+        assert_eq!(dot.matches("label=\"a depends on c\"").count(), 1);
+    }
+
+    #[test]
+    fn format_mixed_terms() {
+        let terms = Map::from_iter([
+            ("b", Term::Negative(NumVS::strictly_lower_than(2u32))),
+            ("a", Term::Positive(NumVS::higher_than(1u32))),
+            ("c", Term::Negative(NumVS::singleton(3u32))),
+        ]);
+        let formatted =
+            ReportFormatter::<_, _, String>::format_terms(&DefaultStringReportFormatter, &terms);
+        assert_eq!(
+            formatted,
+            "a >=1, b not ( <2 ) and c not ( 3 ) are incompatible"
+        );
+
+        struct Excluding;
+        impl ReportPhrases for Excluding {
+            fn negated_term_phrase(&self, package: &str, set: &str) -> String {
+                format!("{} outside of {}", package, set)
+            }
+        }
+        assert_eq!(
+            ReportFormatter::<_, _, String>::format_terms(&Excluding, &terms),
+            "a >=1, b outside of <2 and c outside of 3 are incompatible"
+        );
+    }
+
+    #[test]
+    fn format_combined_terms() {
+        // The versions of a depend on c 1 which depends on a >=3,
+        // so the solver derives a term intersecting the positive and negative constraints on a,
+        // the versions of a that b 1 allows.
+        let mut dependency_provider = OfflineDependencyProvider::<&str, NumVS>::new();
+        dependency_provider.add_dependencies(
+            "root",
+            1u32,
+            [("a", NumVS::higher_than(1u32)), ("b", NumVS::full())],
+        );
+        for v in 1..5u32 {
+            dependency_provider.add_dependencies("a", v, [("c", NumVS::singleton(1u32))]);
+        }
+        dependency_provider.add_dependencies("b", 1u32, [("a", NumVS::strictly_lower_than(3u32))]);
+        dependency_provider.add_dependencies("c", 1u32, [("a", NumVS::higher_than(3u32))]);
+        let Err(PubGrubError::NoSolution(derivation_tree)) =
+            crate::resolve(&dependency_provider, "root", 1u32)
+        else {
+            unreachable!()
+        };
+        assert_eq!(
+            DefaultStringReporter::report(&derivation_tree),
+            r#"Because there is no version of b in != 1 and b 1 depends on a <3, b depends on a <3. (1)
+
+Because there is no version of a in >1, <2 | >2, <3 and a 1 | 2 depends on c 1, a >=1, <3 depends on c 1.
+And because c 1 depends on a >=3, a >=1, <3 is forbidden.
+And because b depends on a <3 (1), b depends on a <1.
+And because root 1 depends on a >=1 and root 1 depends on b, root 1 is forbidden."#
+        );
+    }
+
+    #[test]
+    fn max_segments() {
+        // 20 segments: the even versions below 40.
+        let set = (0..20u32).fold(NumVS::empty(), |acc, v| acc.union(&NumVS::singleton(2 * v)));
+        let external: External<_, _, String> =
+            External::FromDependencyOf("a", NumVS::full(), "b", set.clone());
+        assert_eq!(
+            DefaultStringReportFormatter::with_max_segments(3).format_external(&external),
+            "a depends on b 0 | 2 | 4 (and 17 more ranges)"
+        );
+        assert_eq!(
+            DefaultStringReportFormatter::with_max_segments(19).format_external(&external),
+            "a depends on b 0 | 2 | 4 | 6 | 8 | 10 | 12 | 14 | 16 | 18 | 20 | 22 | 24 | 26 | 28 | 30 | 32 | 34 | 36 (and 1 more range)"
+        );
+        // At least one segment is displayed.
+        assert_eq!(
+            DefaultStringReportFormatter::with_max_segments(0).format_external(&external),
+            "a depends on b 0 (and 19 more ranges)"
+        );
+        assert_eq!(
+            DefaultStringReportFormatter::with_max_segments(20).format_external(&external),
+            DefaultStringReportFormatter.format_external(&external)
+        );
+        assert_eq!(
+            DefaultStringReportFormatter.format_external(&external),
+            format!("a depends on b {}", set)
+        );
+
+        let terms = Map::from_iter([("b", Term::Positive(set))]);
+        assert_eq!(
+            ReportFormatter::<_, _, String>::format_terms(
+                &DefaultStringReportFormatter::with_max_segments(2),
+                &terms
+            ),
+            "b 0 | 2 (and 18 more ranges) is forbidden"
+        );
+    }
+
+    #[test]
+    fn merge_dependents() {
+        // Once "there is no version of e in 2" is collapsed into "a 2 depends on e 2",
+        // a 2 and a 3 depend on the same versions of e, along the same explanation chain.
+        let mut dependency_provider = OfflineDependencyProvider::<&str, NumVS>::new();
+        dependency_provider.add_dependencies("root", 1u32, [("a", NumVS::full())]);
+        dependency_provider.add_dependencies("a", 1u32, [("d", NumVS::higher_than(2u32))]);
+        dependency_provider.add_dependencies("a", 2u32, [("e", NumVS::singleton(2u32))]);
+        dependency_provider.add_dependencies("a", 3u32, [("e", NumVS::higher_than(2u32))]);
+        dependency_provider.add_dependencies("d", 1u32, []);
+        dependency_provider.add_dependencies("e", 1u32, []);
+        let Err(PubGrubError::NoSolution(mut derivation_tree)) =
+            crate::resolve(&dependency_provider, "root", 1u32)
+        else {
+            unreachable!()
+        };
+        derivation_tree.collapse_no_versions();
+        assert_eq!(
+            DefaultStringReporter::report(&derivation_tree),
+            "Because a <2 | >2, <3 | >3 depends on d >=2 and a 2 depends on e >=2, a != 3 is forbidden.\n\
+            And because a 3 depends on e >=2 and root 1 depends on a, root 1 is forbidden."
+        );
+        derivation_tree.merge_dependents();
+        assert_eq!(
+            DefaultStringReporter::report(&derivation_tree),
+            "Because a <2 | >2, <3 | >3 depends on d >=2 and a 2 | 3 depends on e >=2, a * is forbidden.\n\
+            And because root 1 depends on a, root 1 is forbidden."
+        );
+    }
+
+    #[test]
+    fn strip_root() {
+        let root_forbidden = Arc::new(DerivationTree::<_, _, String>::Derived(Derived {
+            terms: Map::from_iter([("root", Term::Positive(NumVS::singleton(1u32)))]),
+            shared_id: None,
+            cause1: Arc::new(DerivationTree::External(External::FromDependencyOf(
+                "root",
+                NumVS::singleton(1u32),
+                "a",
+                NumVS::singleton(2u32),
+            ))),
+            cause2: Arc::new(DerivationTree::External(External::NoVersions(
+                "a",
+                NumVS::singleton(2u32),
+            ))),
+        }));
+        let mut tree = DerivationTree::Derived(Derived {
+            terms: Map::default(),
+            shared_id: None,
+            cause1: root_forbidden,
+            cause2: Arc::new(DerivationTree::External(External::NotRoot("root", 1u32))),
+        });
+
+        // Another package is not the root.
+        let report = DefaultStringReporter::report(&tree);
+        tree.strip_root(&"a");
+        assert_eq!(DefaultStringReporter::report(&tree), report);
+
+        assert_eq!(
+            report,
+            "Because root 1 depends on a 2 and there is no version of a in 2, root 1 is forbidden.\n\
+            And because we are solving dependencies of root 1, version solving failed."
+        );
+        tree.strip_root(&"root");
+        assert_eq!(
+            DefaultStringReporter::report(&tree),
+            "Because root 1 depends on a 2 and there is no version of a in 2, root 1 is forbidden."
+        );
+    }
+
+    #[test]
+    fn to_dot_escapes_labels() {
+        let tree: DerivationTree<&str, NumVS, String> = DerivationTree::External(External::Custom(
+            "a",
+            NumVS::full(),
+            "because \"reasons\"".to_string(),
+        ));
+        assert!(tree
+            .to_dot()
+            .contains(r#"label="dependencies of a are unavailable because \"reasons\"""#));
+    }
+}
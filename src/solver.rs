@@ -59,16 +59,23 @@
 //! to satisfy the dependencies of that package and version pair.
 //! If there is no solution, the reason will be provided as clear as possible.
 
+use std::cell::Cell;
 use std::cmp::Reverse;
-use std::collections::{BTreeMap, BTreeSet as Set};
+use std::collections::{BTreeMap, BTreeSet};
 use std::convert::Infallible;
 use std::error::Error;
-use std::fmt::{Debug, Display};
+use std::fmt::{Debug, Display, Write as _};
+use std::ops::ControlFlow;
+use std::sync::Arc;
 
 use log::{debug, info};
+use thiserror::Error;
 
-use crate::internal::{Incompatibility, State};
-use crate::{DependencyConstraints, Map, Package, PubGrubError, SelectedDependencies, VersionSet};
+use crate::internal::{HashArena, Id, Incompatibility, PartialSolution, State};
+use crate::{
+    DependencyConstraints, DerivationTree, Derived, External, Map, NoSolutionError, Package,
+    PubGrubError, SelectedDependencies, Set, Term, VersionSet,
+};
 
 /// Main function of the library.
 /// Finds a set of packages satisfying dependency bounds for a given package + version pair.
@@ -77,107 +84,1663 @@ pub fn resolve<DP: DependencyProvider>(
     package: DP::P,
     version: impl Into<DP::V>,
 ) -> Result<SelectedDependencies<DP>, PubGrubError<DP>> {
-    let mut state: State<DP> = State::init(package.clone(), version.into());
-    let mut added_dependencies: Map<DP::P, Set<DP::V>> = Map::default();
-    let mut next = package;
+    resolve_with_inspector(dependency_provider, package, version, |_| {})
+}
+
+/// Same as [resolve], but calling `inspector` with the state of the resolution
+/// after each unit propagation, before the next decision.
+///
+/// This is meant for debugging and logging,
+/// for example to see which packages are decided when a resolution is slow.
+pub fn resolve_with_inspector<DP: DependencyProvider>(
+    dependency_provider: &DP,
+    package: DP::P,
+    version: impl Into<DP::V>,
+    inspector: impl FnMut(&ResolutionState<DP>),
+) -> Result<SelectedDependencies<DP>, PubGrubError<DP>> {
+    let mut state = State::init(package.clone(), version.into());
+    let outcome = resolve_inner(
+        dependency_provider,
+        &mut state,
+        &mut Map::default(),
+        package,
+        u64::MAX,
+        ResolveLimits::default(),
+        inspector,
+    )?;
+    outcome_to_result(outcome)
+}
+
+/// Convert the outcome of a resolution without step budget to the result of [resolve].
+pub(crate) fn outcome_to_result<DP: DependencyProvider>(
+    outcome: ResolveOutcome<DP>,
+) -> Result<SelectedDependencies<DP>, PubGrubError<DP>> {
+    match outcome {
+        ResolveOutcome::Solved(solution) => Ok(solution),
+        ResolveOutcome::NoSolution(derivation_tree) => {
+            Err(PubGrubError::NoSolution(derivation_tree))
+        }
+        ResolveOutcome::Exhausted { .. } => {
+            Err(PubGrubError::Failure("resolution steps exhausted".into()))
+        }
+    }
+}
+
+/// Same as [resolve], but also returning statistics about the resolution,
+/// whether it succeeded or not.
+pub fn resolve_with_stats<DP: DependencyProvider>(
+    dependency_provider: &DP,
+    package: DP::P,
+    version: impl Into<DP::V>,
+) -> (
+    Result<SelectedDependencies<DP>, PubGrubError<DP>>,
+    ResolveStats,
+) {
+    let mut state = State::init(package.clone(), version.into());
+    let result = resolve_inner(
+        dependency_provider,
+        &mut state,
+        &mut Map::default(),
+        package,
+        u64::MAX,
+        ResolveLimits::default(),
+        |_| {},
+    )
+    .and_then(outcome_to_result);
+    (result, state.stats)
+}
+
+/// Same as [resolve], but also returning the history of the resolution,
+/// whether it succeeded or not.
+///
+/// Unlike the final solution, it includes the decisions that were undone by backtracking,
+/// for example to replay the resolution in a visualization.
+#[allow(clippy::type_complexity)]
+pub fn resolve_with_history<DP: DependencyProvider>(
+    dependency_provider: &DP,
+    package: DP::P,
+    version: impl Into<DP::V>,
+) -> (
+    Result<SelectedDependencies<DP>, PubGrubError<DP>>,
+    Vec<ResolveEvent<DP::P, DP::V>>,
+) {
+    let mut state = State::init(package.clone(), version.into());
+    state.history = Some(Vec::new());
+    let result = resolve_inner(
+        dependency_provider,
+        &mut state,
+        &mut Map::default(),
+        package,
+        u64::MAX,
+        ResolveLimits::default(),
+        |_| {},
+    )
+    .and_then(outcome_to_result);
+    (result, state.history.unwrap_or_default())
+}
+
+/// Same as [resolve], but forbidding some versions of packages from the start,
+/// for example because of a policy.
+///
+/// Each constraint `(package, versions, reason)` forbids the given versions of the package,
+/// like a version with [Dependencies::Unavailable] would be,
+/// with the reason appearing in the [DerivationTree] as a [Custom](External::Custom) incompatibility.
+pub fn resolve_with_constraints<DP: DependencyProvider>(
+    dependency_provider: &DP,
+    package: DP::P,
+    version: impl Into<DP::V>,
+    constraints: impl IntoIterator<Item = (DP::P, DP::VS, DP::M)>,
+) -> Result<SelectedDependencies<DP>, PubGrubError<DP>> {
+    let mut state = State::init(package.clone(), version.into());
+    for (package, versions, reason) in constraints {
+        let package = state.package_store.alloc(package);
+        state.add_incompatibility(Incompatibility::custom_term(
+            package,
+            Term::Positive(versions),
+            reason,
+        ))?;
+    }
+    let outcome = resolve_inner(
+        dependency_provider,
+        &mut state,
+        &mut Map::default(),
+        package,
+        u64::MAX,
+        ResolveLimits::default(),
+        |_| {},
+    )?;
+    outcome_to_result(outcome)
+}
+
+/// Statistics about a resolution, returned by [resolve_with_stats].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ResolveStats {
+    /// Number of decisions, choosing a version for a package.
+    pub decisions: u64,
+    /// Number of backtracks, undoing decisions after a conflict.
+    pub backtracks: u64,
+    /// Number of incompatibilities derived from others during conflict resolution.
+    pub derived_incompatibilities: u64,
+    /// Highest decision level reached.
+    pub peak_decision_level: u32,
+}
+
+/// Event of a resolution, returned by [resolve_with_history].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ResolveEvent<P: Package, V> {
+    /// A version was chosen for a package.
+    Decided {
+        /// Package decided.
+        package: P,
+        /// Version chosen.
+        version: V,
+        /// Decision level after this decision.
+        level: u32,
+    },
+    /// Decisions were undone after a conflict.
+    Backtracked {
+        /// Decision level after backtracking.
+        to_level: u32,
+    },
+    /// An incompatibility was derived during conflict resolution,
+    /// from the one of the conflict and the cause of the assignment of a package.
+    Derived {
+        /// Package whose assignment was resolved.
+        package: P,
+    },
+}
+
+/// Read-only view of a resolution in progress, given to the inspector of [resolve_with_inspector].
+pub struct ResolutionState<'a, DP: DependencyProvider> {
+    partial_solution: &'a PartialSolution<DP>,
+    package_store: &'a HashArena<DP::P>,
+}
+
+impl<DP: DependencyProvider> ResolutionState<'_, DP> {
+    /// Packages with a chosen version, in the order they were decided.
+    pub fn decided(&self) -> impl Iterator<Item = (&DP::P, &DP::V)> {
+        self.partial_solution
+            .decided_packages()
+            .map(|(p, v)| (&self.package_store[p], v))
+    }
+
+    /// Current decision level, incremented by each decision and lowered by backtracking.
+    pub fn current_decision_level(&self) -> u32 {
+        self.partial_solution.current_decision_level().0
+    }
+
+    /// The only version allowed for a package by the partial solution, if there is one.
+    ///
+    /// This is the case of decided packages,
+    /// and of undecided ones constrained to a single version by the dependencies so far.
+    pub fn pinned_version(&self, package: &DP::P) -> Option<&DP::V> {
+        let id = self.package_store.get_id(package)?;
+        let term = self.partial_solution.term_intersection_for_package(&id)?;
+        if term.is_positive() {
+            term.unwrap_positive().as_singleton()
+        } else {
+            None
+        }
+    }
+}
+
+/// Outcome of [resolve_bounded].
+pub enum ResolveOutcome<DP: DependencyProvider> {
+    /// A solution was found.
+    Solved(SelectedDependencies<DP>),
+    /// There is no solution for this set of dependencies.
+    NoSolution(NoSolutionError<DP>),
+    /// The step budget was exhausted before the end of the resolution.
+    Exhausted {
+        /// Number of steps made, equal to the budget.
+        /// Steps choosing a version that was then rejected are counted too,
+        /// so it may be higher than the number of decided packages.
+        steps: u64,
+        /// State of the resolution when it was stopped.
+        partial_solution_summary: PartialSolutionSummary<DP::P, DP::V>,
+    },
+}
+
+impl<DP: DependencyProvider> Debug for ResolveOutcome<DP> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Solved(solution) => f.debug_tuple("Solved").field(solution).finish(),
+            Self::NoSolution(derivation_tree) => {
+                f.debug_tuple("NoSolution").field(derivation_tree).finish()
+            }
+            Self::Exhausted {
+                steps,
+                partial_solution_summary,
+            } => f
+                .debug_struct("Exhausted")
+                .field("steps", steps)
+                .field("partial_solution_summary", partial_solution_summary)
+                .finish(),
+        }
+    }
+}
+
+/// Progress of a resolution stopped by [resolve_bounded].
+#[derive(Debug, Clone)]
+pub struct PartialSolutionSummary<P: Package, V> {
+    /// Packages decided so far, with their chosen version.
+    pub decided: Map<P, V>,
+    /// Packages required by the decisions so far, but not decided yet.
+    pub undecided: Vec<P>,
+}
+
+/// Same as [resolve], but stops after a given number of steps
+/// instead of running as long as needed.
+///
+/// A step is the choice of a version for one package,
+/// after unit propagation of the previous choice.
+/// When the budget is exhausted, the progress made so far is returned
+/// in [ResolveOutcome::Exhausted].
+pub fn resolve_bounded<DP: DependencyProvider>(
+    dependency_provider: &DP,
+    package: DP::P,
+    version: impl Into<DP::V>,
+    max_steps: u64,
+) -> Result<ResolveOutcome<DP>, PubGrubError<DP>> {
+    let mut state = State::init(package.clone(), version.into());
+    resolve_inner(
+        dependency_provider,
+        &mut state,
+        &mut Map::default(),
+        package,
+        max_steps,
+        ResolveLimits::default(),
+        |_| {},
+    )
+}
+
+/// Limits on the memory used by [resolve_with_limits].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ResolveLimits {
+    /// Maximum number of incompatibilities, learned from the dependencies
+    /// and derived from conflicts, kept during the resolution.
+    pub max_incompatibilities: usize,
+    /// Maximum decision level, the number of decisions not undone by backtracking.
+    pub max_decision_level: u32,
+}
+
+/// No limit.
+impl Default for ResolveLimits {
+    fn default() -> Self {
+        Self {
+            max_incompatibilities: usize::MAX,
+            max_decision_level: u32::MAX,
+        }
+    }
+}
+
+/// Same as [resolve], but failing with [PubGrubError::LimitExceeded]
+/// when the resolution goes over the given limits.
+///
+/// On adversarial inputs, the number of incompatibilities can grow a lot
+/// before [should_cancel](DependencyProvider::should_cancel) is given a chance to stop it.
+/// The number of incompatibilities is checked each time incompatibilities are added,
+/// and the decision level after each unit propagation,
+/// to protect a shared service from running out of memory.
+pub fn resolve_with_limits<DP: DependencyProvider>(
+    dependency_provider: &DP,
+    package: DP::P,
+    version: impl Into<DP::V>,
+    limits: ResolveLimits,
+) -> Result<SelectedDependencies<DP>, PubGrubError<DP>> {
+    let mut state = State::init(package.clone(), version.into());
+    let outcome = resolve_inner(
+        dependency_provider,
+        &mut state,
+        &mut Map::default(),
+        package,
+        u64::MAX,
+        limits,
+        |_| {},
+    )?;
+    outcome_to_result(outcome)
+}
+
+/// Main loop of the resolution, shared by [resolve_with_inspector], [resolve_bounded],
+/// [resolve_with_limits] and [Resolver].
+///
+/// `added_dependencies` records the package versions whose dependencies
+/// were already added to the `state` incompatibilities.
+fn resolve_inner<DP: DependencyProvider>(
+    dependency_provider: &DP,
+    state: &mut State<DP>,
+    added_dependencies: &mut Map<Id<DP::P>, BTreeSet<DP::V>>,
+    package: DP::P,
+    max_steps: u64,
+    limits: ResolveLimits,
+    mut inspector: impl FnMut(&ResolutionState<DP>),
+) -> Result<ResolveOutcome<DP>, PubGrubError<DP>> {
+    let mut resolution = Resolution::new(state, added_dependencies, package, max_steps, limits);
     loop {
-        dependency_provider
-            .should_cancel()
-            .map_err(PubGrubError::ErrorInShouldCancel)?;
+        let request = match resolution.choose(dependency_provider, &mut inspector)? {
+            ControlFlow::Break(outcome) => return Ok(outcome),
+            ControlFlow::Continue(request) => request,
+        };
+        let decision = dependency_provider
+            .choose_version_with_tried(request.package, request.range, request.already_tried)
+            .map_err(PubGrubError::ErrorChoosingPackageVersion)?;
+        let Some(version) = resolution.chosen(dependency_provider, decision)? else {
+            continue;
+        };
+        let package = resolution.package();
+        let dependencies = dependency_provider
+            .get_dependencies(package, &version)
+            .map_err(|err| PubGrubError::ErrorRetrievingDependencies {
+                package: package.clone(),
+                version: version.clone(),
+                source: err,
+            })?;
+        resolution.add_dependencies(dependency_provider, version, dependencies)?;
+    }
+}
+
+/// The methods of a dependency provider that a [Resolution] calls itself.
+///
+/// They are all synchronous, choosing versions and retrieving dependencies
+/// are left to the function driving the resolution.
+pub(crate) trait ResolutionHooks<DP: DependencyProvider> {
+    fn prioritize(&self, package: &DP::P, range: &DP::VS) -> DP::Priority;
+    fn package_exists(&self, package: &DP::P) -> bool;
+    fn prefetch(&self, requests: &[(&DP::P, &DP::VS)]);
+    fn should_cancel(&self) -> Result<(), DP::Err>;
+    fn on_progress(&self, info: ProgressInfo);
+}
+
+impl<DP: DependencyProvider> ResolutionHooks<DP> for DP {
+    fn prioritize(&self, package: &DP::P, range: &DP::VS) -> DP::Priority {
+        DependencyProvider::prioritize(self, package, range)
+    }
+
+    fn package_exists(&self, package: &DP::P) -> bool {
+        DependencyProvider::package_exists(self, package)
+    }
+
+    fn prefetch(&self, requests: &[(&DP::P, &DP::VS)]) {
+        DependencyProvider::prefetch(self, requests)
+    }
+
+    fn should_cancel(&self) -> Result<(), DP::Err> {
+        DependencyProvider::should_cancel(self)
+    }
+
+    fn on_progress(&self, info: ProgressInfo) {
+        DependencyProvider::on_progress(self, info)
+    }
+}
+
+/// Version to choose for the next package to decide, asked by [Resolution::choose].
+pub(crate) struct VersionRequest<'r, DP: DependencyProvider> {
+    pub(crate) package: &'r DP::P,
+    pub(crate) range: &'r DP::VS,
+    pub(crate) already_tried: &'r BTreeSet<DP::V>,
+}
+
+/// A resolution in progress, driven by [resolve_inner]
+/// or by `resolve_async` with the `async` feature.
+///
+/// It runs the algorithm in three steps, and the driver calls the dependency provider in between:
+/// [choose](Self::choose) picks the next package to decide,
+/// the driver chooses a version of it and gives it to [chosen](Self::chosen),
+/// which may need the dependencies of that version,
+/// that the driver retrieves and gives to [add_dependencies](Self::add_dependencies).
+/// This way, the same algorithm serves synchronous and asynchronous dependency providers.
+pub(crate) struct Resolution<'a, DP: DependencyProvider> {
+    state: &'a mut State<DP>,
+    /// Package versions whose dependencies were already added to the state incompatibilities.
+    added_dependencies: &'a mut Map<Id<DP::P>, BTreeSet<DP::V>>,
+    /// Package being decided, or of the last decision before [choose](Self::choose).
+    next: Id<DP::P>,
+    steps: u64,
+    max_steps: u64,
+    /// The maximum number of incompatibilities is checked by the state.
+    max_decision_level: u32,
+    /// Versions already tried for a package that was never decided.
+    not_tried: BTreeSet<DP::V>,
+}
+
+impl<'a, DP: DependencyProvider> Resolution<'a, DP> {
+    /// Start resolving from the root `package` of the `state`.
+    pub(crate) fn new(
+        state: &'a mut State<DP>,
+        added_dependencies: &'a mut Map<Id<DP::P>, BTreeSet<DP::V>>,
+        package: DP::P,
+        max_steps: u64,
+        limits: ResolveLimits,
+    ) -> Self {
+        let next = state.package_store.alloc(package);
+        state.max_incompatibilities = limits.max_incompatibilities;
+        Self {
+            state,
+            added_dependencies,
+            next,
+            steps: 0,
+            max_steps,
+            max_decision_level: limits.max_decision_level,
+            not_tried: BTreeSet::new(),
+        }
+    }
+
+    /// Package being decided.
+    pub(crate) fn package(&self) -> &DP::P {
+        &self.state.package_store[self.next]
+    }
+
+    /// Propagate the last decision, then pick the next package to decide,
+    /// or stop with the outcome of the resolution.
+    pub(crate) fn choose(
+        &mut self,
+        dependency_provider: &impl ResolutionHooks<DP>,
+        inspector: &mut impl FnMut(&ResolutionState<DP>),
+    ) -> Result<ControlFlow<ResolveOutcome<DP>, VersionRequest<'_, DP>>, PubGrubError<DP>> {
+        let state = &mut *self.state;
+        if let Err(source) = dependency_provider.should_cancel() {
+            return Err(PubGrubError::ErrorInShouldCancel {
+                source,
+                decided: state
+                    .partial_solution
+                    .extract_solution(&state.package_store),
+            });
+        }
+
+        info!("unit_propagation: {}", state.package_store[self.next]);
+        match state.unit_propagation(self.next) {
+            Ok(()) => {}
+            Err(PubGrubError::NoSolution(derivation_tree)) => {
+                return Ok(ControlFlow::Break(ResolveOutcome::NoSolution(
+                    derivation_tree,
+                )));
+            }
+            Err(err) => return Err(err),
+        }
+        let decision_level = state.partial_solution.current_decision_level().0;
+        if decision_level > self.max_decision_level {
+            return Err(PubGrubError::LimitExceeded {
+                incompatibilities: state.incompatibility_store.len(),
+                decision_level,
+            });
+        }
+
+        debug!(
+            "Partial solution after unit propagation: {}",
+            state.partial_solution.display(&state.package_store)
+        );
+
+        dependency_provider.on_progress(ProgressInfo {
+            decision_level: state.partial_solution.current_decision_level().0,
+            decided_count: state.partial_solution.decided_count(),
+            undecided_count: state.partial_solution.undecided_count(),
+            conflict_count: state.conflict_count,
+        });
+        inspector(&ResolutionState {
+            partial_solution: &state.partial_solution,
+            package_store: &state.package_store,
+        });
+
+        let Some(highest_priority_pkg) =
+            state.partial_solution.pick_highest_priority_pkg(|p, r| {
+                dependency_provider.prioritize(&state.package_store[p], r)
+            })
+        else {
+            return Ok(ControlFlow::Break(ResolveOutcome::Solved(
+                state
+                    .partial_solution
+                    .extract_solution(&state.package_store),
+            )));
+        };
+        self.next = highest_priority_pkg;
+
+        if self.steps == self.max_steps {
+            return Ok(ControlFlow::Break(ResolveOutcome::Exhausted {
+                steps: self.steps,
+                partial_solution_summary: PartialSolutionSummary {
+                    decided: state
+                        .partial_solution
+                        .extract_solution(&state.package_store),
+                    undecided: state
+                        .partial_solution
+                        .undecided_packages()
+                        .map(|p| state.package_store[p].clone())
+                        .collect(),
+                },
+            }));
+        }
+        self.steps += 1;
+
+        let term_intersection = state
+            .partial_solution
+            .term_intersection_for_package(&self.next)
+            .ok_or_else(|| {
+                PubGrubError::Failure("a package was chosen but we don't have a term.".into())
+            })?;
+        Ok(ControlFlow::Continue(VersionRequest {
+            package: &state.package_store[self.next],
+            range: term_intersection.unwrap_positive(),
+            already_tried: self
+                .added_dependencies
+                .get(&self.next)
+                .unwrap_or(&self.not_tried),
+        }))
+    }
+
+    /// Decide the version chosen by the dependency provider for the package being decided.
+    ///
+    /// The version is returned when its dependencies must be retrieved first,
+    /// to give them to [add_dependencies](Self::add_dependencies).
+    pub(crate) fn chosen(
+        &mut self,
+        dependency_provider: &impl ResolutionHooks<DP>,
+        decision: Option<DP::V>,
+    ) -> Result<Option<DP::V>, PubGrubError<DP>> {
+        let state = &mut *self.state;
+        let next = self.next;
+        info!("DP chose: {} @ {:?}", state.package_store[next], decision);
+        let term_intersection = state
+            .partial_solution
+            .term_intersection_for_package(&next)
+            .ok_or_else(|| {
+                PubGrubError::Failure("a package was chosen but we don't have a term.".into())
+            })?;
+
+        // Pick the next compatible version.
+        let Some(v) = decision else {
+            let inc = if dependency_provider.package_exists(&state.package_store[next]) {
+                Incompatibility::no_versions(next, term_intersection.clone())
+            } else {
+                Incompatibility::package_not_found(next)
+            };
+            state.add_incompatibility(inc)?;
+            return Ok(None);
+        };
+
+        if !term_intersection.contains(&v) {
+            return Err(PubGrubError::InvalidVersionChoice {
+                package: state.package_store[next].clone(),
+                version: v,
+                range: term_intersection.unwrap_positive().clone(),
+            });
+        }
+
+        let is_new_dependency = self
+            .added_dependencies
+            .entry(next)
+            .or_default()
+            .insert(v.clone());
+        if is_new_dependency {
+            // Retrieve that package dependencies.
+            return Ok(Some(v));
+        }
+
+        // `dep_incompats` are already in `incompatibilities` so we know there are not satisfied
+        // terms and can add the decision directly.
+        info!(
+            "add_decision (not first time): {} @ {}",
+            state.package_store[next], v
+        );
+        let decided_count = state.partial_solution.decided_count();
+        let decided_version = state.history.is_some().then(|| v.clone());
+        state.partial_solution.add_decision(next, v);
+        self.record_decision(decided_count, decided_version);
+        Ok(None)
+    }
+
+    /// Decide the version returned by [chosen](Self::chosen) with its dependencies,
+    /// if they are available.
+    pub(crate) fn add_dependencies(
+        &mut self,
+        dependency_provider: &impl ResolutionHooks<DP>,
+        version: DP::V,
+        dependencies: Dependencies<DP::P, DP::VS, DP::M>,
+    ) -> Result<(), PubGrubError<DP>> {
+        let state = &mut *self.state;
+        let p = self.next;
+        let dependencies = match dependencies {
+            Dependencies::Unavailable(reason) => {
+                return state
+                    .add_incompatibility(Incompatibility::custom_version(p, version, reason));
+            }
+            Dependencies::Available(x) => x,
+        };
+
+        // Let the dependency provider fetch what it needs for these dependencies
+        // before they are decided one by one.
+        if !dependencies.is_empty() {
+            let prefetch: Vec<(&DP::P, &DP::VS)> = dependencies.iter().collect();
+            dependency_provider.prefetch(&prefetch);
+        }
+
+        // Add that package and version if the dependencies are not problematic.
+        let decided_count = state.partial_solution.decided_count();
+        let decided_version = state.history.is_some().then(|| version.clone());
+        let dep_incompats =
+            state.add_incompatibility_from_dependencies(p, version.clone(), dependencies)?;
+        state.partial_solution.add_version(
+            p,
+            version,
+            dep_incompats,
+            &state.incompatibility_store,
+            &state.package_store,
+        );
+        self.record_decision(decided_count, decided_version);
+        Ok(())
+    }
+
+    /// Count the decision of the package being decided in the statistics and history,
+    /// if the number of decided packages went up from `decided_count`.
+    fn record_decision(&mut self, decided_count: usize, version: Option<DP::V>) {
+        let state = &mut *self.state;
+        if state.partial_solution.decided_count() > decided_count {
+            let level = state.partial_solution.current_decision_level().0;
+            state.stats.decisions += 1;
+            state.stats.peak_decision_level = state.stats.peak_decision_level.max(level);
+            if let (Some(history), Some(version)) = (&mut state.history, version) {
+                history.push(ResolveEvent::Decided {
+                    package: state.package_store[self.next].clone(),
+                    version,
+                    level,
+                });
+            }
+        }
+    }
+}
+
+/// Resolver keeping what it learned between successive resolutions of the same root package.
+///
+/// The incompatibilities found during a resolution, and the dependencies retrieved,
+/// are reused by the next one.
+/// When the versions or dependencies of a package change in the dependency provider,
+/// call [invalidate_package](Resolver::invalidate_package) before resolving again,
+/// so that everything derived from the old ones is forgotten.
+pub struct Resolver<DP: DependencyProvider> {
+    package: DP::P,
+    version: DP::V,
+    /// State of the last resolution, and the package versions whose dependencies were added to it.
+    /// It is [None] before the first resolution and after an error.
+    #[allow(clippy::type_complexity)]
+    previous: Option<(State<DP>, Map<Id<DP::P>, BTreeSet<DP::V>>)>,
+    /// Packages that changed since the last resolution.
+    invalidated: Set<DP::P>,
+}
+
+impl<DP: DependencyProvider> Resolver<DP> {
+    /// Create a resolver for a given package + version pair.
+    pub fn new(package: DP::P, version: impl Into<DP::V>) -> Self {
+        Self {
+            package,
+            version: version.into(),
+            previous: None,
+            invalidated: Set::default(),
+        }
+    }
+
+    /// Finds a set of packages satisfying dependency bounds for the root package,
+    /// like [resolve].
+    ///
+    /// The dependency provider should be the same for all the resolutions,
+    /// up to the changes of the packages given to [invalidate_package](Resolver::invalidate_package).
+    pub fn resolve(
+        &mut self,
+        dependency_provider: &DP,
+    ) -> Result<SelectedDependencies<DP>, PubGrubError<DP>> {
+        let (mut state, mut added_dependencies) = match self.previous.take() {
+            Some((mut state, mut added_dependencies)) => {
+                state.restart(&self.invalidated);
+                added_dependencies
+                    .retain(|&p, _| !self.invalidated.contains(&state.package_store[p]));
+                (state, added_dependencies)
+            }
+            None => (
+                State::init(self.package.clone(), self.version.clone()),
+                Map::default(),
+            ),
+        };
+        self.invalidated.clear();
+        // On error, the state may be in the middle of adding dependencies, so it is dropped.
+        let outcome = resolve_inner(
+            dependency_provider,
+            &mut state,
+            &mut added_dependencies,
+            self.package.clone(),
+            u64::MAX,
+            ResolveLimits::default(),
+            |_| {},
+        )?;
+        self.previous = Some((state, added_dependencies));
+        outcome_to_result(outcome)
+    }
+
+    /// Forget what was learned from the versions and dependencies of a package,
+    /// because they changed in the dependency provider.
+    pub fn invalidate_package(&mut self, package: DP::P) {
+        self.invalidated.insert(package);
+    }
+}
+
+/// Same as [resolve], but preferring the given versions of some packages,
+/// for example the ones recorded in a lockfile.
+///
+/// When a package with a preferred version is decided,
+/// the preferred version is chosen if it is in the allowed range
+/// and the dependency provider still has it,
+/// otherwise the dependency provider chooses as usual.
+/// A preferred version that is not part of any solution does not prevent finding another one.
+///
+/// The solution of a previous resolution can also be given as preferences, as a warm start:
+/// when the dependency graph barely changed, the same versions are found again
+/// without going through the conflicts that led to them.
+pub fn resolve_with_preferences<DP: DependencyProvider>(
+    dependency_provider: &DP,
+    package: DP::P,
+    version: impl Into<DP::V>,
+    preferences: &Map<DP::P, DP::V>,
+) -> Result<SelectedDependencies<DP>, PubGrubError<DP>> {
+    let dependency_provider = PreferencesDependencyProvider {
+        dependency_provider,
+        preferences,
+    };
+    resolve(&dependency_provider, package, version).map_err(unwrap_error)
+}
+
+/// Same as [resolve], but using the [deprioritized](DependencyProvider::is_deprioritized) versions,
+/// like yanked ones, only when there is no solution without them.
+///
+/// The first resolution skips the deprioritized versions when choosing a version.
+/// If it fails because of a conflict and some versions were skipped,
+/// a second resolution is done with all the versions, and its result is returned.
+/// It still chooses the versions that are not deprioritized first,
+/// and a deprioritized version only when there is no other one left in the range of a package.
+pub fn resolve_with_deprioritized<DP: DependencyProvider>(
+    dependency_provider: &DP,
+    package: DP::P,
+    version: impl Into<DP::V>,
+) -> Result<SelectedDependencies<DP>, PubGrubError<DP>> {
+    let version = version.into();
+    let first_pass = SkipDeprioritizedDependencyProvider {
+        dependency_provider,
+        fallback: false,
+        skipped: Cell::new(false),
+    };
+    match resolve(&first_pass, package.clone(), version.clone()) {
+        Err(PubGrubError::NoSolution(_)) if first_pass.skipped.get() => {
+            info!("no solution without deprioritized versions, using them as a last resort");
+            let second_pass = SkipDeprioritizedDependencyProvider {
+                dependency_provider,
+                fallback: true,
+                skipped: Cell::new(false),
+            };
+            resolve(&second_pass, package, version).map_err(unwrap_error)
+        }
+        result => result.map_err(unwrap_error),
+    }
+}
+
+/// Same as [resolve], but for the first version of the package in `range` that has a solution,
+/// returned with that solution.
+///
+/// The versions are tried in the order [choose_version](DependencyProvider::choose_version)
+/// returns them, from the newest to the oldest for [OfflineDependencyProvider] by default,
+/// each one being removed from the range for the next choice.
+/// If none of them has a solution, the [DerivationTree] of the last one tried is returned.
+pub fn resolve_latest<DP: DependencyProvider>(
+    dependency_provider: &DP,
+    package: DP::P,
+    range: DP::VS,
+) -> Result<(DP::V, SelectedDependencies<DP>), PubGrubError<DP>> {
+    let mut remaining = range.clone();
+    let mut last_error = None;
+    while let Some(version) = dependency_provider
+        .choose_version(&package, &remaining)
+        .map_err(PubGrubError::ErrorChoosingPackageVersion)?
+    {
+        if !remaining.contains(&version) {
+            return Err(PubGrubError::InvalidVersionChoice {
+                package,
+                version,
+                range: remaining,
+            });
+        }
+        match resolve(dependency_provider, package.clone(), version.clone()) {
+            Ok(solution) => return Ok((version, solution)),
+            Err(PubGrubError::NoSolution(derivation_tree)) => {
+                info!("no solution for {package} {version}, trying another version");
+                last_error = Some(PubGrubError::NoSolution(derivation_tree));
+            }
+            Err(err) => return Err(err),
+        }
+        remaining = remaining.intersection(&DP::VS::singleton(version).complement());
+    }
+    Err(last_error.unwrap_or_else(|| {
+        PubGrubError::NoSolution(DerivationTree::External(External::NoVersions(
+            package, range,
+        )))
+    }))
+}
+
+/// Finds a set of packages satisfying the dependency bounds of several package + version pairs,
+/// all of them being part of the solution.
+///
+/// This is the same as resolving a root package depending on exactly each of these versions,
+/// but that hidden root package does not appear in the solution nor in the [DerivationTree]
+/// when there is no solution.
+/// Instead, each of the given versions is required by a [NotRoot](External::NotRoot) incompatibility.
+pub fn resolve_multi<DP: DependencyProvider>(
+    dependency_provider: &DP,
+    roots: impl IntoIterator<Item = (DP::P, DP::V)>,
+) -> Result<SelectedDependencies<DP>, PubGrubError<DP>> {
+    let mut root_versions: Map<DP::P, DP::V> = Map::default();
+    let mut root_dependencies = DependencyConstraints::default();
+    for (package, version) in roots {
+        let range = DP::VS::singleton(version.clone());
+        root_dependencies
+            .entry(package.clone())
+            .and_modify(|r: &mut DP::VS| *r = r.intersection(&range))
+            .or_insert(range);
+        root_versions.entry(package).or_insert(version);
+    }
+    let multi_root_provider =
+        MultiRootDependencyProvider::new(dependency_provider, root_dependencies);
+    resolve(
+        &multi_root_provider,
+        MultiRootPackage::Root,
+        MultiRootVersion::Root,
+    )
+    .map(strip_hidden_root_solution)
+    .map_err(|err| unwrap_multi_root_error(err, &root_versions))
+}
+
+/// Finds a set of packages satisfying the given constraints,
+/// as if they were the dependencies of a root package.
+///
+/// That root package is hidden, it does not appear in the solution nor in the [DerivationTree]
+/// when there is no solution.
+/// Instead, the conflicts are reported between the constrained packages themselves,
+/// for example "a 1 and b 2 are incompatible" when the constraints are `a 1` and `b 2`.
+pub fn resolve_constraints<DP: DependencyProvider>(
+    dependency_provider: &DP,
+    constraints: DependencyConstraints<DP::P, DP::VS>,
+) -> Result<SelectedDependencies<DP>, PubGrubError<DP>> {
+    let multi_root_provider = MultiRootDependencyProvider::new(dependency_provider, constraints);
+    resolve(
+        &multi_root_provider,
+        MultiRootPackage::Root,
+        MultiRootVersion::Root,
+    )
+    .map(strip_hidden_root_solution)
+    .map_err(|err| unwrap_multi_root_error(err, &Map::default()))
+}
+
+/// Remove the hidden root of [resolve_multi] from a solution.
+fn strip_hidden_root_solution<P: Package, V>(
+    solution: impl IntoIterator<Item = (MultiRootPackage<P>, MultiRootVersion<V>)>,
+) -> Map<P, V> {
+    solution
+        .into_iter()
+        .filter_map(|(package, version)| match package {
+            MultiRootPackage::Root => None,
+            MultiRootPackage::Package(p) => Some((p, version.into_inner())),
+        })
+        .collect()
+}
+
+/// Convert an error of the [MultiRootDependencyProvider] back to an error of the wrapped one,
+/// removing the hidden root from the derivation tree with [strip_hidden_root].
+fn unwrap_multi_root_error<DP: DependencyProvider>(
+    err: PubGrubError<MultiRootDependencyProvider<DP>>,
+    root_versions: &Map<DP::P, DP::V>,
+) -> PubGrubError<DP> {
+    match err {
+        PubGrubError::NoSolution(derivation_tree) => {
+            match strip_hidden_root(&derivation_tree, root_versions) {
+                Some(derivation_tree) => PubGrubError::NoSolution(derivation_tree),
+                None => PubGrubError::Failure("conflict without any root package".into()),
+            }
+        }
+        PubGrubError::IndependentConflicts(derivation_trees) => {
+            let derivation_trees: Option<Vec<_>> = derivation_trees
+                .iter()
+                .map(|derivation_tree| strip_hidden_root(derivation_tree, root_versions))
+                .collect();
+            match derivation_trees {
+                Some(derivation_trees) => PubGrubError::IndependentConflicts(derivation_trees),
+                None => PubGrubError::Failure("conflict without any root package".into()),
+            }
+        }
+        PubGrubError::ErrorRetrievingDependencies {
+            package,
+            version,
+            source,
+        } => match package {
+            MultiRootPackage::Package(package) => PubGrubError::ErrorRetrievingDependencies {
+                package,
+                version: version.into_inner(),
+                source,
+            },
+            MultiRootPackage::Root => {
+                unreachable!("the hidden root dependencies are always available")
+            }
+        },
+        PubGrubError::ErrorChoosingPackageVersion(err) => {
+            PubGrubError::ErrorChoosingPackageVersion(err)
+        }
+        PubGrubError::ErrorInShouldCancel { source, decided } => {
+            PubGrubError::ErrorInShouldCancel {
+                source,
+                decided: strip_hidden_root_solution(decided),
+            }
+        }
+        PubGrubError::LimitExceeded {
+            incompatibilities,
+            decision_level,
+        } => PubGrubError::LimitExceeded {
+            incompatibilities,
+            decision_level,
+        },
+        PubGrubError::InvalidVersionChoice {
+            package,
+            version,
+            range,
+        } => match package {
+            MultiRootPackage::Package(package) => PubGrubError::InvalidVersionChoice {
+                package,
+                version: version.into_inner(),
+                range: range.versions,
+            },
+            MultiRootPackage::Root => {
+                unreachable!("the hidden root version is always chosen in its range")
+            }
+        },
+        PubGrubError::Failure(msg) => PubGrubError::Failure(msg),
+    }
+}
+
+/// Package of [resolve_multi] and [resolve_constraints],
+/// either the hidden root or an actual package.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum MultiRootPackage<P> {
+    Root,
+    Package(P),
+}
+
+impl<P: Display> Display for MultiRootPackage<P> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Root => write!(f, "root"),
+            Self::Package(p) => write!(f, "{}", p),
+        }
+    }
+}
+
+/// Version of a [MultiRootPackage].
+///
+/// The hidden root has its own version,
+/// so there is no need to pick a version of an actual package for it.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+enum MultiRootVersion<V> {
+    Root,
+    Version(V),
+}
+
+impl<V> MultiRootVersion<V> {
+    /// The version of an actual package, only the hidden root has the hidden root version.
+    fn into_inner(self) -> V {
+        match self {
+            Self::Root => unreachable!("only the hidden root has the hidden root version"),
+            Self::Version(v) => v,
+        }
+    }
+}
+
+impl<V: Display> Display for MultiRootVersion<V> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Root => write!(f, "root"),
+            Self::Version(v) => write!(f, "{}", v),
+        }
+    }
+}
+
+/// Set of [MultiRootVersion], the versions of the wrapped version set
+/// and the hidden root version if `root` is true.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct MultiRootVersionSet<VS> {
+    root: bool,
+    versions: VS,
+}
+
+impl<VS> From<VS> for MultiRootVersionSet<VS> {
+    fn from(versions: VS) -> Self {
+        Self {
+            root: false,
+            versions,
+        }
+    }
+}
+
+impl<VS: Display> Display for MultiRootVersionSet<VS> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.root {
+            write!(f, "root or {}", self.versions)
+        } else {
+            write!(f, "{}", self.versions)
+        }
+    }
+}
+
+impl<VS: VersionSet> VersionSet for MultiRootVersionSet<VS> {
+    type V = MultiRootVersion<VS::V>;
+
+    fn empty() -> Self {
+        Self::from(VS::empty())
+    }
+
+    fn singleton(v: Self::V) -> Self {
+        match v {
+            MultiRootVersion::Root => Self {
+                root: true,
+                versions: VS::empty(),
+            },
+            MultiRootVersion::Version(v) => Self::from(VS::singleton(v)),
+        }
+    }
+
+    fn complement(&self) -> Self {
+        Self {
+            root: !self.root,
+            versions: self.versions.complement(),
+        }
+    }
+
+    fn intersection(&self, other: &Self) -> Self {
+        Self {
+            root: self.root && other.root,
+            versions: self.versions.intersection(&other.versions),
+        }
+    }
+
+    fn contains(&self, v: &Self::V) -> bool {
+        match v {
+            MultiRootVersion::Root => self.root,
+            MultiRootVersion::Version(v) => self.versions.contains(v),
+        }
+    }
+
+    fn full() -> Self {
+        Self {
+            root: true,
+            versions: VS::full(),
+        }
+    }
+
+    fn union(&self, other: &Self) -> Self {
+        Self {
+            root: self.root || other.root,
+            versions: self.versions.union(&other.versions),
+        }
+    }
+
+    fn approximate_len(&self) -> usize {
+        usize::from(self.root) + self.versions.approximate_len()
+    }
+
+    fn is_disjoint(&self, other: &Self) -> bool {
+        !(self.root && other.root) && self.versions.is_disjoint(&other.versions)
+    }
+
+    fn subset_of(&self, other: &Self) -> bool {
+        (!self.root || other.root) && self.versions.subset_of(&other.versions)
+    }
+}
+
+/// Wraps a [DependencyProvider] to add a hidden root package depending on the roots of [resolve_multi],
+/// or on the constraints of [resolve_constraints].
+struct MultiRootDependencyProvider<'a, DP: DependencyProvider> {
+    dependency_provider: &'a DP,
+    root_dependencies: DependencyConstraints<MultiRootPackage<DP::P>, MultiRootVersionSet<DP::VS>>,
+}
+
+impl<'a, DP: DependencyProvider> MultiRootDependencyProvider<'a, DP> {
+    fn new(
+        dependency_provider: &'a DP,
+        root_dependencies: DependencyConstraints<DP::P, DP::VS>,
+    ) -> Self {
+        Self {
+            dependency_provider,
+            root_dependencies: root_dependencies
+                .into_iter()
+                .map(|(p, range)| (MultiRootPackage::Package(p), range.into()))
+                .collect(),
+        }
+    }
+}
+
+impl<DP: DependencyProvider> DependencyProvider for MultiRootDependencyProvider<'_, DP> {
+    type P = MultiRootPackage<DP::P>;
+    type V = MultiRootVersion<DP::V>;
+    type VS = MultiRootVersionSet<DP::VS>;
+    type M = DP::M;
+
+    fn prioritize(&self, package: &Self::P, range: &Self::VS) -> Self::Priority {
+        match package {
+            MultiRootPackage::Root => None,
+            MultiRootPackage::Package(p) => {
+                Some(self.dependency_provider.prioritize(p, &range.versions))
+            }
+        }
+    }
 
-        info!("unit_propagation: {}", &next);
-        state.unit_propagation(next)?;
+    type Priority = Option<DP::Priority>;
 
-        debug!(
-            "Partial solution after unit propagation: {}",
-            state.partial_solution
-        );
+    type Err = DP::Err;
 
-        let Some(highest_priority_pkg) = state
-            .partial_solution
-            .pick_highest_priority_pkg(|p, r| dependency_provider.prioritize(p, r))
-        else {
-            return Ok(state.partial_solution.extract_solution());
-        };
-        next = highest_priority_pkg;
+    fn choose_version(
+        &self,
+        package: &Self::P,
+        range: &Self::VS,
+    ) -> Result<Option<Self::V>, DP::Err> {
+        match package {
+            MultiRootPackage::Root => Ok(Some(MultiRootVersion::Root)),
+            MultiRootPackage::Package(p) => Ok(self
+                .dependency_provider
+                .choose_version(p, &range.versions)?
+                .map(MultiRootVersion::Version)),
+        }
+    }
 
-        let term_intersection = state
-            .partial_solution
-            .term_intersection_for_package(&next)
-            .ok_or_else(|| {
-                PubGrubError::Failure("a package was chosen but we don't have a term.".into())
-            })?;
-        let decision = dependency_provider
-            .choose_version(&next, term_intersection.unwrap_positive())
-            .map_err(PubGrubError::ErrorChoosingPackageVersion)?;
-        info!("DP chose: {} @ {:?}", next, decision);
+    fn choose_version_with_tried(
+        &self,
+        package: &Self::P,
+        range: &Self::VS,
+        already_tried: &BTreeSet<Self::V>,
+    ) -> Result<Option<Self::V>, DP::Err> {
+        match package {
+            MultiRootPackage::Root => Ok(Some(MultiRootVersion::Root)),
+            MultiRootPackage::Package(p) => {
+                let already_tried = already_tried
+                    .iter()
+                    .filter_map(|v| match v {
+                        MultiRootVersion::Root => None,
+                        MultiRootVersion::Version(v) => Some(v.clone()),
+                    })
+                    .collect();
+                Ok(self
+                    .dependency_provider
+                    .choose_version_with_tried(p, &range.versions, &already_tried)?
+                    .map(MultiRootVersion::Version))
+            }
+        }
+    }
 
-        // Pick the next compatible version.
-        let v = match decision {
-            None => {
-                let inc = Incompatibility::no_versions(next.clone(), term_intersection.clone());
-                state.add_incompatibility(inc);
-                continue;
+    fn package_exists(&self, package: &Self::P) -> bool {
+        match package {
+            MultiRootPackage::Root => true,
+            MultiRootPackage::Package(p) => self.dependency_provider.package_exists(p),
+        }
+    }
+
+    fn is_deprioritized(&self, package: &Self::P, version: &Self::V) -> bool {
+        match (package, version) {
+            (MultiRootPackage::Package(p), MultiRootVersion::Version(v)) => {
+                self.dependency_provider.is_deprioritized(p, v)
+            }
+            _ => false,
+        }
+    }
+
+    fn version_preference(&self, package: &Self::P) -> VersionPreference<Self::V> {
+        match package {
+            MultiRootPackage::Root => VersionPreference::Newest,
+            MultiRootPackage::Package(p) => match self.dependency_provider.version_preference(p) {
+                VersionPreference::Newest => VersionPreference::Newest,
+                VersionPreference::Oldest => VersionPreference::Oldest,
+                VersionPreference::ClosestTo(v) => {
+                    VersionPreference::ClosestTo(MultiRootVersion::Version(v))
+                }
+            },
+        }
+    }
+
+    fn get_dependencies(
+        &self,
+        package: &Self::P,
+        version: &Self::V,
+    ) -> Result<Dependencies<Self::P, Self::VS, DP::M>, DP::Err> {
+        let (p, v) = match (package, version) {
+            (MultiRootPackage::Root, _) => {
+                return Ok(Dependencies::Available(self.root_dependencies.clone()))
+            }
+            (MultiRootPackage::Package(p), MultiRootVersion::Version(v)) => (p, v),
+            (MultiRootPackage::Package(_), MultiRootVersion::Root) => {
+                unreachable!("only the hidden root has the hidden root version")
             }
-            Some(x) => x,
         };
+        Ok(match self.dependency_provider.get_dependencies(p, v)? {
+            Dependencies::Available(dependencies) => Dependencies::Available(
+                dependencies
+                    .into_iter()
+                    .map(|(p, range)| (MultiRootPackage::Package(p), range.into()))
+                    .collect(),
+            ),
+            Dependencies::Unavailable(reason) => Dependencies::Unavailable(reason),
+        })
+    }
 
-        if !term_intersection.contains(&v) {
-            return Err(PubGrubError::Failure(
-                "choose_package_version picked an incompatible version".into(),
-            ));
+    fn prefetch(&self, requests: &[(&Self::P, &Self::VS)]) {
+        let requests: Vec<(&DP::P, &DP::VS)> = requests
+            .iter()
+            .filter_map(|&(package, range)| match package {
+                MultiRootPackage::Root => None,
+                MultiRootPackage::Package(p) => Some((p, &range.versions)),
+            })
+            .collect();
+        if !requests.is_empty() {
+            self.dependency_provider.prefetch(&requests)
         }
+    }
 
-        let is_new_dependency = added_dependencies
-            .entry(next.clone())
-            .or_default()
-            .insert(v.clone());
+    forward_to_dependency_provider!(dependency_provider: should_cancel, on_progress);
+}
 
-        if is_new_dependency {
-            // Retrieve that package dependencies.
-            let p = &next;
-            let dependencies = dependency_provider.get_dependencies(p, &v).map_err(|err| {
-                PubGrubError::ErrorRetrievingDependencies {
-                    package: p.clone(),
-                    version: v.clone(),
-                    source: err,
+/// Remove the hidden root of [resolve_multi] and [resolve_constraints] from a derivation tree.
+///
+/// The dependency of the hidden root on a root package of `root_versions` becomes
+/// the [NotRoot](External::NotRoot) incompatibility of that root package.
+/// The derivations with the [NotRoot](External::NotRoot) incompatibility of the hidden root
+/// are replaced by their other cause, which then has the same terms.
+/// The other dependencies of the hidden root, the constraints of [resolve_constraints],
+/// are removed the same way, and the other cause keeps the term of the constrained package.
+/// Returns [None] for these incompatibilities of the hidden root.
+fn strip_hidden_root<P: Package, VS: VersionSet, M: Eq + Clone + Debug + Display>(
+    derivation_tree: &DerivationTree<MultiRootPackage<P>, MultiRootVersionSet<VS>, M>,
+    root_versions: &Map<P, VS::V>,
+) -> Option<DerivationTree<P, VS, M>> {
+    use MultiRootPackage::{Package as Pkg, Root};
+    let unexpected_root = || unreachable!("only the hidden root depends on the hidden root");
+    match derivation_tree {
+        DerivationTree::External(external) => Some(DerivationTree::External(match external {
+            External::NotRoot(Root, _) => return None,
+            External::FromDependencyOf(Root, _, Pkg(p), _) => match root_versions.get(p) {
+                Some(v) => External::NotRoot(p.clone(), v.clone()),
+                None => return None,
+            },
+            External::NotRoot(Pkg(p), v) => External::NotRoot(p.clone(), v.clone().into_inner()),
+            External::NoVersions(Pkg(p), range) => {
+                External::NoVersions(p.clone(), range.versions.clone())
+            }
+            External::PackageNotFound(Pkg(p)) => External::PackageNotFound(p.clone()),
+            External::FromDependencyOf(Pkg(p1), range1, Pkg(p2), range2) => {
+                External::FromDependencyOf(
+                    p1.clone(),
+                    range1.versions.clone(),
+                    p2.clone(),
+                    range2.versions.clone(),
+                )
+            }
+            External::Custom(Pkg(p), range, metadata) => {
+                External::Custom(p.clone(), range.versions.clone(), metadata.clone())
+            }
+            External::NoVersions(Root, _)
+            | External::PackageNotFound(Root)
+            | External::FromDependencyOf(_, _, Root, _)
+            | External::Custom(Root, _, _) => unexpected_root(),
+        })),
+        DerivationTree::Derived(derived) => {
+            let cause1 = strip_hidden_root(&derived.cause1, root_versions);
+            let cause2 = strip_hidden_root(&derived.cause2, root_versions);
+            match (cause1, cause2) {
+                (Some(cause1), Some(cause2)) => Some(DerivationTree::Derived(Derived {
+                    terms: derived
+                        .terms
+                        .iter()
+                        .filter_map(|(p, term)| match (p, term) {
+                            (Root, _) => None,
+                            (Pkg(p), Term::Positive(range)) => {
+                                Some((p.clone(), Term::Positive(range.versions.clone())))
+                            }
+                            (Pkg(p), Term::Negative(range)) => {
+                                Some((p.clone(), Term::Negative(range.versions.clone())))
+                            }
+                        })
+                        .collect(),
+                    shared_id: derived.shared_id,
+                    cause1: Arc::new(cause1),
+                    cause2: Arc::new(cause2),
+                })),
+                (Some(cause), None) | (None, Some(cause)) => Some(cause),
+                (None, None) => None,
+            }
+        }
+    }
+}
+
+/// Same as [resolve], but packages with the same priority are decided
+/// in the order of their names, smallest first.
+///
+/// Otherwise, the order between packages with the same priority is the order
+/// in which they were discovered, which for the dependencies of a same version
+/// is the iteration order of the [DependencyConstraints] map.
+/// With this tie-break, the resolution only depends on the priorities and the package names,
+/// which makes it reproducible across runs and machines, whatever the hasher.
+pub fn resolve_deterministic<DP: DependencyProvider>(
+    dependency_provider: &DP,
+    package: DP::P,
+    version: impl Into<DP::V>,
+) -> Result<SelectedDependencies<DP>, PubGrubError<DP>>
+where
+    DP::P: Ord,
+{
+    let dependency_provider = DeterministicDependencyProvider(dependency_provider);
+    resolve(&dependency_provider, package, version).map_err(unwrap_error)
+}
+
+/// Same as [resolve], but when there is no solution,
+/// keep looking for other conflicts independent from the ones already found.
+///
+/// After each conflict, the dependencies of the root package taking part in it
+/// (see [root_causes](crate::DerivationTree::root_causes)) are removed,
+/// and the resolution is started again.
+/// This stops when the remaining dependencies can be satisfied,
+/// or when a conflict does not involve any remaining dependency of the root package.
+/// All the conflicts found are then returned in a [PubGrubError::IndependentConflicts],
+/// in the order they were found.
+pub fn resolve_all_conflicts<DP: DependencyProvider>(
+    dependency_provider: &DP,
+    package: DP::P,
+    version: impl Into<DP::V>,
+) -> Result<SelectedDependencies<DP>, PubGrubError<DP>> {
+    let mut dependency_provider = RelaxedRootDependencyProvider {
+        dependency_provider,
+        root: package,
+        root_version: version.into(),
+        removed: Set::default(),
+    };
+    let mut conflicts = Vec::new();
+    loop {
+        let root = dependency_provider.root.clone();
+        let root_version = dependency_provider.root_version.clone();
+        match resolve(&dependency_provider, root.clone(), root_version) {
+            Ok(solution) if conflicts.is_empty() => return Ok(solution),
+            Ok(_) => return Err(PubGrubError::IndependentConflicts(conflicts)),
+            Err(PubGrubError::NoSolution(derivation_tree)) => {
+                let mut relaxed = false;
+                for (dependency, _) in derivation_tree.root_causes(&root) {
+                    relaxed |= dependency_provider.removed.insert(dependency);
                 }
-            })?;
+                conflicts.push(derivation_tree);
+                if !relaxed {
+                    return Err(PubGrubError::IndependentConflicts(conflicts));
+                }
+            }
+            Err(err) => return Err(unwrap_error(err)),
+        }
+    }
+}
+
+/// Convert the error of a [DependencyProvider] wrapping another one
+/// into the error of the wrapped one.
+fn unwrap_error<W, DP>(err: PubGrubError<W>) -> PubGrubError<DP>
+where
+    DP: DependencyProvider,
+    W: DependencyProvider<P = DP::P, V = DP::V, VS = DP::VS, M = DP::M, Err = DP::Err>,
+{
+    match err {
+        PubGrubError::NoSolution(tree) => PubGrubError::NoSolution(tree),
+        PubGrubError::IndependentConflicts(trees) => PubGrubError::IndependentConflicts(trees),
+        PubGrubError::ErrorRetrievingDependencies {
+            package,
+            version,
+            source,
+        } => PubGrubError::ErrorRetrievingDependencies {
+            package,
+            version,
+            source,
+        },
+        PubGrubError::ErrorChoosingPackageVersion(err) => {
+            PubGrubError::ErrorChoosingPackageVersion(err)
+        }
+        PubGrubError::ErrorInShouldCancel { source, decided } => {
+            PubGrubError::ErrorInShouldCancel { source, decided }
+        }
+        PubGrubError::LimitExceeded {
+            incompatibilities,
+            decision_level,
+        } => PubGrubError::LimitExceeded {
+            incompatibilities,
+            decision_level,
+        },
+        PubGrubError::InvalidVersionChoice {
+            package,
+            version,
+            range,
+        } => PubGrubError::InvalidVersionChoice {
+            package,
+            version,
+            range,
+        },
+        PubGrubError::Failure(msg) => PubGrubError::Failure(msg),
+    }
+}
+
+/// Wraps a [DependencyProvider] to remove some dependencies of the root package.
+struct RelaxedRootDependencyProvider<'a, DP: DependencyProvider> {
+    dependency_provider: &'a DP,
+    root: DP::P,
+    root_version: DP::V,
+    removed: Set<DP::P>,
+}
+
+impl<DP: DependencyProvider> DependencyProvider for RelaxedRootDependencyProvider<'_, DP> {
+    type P = DP::P;
+    type V = DP::V;
+    type VS = DP::VS;
+    type M = DP::M;
+
+    type Priority = DP::Priority;
+
+    type Err = DP::Err;
+
+    fn get_dependencies(
+        &self,
+        package: &DP::P,
+        version: &DP::V,
+    ) -> Result<Dependencies<DP::P, DP::VS, DP::M>, DP::Err> {
+        let mut dependencies = self
+            .dependency_provider
+            .get_dependencies(package, version)?;
+        if package == &self.root && version == &self.root_version {
+            if let Dependencies::Available(constraints) = &mut dependencies {
+                constraints.retain(|dependency, _| !self.removed.contains(dependency));
+            }
+        }
+        Ok(dependencies)
+    }
 
-            let dependencies = match dependencies {
-                Dependencies::Unavailable(reason) => {
-                    state.add_incompatibility(Incompatibility::custom_version(
-                        p.clone(),
-                        v.clone(),
-                        reason,
-                    ));
-                    continue;
+    forward_to_dependency_provider!(
+        dependency_provider: prioritize, choose_version, choose_version_with_tried, package_exists,
+        is_deprioritized, version_preference, prefetch, should_cancel, on_progress
+    );
+}
+
+/// Wraps a [DependencyProvider] to choose preferred versions first.
+struct PreferencesDependencyProvider<'a, DP: DependencyProvider> {
+    dependency_provider: &'a DP,
+    preferences: &'a Map<DP::P, DP::V>,
+}
+
+impl<DP: DependencyProvider> PreferencesDependencyProvider<'_, DP> {
+    /// Call `choose` on the preferred version first, if it is in the range and was not tried yet,
+    /// and on the whole range if the wrapped provider does not have it.
+    fn choose_preferred(
+        &self,
+        package: &DP::P,
+        range: &DP::VS,
+        already_tried: &BTreeSet<DP::V>,
+        mut choose: impl FnMut(&DP::VS) -> Result<Option<DP::V>, DP::Err>,
+    ) -> Result<Option<DP::V>, DP::Err> {
+        if let Some(preferred) = self.preferences.get(package) {
+            if range.contains(preferred) && !already_tried.contains(preferred) {
+                let preferred_range = range.intersection(&DP::VS::singleton(preferred.clone()));
+                if let Some(version) = choose(&preferred_range)? {
+                    return Ok(Some(version));
                 }
-                Dependencies::Available(x) => x,
-            };
+            }
+        }
+        choose(range)
+    }
+}
+
+impl<DP: DependencyProvider> DependencyProvider for PreferencesDependencyProvider<'_, DP> {
+    type P = DP::P;
+    type V = DP::V;
+    type VS = DP::VS;
+    type M = DP::M;
+
+    type Priority = DP::Priority;
+
+    type Err = DP::Err;
+
+    fn choose_version(&self, package: &DP::P, range: &DP::VS) -> Result<Option<DP::V>, DP::Err> {
+        self.choose_preferred(package, range, &BTreeSet::new(), |range| {
+            self.dependency_provider.choose_version(package, range)
+        })
+    }
 
-            // Add that package and version if the dependencies are not problematic.
-            let dep_incompats =
-                state.add_incompatibility_from_dependencies(p.clone(), v.clone(), dependencies);
+    fn choose_version_with_tried(
+        &self,
+        package: &DP::P,
+        range: &DP::VS,
+        already_tried: &BTreeSet<DP::V>,
+    ) -> Result<Option<DP::V>, DP::Err> {
+        self.choose_preferred(package, range, already_tried, |range| {
+            self.dependency_provider
+                .choose_version_with_tried(package, range, already_tried)
+        })
+    }
+
+    forward_to_dependency_provider!(
+        dependency_provider: prioritize, package_exists, is_deprioritized, version_preference,
+        get_dependencies, prefetch, should_cancel, on_progress
+    );
+}
+
+/// Wraps a [DependencyProvider] to skip its deprioritized versions,
+/// for the resolutions of [resolve_with_deprioritized].
+struct SkipDeprioritizedDependencyProvider<'a, DP: DependencyProvider> {
+    dependency_provider: &'a DP,
+    /// Whether a deprioritized version is chosen when there is no other one in the range,
+    /// instead of no version at all.
+    fallback: bool,
+    /// Whether a deprioritized version was skipped.
+    skipped: Cell<bool>,
+}
 
-            state.partial_solution.add_version(
-                p.clone(),
-                v.clone(),
-                dep_incompats,
-                &state.incompatibility_store,
-            );
+impl<DP: DependencyProvider> SkipDeprioritizedDependencyProvider<'_, DP> {
+    /// Call `choose` on smaller and smaller ranges until it returns a version
+    /// that is not deprioritized,
+    /// and on the whole range again if there is none and `fallback` is set.
+    fn skip_deprioritized(
+        &self,
+        package: &DP::P,
+        range: &DP::VS,
+        mut choose: impl FnMut(&DP::VS) -> Result<Option<DP::V>, DP::Err>,
+    ) -> Result<Option<DP::V>, DP::Err> {
+        let mut remaining = range.clone();
+        while let Some(version) = choose(&remaining)? {
+            if !self.dependency_provider.is_deprioritized(package, &version) {
+                return Ok(Some(version));
+            }
+            self.skipped.set(true);
+            remaining = remaining.intersection(&DP::VS::singleton(version).complement());
+        }
+        if self.fallback {
+            choose(range)
         } else {
-            // `dep_incompats` are already in `incompatibilities` so we know there are not satisfied
-            // terms and can add the decision directly.
-            info!("add_decision (not first time): {} @ {}", &next, v);
-            state.partial_solution.add_decision(next.clone(), v);
+            Ok(None)
         }
     }
 }
 
+impl<DP: DependencyProvider> DependencyProvider for SkipDeprioritizedDependencyProvider<'_, DP> {
+    type P = DP::P;
+    type V = DP::V;
+    type VS = DP::VS;
+    type M = DP::M;
+
+    type Priority = DP::Priority;
+
+    type Err = DP::Err;
+
+    fn choose_version(&self, package: &DP::P, range: &DP::VS) -> Result<Option<DP::V>, DP::Err> {
+        self.skip_deprioritized(package, range, |range| {
+            self.dependency_provider.choose_version(package, range)
+        })
+    }
+
+    fn choose_version_with_tried(
+        &self,
+        package: &DP::P,
+        range: &DP::VS,
+        already_tried: &BTreeSet<DP::V>,
+    ) -> Result<Option<DP::V>, DP::Err> {
+        self.skip_deprioritized(package, range, |range| {
+            self.dependency_provider
+                .choose_version_with_tried(package, range, already_tried)
+        })
+    }
+
+    forward_to_dependency_provider!(
+        dependency_provider: prioritize, package_exists, is_deprioritized, version_preference,
+        get_dependencies, prefetch, should_cancel, on_progress
+    );
+}
+
+/// Wraps a [DependencyProvider] to break ties between equal priorities by package name.
+struct DeterministicDependencyProvider<'a, DP: DependencyProvider>(&'a DP);
+
+impl<DP: DependencyProvider> DependencyProvider for DeterministicDependencyProvider<'_, DP>
+where
+    DP::P: Ord,
+{
+    type P = DP::P;
+    type V = DP::V;
+    type VS = DP::VS;
+    type M = DP::M;
+
+    fn prioritize(&self, package: &DP::P, range: &DP::VS) -> Self::Priority {
+        // The highest priority is decided first, so the smallest name must be the highest.
+        (self.0.prioritize(package, range), Reverse(package.clone()))
+    }
+
+    type Priority = (DP::Priority, Reverse<DP::P>);
+
+    type Err = DP::Err;
+
+    forward_to_dependency_provider!(
+        0: choose_version, choose_version_with_tried, package_exists, is_deprioritized,
+        version_preference, get_dependencies, prefetch, should_cancel, on_progress
+    );
+}
+
+/// Progress of a resolution, given to [DependencyProvider::on_progress].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ProgressInfo {
+    /// Current decision level, incremented by each decision and lowered by backtracking.
+    pub decision_level: u32,
+    /// Number of packages with a chosen version.
+    pub decided_count: usize,
+    /// Number of packages required by the current decisions, but without a chosen version yet.
+    pub undecided_count: usize,
+    /// Number of conflicts resolved so far.
+    pub conflict_count: u64,
+}
+
 /// An enum used by [DependencyProvider] that holds information about package dependencies.
 /// For each [Package] there is a set of versions allowed as a dependency.
 #[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Dependencies<P: Package, VS: VersionSet, M: Eq + Clone + Debug + Display> {
     /// Package dependencies are unavailable with the reason why they are missing.
     Unavailable(M),
@@ -238,6 +1801,11 @@ pub trait DependencyProvider {
     /// > since these packages will run out of versions to try more quickly.
     /// > But there's likely room for improvement in these heuristics.
     ///
+    /// Packages with equal priorities are picked in the order the resolver first saw them,
+    /// independently of their hashes.
+    /// The dependencies of a version are seen in the order they are returned by
+    /// [get_dependencies](DependencyProvider::get_dependencies).
+    ///
     /// Note: the resolver may call this even when the range has not changed,
     /// if it is more efficient for the resolvers internal data structures.
     fn prioritize(&self, package: &Self::P, range: &Self::VS) -> Self::Priority;
@@ -248,6 +1816,15 @@ pub trait DependencyProvider {
     /// the fewest versions that match the outstanding constraint.
     type Priority: Ord + Clone;
 
+    /// A priority favoring the packages with the fewest allowed versions,
+    /// estimated by [approximate_len](VersionSet::approximate_len).
+    ///
+    /// It can be returned by [prioritize](Self::prioritize) with `Priority = Reverse<usize>`,
+    /// to follow the Pub heuristic without counting versions.
+    fn prioritize_by_width(&self, range: &Self::VS) -> Reverse<usize> {
+        Reverse(range.approximate_len())
+    }
+
     /// The kind of error returned from these methods.
     ///
     /// Returning this signals that resolution should fail with this error.
@@ -262,6 +1839,59 @@ pub trait DependencyProvider {
         range: &Self::VS,
     ) -> Result<Option<Self::V>, Self::Err>;
 
+    /// Same as [choose_version](Self::choose_version), also given the versions of the package
+    /// that were already decided earlier in this resolution, before backtracking.
+    ///
+    /// When choosing a version is expensive, for example because it requires parsing manifests,
+    /// this lets the provider skip the work for versions the solver went back on.
+    /// Those versions may still be in `range` and part of a solution,
+    /// so returning one of them is always correct.
+    /// If not provided, `already_tried` is ignored and [choose_version](Self::choose_version)
+    /// is called.
+    fn choose_version_with_tried(
+        &self,
+        package: &Self::P,
+        range: &Self::VS,
+        already_tried: &BTreeSet<Self::V>,
+    ) -> Result<Option<Self::V>, Self::Err> {
+        let _ = already_tried;
+        self.choose_version(package, range)
+    }
+
+    /// Whether the package exists at all, called when
+    /// [choose_version](Self::choose_version) finds no version of it.
+    ///
+    /// A package that does not exist is reported as [PackageNotFound](External::PackageNotFound)
+    /// instead of [NoVersions](External::NoVersions),
+    /// to tell an unknown package, like a typo, from a package without a matching version.
+    /// If not provided, all packages exist.
+    fn package_exists(&self, package: &Self::P) -> bool {
+        let _ = package;
+        true
+    }
+
+    /// Whether this version should only be used as a last resort, like a yanked version.
+    ///
+    /// [resolve_with_deprioritized] first looks for a solution without these versions,
+    /// and only uses them when there is none.
+    /// The other resolution functions ignore it.
+    /// If not provided, no version is deprioritized.
+    fn is_deprioritized(&self, package: &Self::P, version: &Self::V) -> bool {
+        let _ = (package, version);
+        false
+    }
+
+    /// Which version of the package is preferred among the ones allowed.
+    ///
+    /// The resolver itself only calls [choose_version](Self::choose_version),
+    /// this lets a provider declare its selection policy instead of implementing it,
+    /// for [OfflineDependencyProvider] or with [VersionPreference::choose].
+    /// If not provided, the newest version is preferred.
+    fn version_preference(&self, package: &Self::P) -> VersionPreference<Self::V> {
+        let _ = package;
+        VersionPreference::Newest
+    }
+
     /// Retrieves the package dependencies.
     /// Return [Dependencies::Unavailable] if its dependencies are unavailable.
     #[allow(clippy::type_complexity)]
@@ -271,15 +1901,110 @@ pub trait DependencyProvider {
         version: &Self::V,
     ) -> Result<Dependencies<Self::P, Self::VS, Self::M>, Self::Err>;
 
+    /// Called with packages and ranges whose versions and dependencies
+    /// are likely to be retrieved soon.
+    ///
+    /// When the dependencies of a version are retrieved, the resolver calls `prefetch`
+    /// with these dependencies, so that the provider can fetch what it will need for them
+    /// together, for example in one network request, before they are decided one by one.
+    /// The resolver does not choose any version for this,
+    /// which versions to fetch is up to the provider.
+    /// This is only speculative: some of these packages may never be decided.
+    /// If not provided, nothing is done.
+    fn prefetch(&self, requests: &[(&Self::P, &Self::VS)]) {
+        let _ = requests;
+    }
+
     /// This is called fairly regularly during the resolution,
-    /// if it returns an Err then resolution will be terminated.
+    /// if it returns an Err then resolution will be terminated
+    /// with [PubGrubError::ErrorInShouldCancel], carrying the packages decided so far.
     /// This is helpful if you want to add some form of early termination like a timeout,
     /// or you want to add some form of user feedback if things are taking a while.
     /// If not provided the resolver will run as long as needed.
     fn should_cancel(&self) -> Result<(), Self::Err> {
         Ok(())
     }
+
+    /// This is called after each unit propagation, before the next decision,
+    /// with information about the progress of the resolution.
+    /// This is helpful to give live feedback to the user during long resolutions.
+    /// If not provided, nothing is done.
+    fn on_progress(&self, _info: ProgressInfo) {}
+}
+
+/// Implements the given methods of [DependencyProvider] by forwarding them
+/// to the dependency provider in the given field of `self`,
+/// for wrappers keeping the package, version and error types of the one they wrap.
+macro_rules! forward_to_dependency_provider {
+    ($field:tt: $($method:ident),+ $(,)?) => {
+        $($crate::solver::forward_to_dependency_provider!(@ $field $method);)+
+    };
+    (@ $field:tt prioritize) => {
+        fn prioritize(&self, package: &Self::P, range: &Self::VS) -> Self::Priority {
+            self.$field.prioritize(package, range)
+        }
+    };
+    (@ $field:tt choose_version) => {
+        fn choose_version(
+            &self,
+            package: &Self::P,
+            range: &Self::VS,
+        ) -> Result<Option<Self::V>, Self::Err> {
+            self.$field.choose_version(package, range)
+        }
+    };
+    (@ $field:tt choose_version_with_tried) => {
+        fn choose_version_with_tried(
+            &self,
+            package: &Self::P,
+            range: &Self::VS,
+            already_tried: &BTreeSet<Self::V>,
+        ) -> Result<Option<Self::V>, Self::Err> {
+            self.$field
+                .choose_version_with_tried(package, range, already_tried)
+        }
+    };
+    (@ $field:tt package_exists) => {
+        fn package_exists(&self, package: &Self::P) -> bool {
+            self.$field.package_exists(package)
+        }
+    };
+    (@ $field:tt is_deprioritized) => {
+        fn is_deprioritized(&self, package: &Self::P, version: &Self::V) -> bool {
+            self.$field.is_deprioritized(package, version)
+        }
+    };
+    (@ $field:tt version_preference) => {
+        fn version_preference(&self, package: &Self::P) -> VersionPreference<Self::V> {
+            self.$field.version_preference(package)
+        }
+    };
+    (@ $field:tt get_dependencies) => {
+        fn get_dependencies(
+            &self,
+            package: &Self::P,
+            version: &Self::V,
+        ) -> Result<Dependencies<Self::P, Self::VS, Self::M>, Self::Err> {
+            self.$field.get_dependencies(package, version)
+        }
+    };
+    (@ $field:tt prefetch) => {
+        fn prefetch(&self, requests: &[(&Self::P, &Self::VS)]) {
+            self.$field.prefetch(requests)
+        }
+    };
+    (@ $field:tt should_cancel) => {
+        fn should_cancel(&self) -> Result<(), Self::Err> {
+            self.$field.should_cancel()
+        }
+    };
+    (@ $field:tt on_progress) => {
+        fn on_progress(&self, info: ProgressInfo) {
+            self.$field.on_progress(info)
+        }
+    };
 }
+pub(crate) use forward_to_dependency_provider;
 
 /// A basic implementation of [DependencyProvider].
 #[derive(Debug, Clone, Default)]
@@ -294,6 +2019,60 @@ pub trait DependencyProvider {
 #[cfg_attr(feature = "serde", serde(transparent))]
 pub struct OfflineDependencyProvider<P: Package, VS: VersionSet> {
     dependencies: Map<P, BTreeMap<VS::V, DependencyConstraints<P, VS>>>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    default_version_preference: VersionPreference<VS::V>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    version_preferences: Map<P, VersionPreference<VS::V>>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    priority_strategy: PriorityStrategy,
+}
+
+/// Which version of a package is preferred among the ones allowed,
+/// returned by [DependencyProvider::version_preference].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum VersionPreference<V> {
+    /// Prefer the newest version.
+    #[default]
+    Newest,
+    /// Prefer the oldest version.
+    Oldest,
+    /// Prefer the given version, or else the oldest version newer than it,
+    /// or else the newest version older than it.
+    ClosestTo(V),
+}
+
+impl<V: Ord> VersionPreference<V> {
+    /// The preferred version among the allowed ones, given in increasing order.
+    pub fn choose<'a>(&self, mut versions: impl DoubleEndedIterator<Item = &'a V>) -> Option<&'a V>
+    where
+        V: 'a,
+    {
+        match self {
+            Self::Newest => versions.next_back(),
+            Self::Oldest => versions.next(),
+            Self::ClosestTo(target) => {
+                let mut older = None;
+                for v in versions {
+                    if v >= target {
+                        return Some(v);
+                    }
+                    older = Some(v);
+                }
+                older
+            }
+        }
+    }
+}
+
+/// Which package [OfflineDependencyProvider] decides first.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum PriorityStrategy {
+    /// Decide first the package with the fewest versions allowed.
+    /// This tends to find conflicts earlier if any exist.
+    #[default]
+    FewestVersions,
+    /// Decide first the package with the most versions allowed.
+    MostVersions,
 }
 
 impl<P: Package, VS: VersionSet> OfflineDependencyProvider<P, VS> {
@@ -301,9 +2080,41 @@ impl<P: Package, VS: VersionSet> OfflineDependencyProvider<P, VS> {
     pub fn new() -> Self {
         Self {
             dependencies: Map::default(),
+            default_version_preference: VersionPreference::default(),
+            version_preferences: Map::default(),
+            priority_strategy: PriorityStrategy::default(),
         }
     }
 
+    /// Sets which version of a package is chosen among the ones allowed,
+    /// for the packages without a [version preference](Self::with_version_preference) of their own.
+    /// The default is [VersionPreference::Newest].
+    pub fn with_default_version_preference(
+        mut self,
+        version_preference: VersionPreference<VS::V>,
+    ) -> Self {
+        self.default_version_preference = version_preference;
+        self
+    }
+
+    /// Sets which version of a package is chosen among the ones allowed,
+    /// instead of the [default version preference](Self::with_default_version_preference).
+    pub fn with_version_preference(
+        mut self,
+        package: P,
+        version_preference: VersionPreference<VS::V>,
+    ) -> Self {
+        self.version_preferences.insert(package, version_preference);
+        self
+    }
+
+    /// Sets which package is decided first.
+    /// The default is [PriorityStrategy::FewestVersions].
+    pub fn with_priority_strategy(mut self, priority_strategy: PriorityStrategy) -> Self {
+        self.priority_strategy = priority_strategy;
+        self
+    }
+
     /// Registers the dependencies of a package and version pair.
     /// Dependencies must be added with a single call to
     /// [add_dependencies](OfflineDependencyProvider::add_dependencies).
@@ -330,6 +2141,87 @@ impl<P: Package, VS: VersionSet> OfflineDependencyProvider<P, VS> {
             .or_default() = package_deps;
     }
 
+    /// Creates a provider from individual dependency edges,
+    /// given as `(package, version, dependency, range)`.
+    ///
+    /// The edges are grouped by package and version,
+    /// so that each package version pair gets all its dependencies at once,
+    /// as with [add_dependencies](OfflineDependencyProvider::add_dependencies).
+    /// Several edges from the same package version to the same dependency
+    /// are merged into the intersection of their ranges.
+    /// Package versions without dependencies have no edge,
+    /// they can be added afterwards with an empty list of dependencies.
+    pub fn from_edges(edges: impl IntoIterator<Item = (P, VS::V, P, VS)>) -> Self {
+        let mut provider = Self::new();
+        for (package, version, dependency, range) in edges {
+            let dependencies = provider
+                .dependencies
+                .entry(package)
+                .or_default()
+                .entry(version)
+                .or_default();
+            match dependencies.get_mut(&dependency) {
+                Some(existing) => *existing = existing.intersection(&range),
+                None => {
+                    dependencies.insert(dependency, range);
+                }
+            }
+        }
+        provider
+    }
+
+    /// Removes a version of a package, with its dependencies.
+    /// Does nothing if that package version pair was not saved.
+    pub fn remove_version(&mut self, package: &P, version: &VS::V) {
+        if let Some(versions) = self.dependencies.get_mut(package) {
+            versions.remove(version);
+            if versions.is_empty() {
+                self.dependencies.remove(package);
+            }
+        }
+    }
+
+    /// Removes all versions of a package, with their dependencies.
+    /// Does nothing if that package was not saved.
+    pub fn remove_package(&mut self, package: &P) {
+        self.dependencies.remove(package);
+    }
+
+    /// Adds all the dependencies saved in another provider.
+    /// When a package version pair is in both providers,
+    /// the dependencies from `other` replace the ones already saved,
+    /// like with [add_dependencies](OfflineDependencyProvider::add_dependencies).
+    pub fn merge(&mut self, other: Self) {
+        for (package, versions) in other.dependencies {
+            self.dependencies
+                .entry(package)
+                .or_default()
+                .extend(versions);
+        }
+    }
+
+    /// Same as [merge](OfflineDependencyProvider::merge),
+    /// but fails without changing anything if a package version pair is in both providers
+    /// with different dependencies.
+    /// The error contains one of the conflicting pairs.
+    pub fn try_merge(&mut self, other: Self) -> Result<(), MergeConflict<P, VS::V>> {
+        for (package, versions) in &other.dependencies {
+            let Some(self_versions) = self.dependencies.get(package) else {
+                continue;
+            };
+            for (version, dependencies) in versions {
+                if matches!(self_versions.get(version), Some(deps) if deps != dependencies) {
+                    return Err(MergeConflict {
+                        package: package.clone(),
+                        version: version.clone(),
+                    });
+                }
+            }
+        }
+        self.merge(other);
+        Ok(())
+    }
+
     /// Lists packages that have been saved.
     pub fn packages(&self) -> impl Iterator<Item = &P> {
         self.dependencies.keys()
@@ -341,6 +2233,107 @@ impl<P: Package, VS: VersionSet> OfflineDependencyProvider<P, VS> {
         self.dependencies.get(package).map(|k| k.keys())
     }
 
+    /// Whether at least one version of the package has been saved.
+    ///
+    /// ```
+    /// # use pubgrub::{OfflineDependencyProvider, Range};
+    /// let mut dependency_provider = OfflineDependencyProvider::<&str, Range<u32>>::new();
+    /// dependency_provider.add_dependencies("a", 1u32, []);
+    /// assert!(dependency_provider.contains_package(&"a"));
+    /// assert!(!dependency_provider.contains_package(&"b"));
+    /// ```
+    pub fn contains_package(&self, package: &P) -> bool {
+        self.dependencies.contains_key(package)
+    }
+
+    /// Whether that package version pair has been saved.
+    ///
+    /// ```
+    /// # use pubgrub::{OfflineDependencyProvider, Range};
+    /// let mut dependency_provider = OfflineDependencyProvider::<&str, Range<u32>>::new();
+    /// dependency_provider.add_dependencies("a", 1u32, []);
+    /// assert!(dependency_provider.contains_version(&"a", &1));
+    /// assert!(!dependency_provider.contains_version(&"a", &2));
+    /// assert!(!dependency_provider.contains_version(&"b", &1));
+    /// ```
+    pub fn contains_version(&self, package: &P, version: &VS::V) -> bool {
+        self.dependencies
+            .get(package)
+            .is_some_and(|versions| versions.contains_key(version))
+    }
+
+    /// Number of direct dependencies of a given package and version.
+    /// Returns [None] if no information is available regarding that package and version pair.
+    ///
+    /// ```
+    /// # use pubgrub::{OfflineDependencyProvider, Range};
+    /// let mut dependency_provider = OfflineDependencyProvider::<&str, Range<u32>>::new();
+    /// dependency_provider.add_dependencies("a", 1u32, [("b", Range::full()), ("c", Range::full())]);
+    /// dependency_provider.add_dependencies("b", 1u32, []);
+    /// assert_eq!(dependency_provider.dependency_count(&"a", &1), Some(2));
+    /// assert_eq!(dependency_provider.dependency_count(&"b", &1), Some(0));
+    /// assert_eq!(dependency_provider.dependency_count(&"c", &1), None);
+    /// ```
+    pub fn dependency_count(&self, package: &P, version: &VS::V) -> Option<usize> {
+        Some(self.dependencies.get(package)?.get(version)?.len())
+    }
+
+    /// Lists all saved package, version and dependencies triples.
+    /// Versions of a given package are in sorted order.
+    pub fn iter(&self) -> impl Iterator<Item = (&P, &VS::V, &DependencyConstraints<P, VS>)> {
+        self.dependencies.iter().flat_map(|(package, versions)| {
+            versions
+                .iter()
+                .map(move |(version, dependencies)| (package, version, dependencies))
+        })
+    }
+
+    /// Renders the saved dependencies as a [Graphviz](https://graphviz.org) directed graph
+    /// in the DOT language.
+    ///
+    /// There is one node per saved package version pair, named `"package version"`.
+    /// Each dependency has an edge, labeled with its range, to every saved version
+    /// of the dependency in that range,
+    /// or else a single edge to a node named after the dependency package.
+    ///
+    /// ```
+    /// # use pubgrub::{OfflineDependencyProvider, Range};
+    /// let mut dependency_provider = OfflineDependencyProvider::<&str, Range<u32>>::new();
+    /// dependency_provider.add_dependencies("a", 1u32, [("b", Range::higher_than(2u32))]);
+    /// dependency_provider.add_dependencies("b", 2u32, []);
+    /// let dot = dependency_provider.to_dot();
+    /// assert!(dot.contains(r#""a 1" -> "b 2" [label=">=2"];"#));
+    /// ```
+    pub fn to_dot(&self) -> String {
+        // DOT identifiers are quoted, with `"` and `\` escaped.
+        let quote = |id: String| format!("\"{}\"", id.replace('\\', "\\\\").replace('"', "\\\""));
+        let mut dot = String::from("digraph dependencies {\n");
+        for (package, version, _) in self.iter() {
+            writeln!(dot, "    {};", quote(format!("{package} {version}"))).unwrap();
+        }
+        for (package, version, dependencies) in self.iter() {
+            let node = quote(format!("{package} {version}"));
+            for (dependency, range) in dependencies {
+                let label = quote(range.to_string());
+                let mut targets: Vec<String> = self
+                    .versions(dependency)
+                    .into_iter()
+                    .flatten()
+                    .filter(|v| range.contains(v))
+                    .map(|v| quote(format!("{dependency} {v}")))
+                    .collect();
+                if targets.is_empty() {
+                    targets.push(quote(dependency.to_string()));
+                }
+                for target in targets {
+                    writeln!(dot, "    {node} -> {target} [label={label}];").unwrap();
+                }
+            }
+        }
+        dot.push_str("}\n");
+        dot
+    }
+
     /// Lists dependencies of a given package and version.
     /// Returns [None] if no information is available regarding that package and version pair.
     fn dependencies(&self, package: &P, version: &VS::V) -> Option<DependencyConstraints<P, VS>> {
@@ -348,11 +2341,25 @@ impl<P: Package, VS: VersionSet> OfflineDependencyProvider<P, VS> {
     }
 }
 
+/// Error returned by [OfflineDependencyProvider::try_merge]
+/// when both providers have different dependencies for the same package version pair.
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+#[error("the dependencies of {package} {version} are different in both providers")]
+pub struct MergeConflict<P: Package, V: Debug + Display> {
+    /// Package with conflicting dependencies.
+    pub package: P,
+    /// Version with conflicting dependencies.
+    pub version: V,
+}
+
 /// An implementation of [DependencyProvider] that
 /// contains all dependency information available in memory.
-/// Currently packages are picked with the fewest versions contained in the constraints first.
+/// By default, packages are picked with the fewest versions contained in the constraints first.
 /// But, that may change in new versions if better heuristics are found.
-/// Versions are picked with the newest versions first.
+/// Versions are picked with the newest versions first by default.
+/// Both can be configured with
+/// [with_priority_strategy](OfflineDependencyProvider::with_priority_strategy) and
+/// [with_default_version_preference](OfflineDependencyProvider::with_default_version_preference).
 impl<P: Package, VS: VersionSet> DependencyProvider for OfflineDependencyProvider<P, VS> {
     type P = P;
     type V = VS::V;
@@ -362,20 +2369,35 @@ impl<P: Package, VS: VersionSet> DependencyProvider for OfflineDependencyProvide
     type Err = Infallible;
 
     fn choose_version(&self, package: &P, range: &VS) -> Result<Option<VS::V>, Infallible> {
-        Ok(self
-            .dependencies
+        let Some(versions) = self.dependencies.get(package) else {
+            return Ok(None);
+        };
+        let versions = versions.keys().filter(|v| range.contains(v));
+        Ok(self.version_preference(package).choose(versions).cloned())
+    }
+
+    fn package_exists(&self, package: &P) -> bool {
+        self.contains_package(package)
+    }
+
+    fn version_preference(&self, package: &P) -> VersionPreference<VS::V> {
+        self.version_preferences
             .get(package)
-            .and_then(|versions| versions.keys().rev().find(|v| range.contains(v)).cloned()))
+            .unwrap_or(&self.default_version_preference)
+            .clone()
     }
 
     type Priority = Reverse<usize>;
     fn prioritize(&self, package: &P, range: &VS) -> Self::Priority {
-        Reverse(
-            self.dependencies
-                .get(package)
-                .map(|versions| versions.keys().filter(|v| range.contains(v)).count())
-                .unwrap_or(0),
-        )
+        let count = self
+            .dependencies
+            .get(package)
+            .map(|versions| versions.keys().filter(|v| range.contains(v)).count())
+            .unwrap_or(0);
+        match self.priority_strategy {
+            PriorityStrategy::FewestVersions => Reverse(count),
+            PriorityStrategy::MostVersions => Reverse(usize::MAX - count),
+        }
     }
 
     fn get_dependencies(
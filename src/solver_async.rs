@@ -0,0 +1,288 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! PubGrub version solving algorithm with an asynchronous dependency provider.
+//!
+//! When package metadata is retrieved over the network,
+//! [AsyncDependencyProvider] lets the resolver await that I/O
+//! instead of blocking inside [get_dependencies](DependencyProvider::get_dependencies).
+//! The algorithm itself is the same as in [resolve](crate::resolve),
+//! only the calls to the dependency provider are awaited by [resolve_async].
+//!
+//! No executor is provided: the future returned by [resolve_async]
+//! is run by the executor of the caller, for example the one of a Tokio runtime.
+//! The futures of an [AsyncDependencyProvider] are [Send],
+//! so the one of [resolve_async] can be spawned on a multi-threaded runtime
+//! when the provider is [Sync].
+
+use std::collections::BTreeSet;
+use std::error::Error;
+use std::fmt::{Debug, Display};
+use std::future::Future;
+use std::ops::ControlFlow;
+use std::pin::{pin, Pin};
+
+use crate::internal::State;
+use crate::solver::{
+    forward_to_dependency_provider, outcome_to_result, Resolution, ResolutionHooks, ResolveLimits,
+};
+use crate::{
+    Dependencies, DependencyProvider, Map, Package, ProgressInfo, PubGrubError,
+    SelectedDependencies, VersionPreference, VersionSet,
+};
+
+/// Asynchronous counterpart of [DependencyProvider].
+///
+/// Retrieving versions and dependencies is asynchronous,
+/// while the other methods stay synchronous
+/// since they are expected to only use already retrieved information.
+/// See [DependencyProvider] for the documentation of each item.
+/// The asynchronous methods can be implemented with `async fn`,
+/// as long as the futures they return are [Send].
+pub trait AsyncDependencyProvider {
+    /// How this provider stores the name of the packages.
+    type P: Package;
+
+    /// How this provider stores the versions of the packages.
+    type V: Debug + Display + Clone + Ord;
+
+    /// How this provider stores the version requirements for the packages.
+    type VS: VersionSet<V = Self::V>;
+
+    /// Type for custom incompatibilities.
+    type M: Eq + Clone + Debug + Display;
+
+    /// Priority of a package, the package with the highest priority is decided first.
+    fn prioritize(&self, package: &Self::P, range: &Self::VS) -> Self::Priority;
+
+    /// The type returned from `prioritize`.
+    type Priority: Ord + Clone;
+
+    /// The kind of error returned from these methods.
+    type Err: Error + 'static;
+
+    /// Choose the version of a package to use in the given range.
+    fn choose_version(
+        &self,
+        package: &Self::P,
+        range: &Self::VS,
+    ) -> impl Future<Output = Result<Option<Self::V>, Self::Err>> + Send;
+
+    /// Same as [choose_version](Self::choose_version), also given the versions of the package
+    /// that were already decided earlier in this resolution, before backtracking.
+    /// If not provided, `already_tried` is ignored.
+    fn choose_version_with_tried(
+        &self,
+        package: &Self::P,
+        range: &Self::VS,
+        already_tried: &BTreeSet<Self::V>,
+    ) -> impl Future<Output = Result<Option<Self::V>, Self::Err>> + Send {
+        let _ = already_tried;
+        self.choose_version(package, range)
+    }
+
+    /// Whether the package exists at all, called when
+    /// [choose_version](Self::choose_version) finds no version of it.
+    /// If not provided, all packages exist.
+    fn package_exists(&self, package: &Self::P) -> bool {
+        let _ = package;
+        true
+    }
+
+    /// Whether this version should only be used as a last resort, like a yanked version.
+    /// If not provided, no version is deprioritized.
+    fn is_deprioritized(&self, package: &Self::P, version: &Self::V) -> bool {
+        let _ = (package, version);
+        false
+    }
+
+    /// Which version of the package is preferred among the ones allowed.
+    /// If not provided, the newest version is preferred.
+    fn version_preference(&self, package: &Self::P) -> VersionPreference<Self::V> {
+        let _ = package;
+        VersionPreference::Newest
+    }
+
+    /// Retrieves the package dependencies.
+    /// Return [Dependencies::Unavailable] if its dependencies are unavailable.
+    #[allow(clippy::type_complexity)]
+    fn get_dependencies(
+        &self,
+        package: &Self::P,
+        version: &Self::V,
+    ) -> impl Future<Output = Result<Dependencies<Self::P, Self::VS, Self::M>, Self::Err>> + Send;
+
+    /// Called with packages and ranges whose versions and dependencies
+    /// are likely to be retrieved soon.
+    /// If not provided, nothing is done.
+    fn prefetch(&self, requests: &[(&Self::P, &Self::VS)]) {
+        let _ = requests;
+    }
+
+    /// Called regularly during the resolution,
+    /// if it returns an Err then resolution will be terminated.
+    fn should_cancel(&self) -> Result<(), Self::Err> {
+        Ok(())
+    }
+
+    /// Called after each unit propagation, before the next decision,
+    /// with information about the progress of the resolution.
+    fn on_progress(&self, _info: ProgressInfo) {}
+}
+
+/// Synchronous view of an [AsyncDependencyProvider].
+///
+/// It is the [DependencyProvider] behind [AsyncSelectedDependencies] and [AsyncPubGrubError],
+/// the types of the solution and errors returned by [resolve_async].
+/// It can also be given to [resolve](crate::resolve) from synchronous code,
+/// with the function blocking the current thread until a future completes,
+/// for example `tokio::runtime::Handle::block_on` or `futures::executor::block_on`.
+/// No such function is provided here, since running futures that need a runtime,
+/// like the ones of Tokio I/O, is up to the executor of the caller.
+pub struct BlockingDependencyProvider<ADP> {
+    provider: ADP,
+    #[allow(clippy::type_complexity)]
+    block_on: Box<dyn for<'f> Fn(Pin<&'f mut (dyn Future<Output = ()> + 'f)>)>,
+}
+
+impl<ADP> BlockingDependencyProvider<ADP> {
+    /// Wrap an asynchronous dependency provider,
+    /// with a function running a future to completion on the current thread.
+    pub fn new(
+        provider: ADP,
+        block_on: impl for<'f> Fn(Pin<&'f mut (dyn Future<Output = ()> + 'f)>) + 'static,
+    ) -> Self {
+        Self {
+            provider,
+            block_on: Box::new(block_on),
+        }
+    }
+
+    /// The wrapped asynchronous dependency provider.
+    pub fn inner(&self) -> &ADP {
+        &self.provider
+    }
+
+    /// Run a future to completion with the `block_on` function.
+    fn block_on<F: Future>(&self, future: F) -> F::Output {
+        let mut output = None;
+        {
+            let future = pin!(async { output = Some(future.await) });
+            (self.block_on)(future);
+        }
+        output.expect("block_on should run the future to completion")
+    }
+}
+
+impl<ADP: AsyncDependencyProvider> DependencyProvider for BlockingDependencyProvider<ADP> {
+    type P = ADP::P;
+    type V = ADP::V;
+    type VS = ADP::VS;
+    type M = ADP::M;
+
+    type Priority = ADP::Priority;
+
+    type Err = ADP::Err;
+
+    fn choose_version(
+        &self,
+        package: &Self::P,
+        range: &Self::VS,
+    ) -> Result<Option<Self::V>, Self::Err> {
+        self.block_on(self.provider.choose_version(package, range))
+    }
+
+    fn choose_version_with_tried(
+        &self,
+        package: &Self::P,
+        range: &Self::VS,
+        already_tried: &BTreeSet<Self::V>,
+    ) -> Result<Option<Self::V>, Self::Err> {
+        self.block_on(
+            self.provider
+                .choose_version_with_tried(package, range, already_tried),
+        )
+    }
+
+    fn get_dependencies(
+        &self,
+        package: &Self::P,
+        version: &Self::V,
+    ) -> Result<Dependencies<Self::P, Self::VS, Self::M>, Self::Err> {
+        self.block_on(self.provider.get_dependencies(package, version))
+    }
+
+    forward_to_dependency_provider!(
+        provider: prioritize, package_exists, is_deprioritized, version_preference, prefetch,
+        should_cancel, on_progress
+    );
+}
+
+impl<ADP: AsyncDependencyProvider> ResolutionHooks<BlockingDependencyProvider<ADP>> for ADP {
+    fn prioritize(&self, package: &ADP::P, range: &ADP::VS) -> ADP::Priority {
+        AsyncDependencyProvider::prioritize(self, package, range)
+    }
+
+    fn package_exists(&self, package: &ADP::P) -> bool {
+        AsyncDependencyProvider::package_exists(self, package)
+    }
+
+    fn prefetch(&self, requests: &[(&ADP::P, &ADP::VS)]) {
+        AsyncDependencyProvider::prefetch(self, requests)
+    }
+
+    fn should_cancel(&self) -> Result<(), ADP::Err> {
+        AsyncDependencyProvider::should_cancel(self)
+    }
+
+    fn on_progress(&self, info: ProgressInfo) {
+        AsyncDependencyProvider::on_progress(self, info)
+    }
+}
+
+/// Solution returned by [resolve_async], for the types of an [AsyncDependencyProvider].
+pub type AsyncSelectedDependencies<ADP> = SelectedDependencies<BlockingDependencyProvider<ADP>>;
+
+/// Error returned by [resolve_async], for the types of an [AsyncDependencyProvider].
+pub type AsyncPubGrubError<ADP> = PubGrubError<BlockingDependencyProvider<ADP>>;
+
+/// Finds a set of packages satisfying dependency bounds for a given package + version pair,
+/// awaiting the [AsyncDependencyProvider] instead of blocking on it.
+pub async fn resolve_async<ADP: AsyncDependencyProvider>(
+    dependency_provider: &ADP,
+    package: ADP::P,
+    version: impl Into<ADP::V>,
+) -> Result<AsyncSelectedDependencies<ADP>, AsyncPubGrubError<ADP>> {
+    let mut state: State<BlockingDependencyProvider<ADP>> =
+        State::init(package.clone(), version.into());
+    let mut added_dependencies = Map::default();
+    let mut resolution = Resolution::new(
+        &mut state,
+        &mut added_dependencies,
+        package,
+        u64::MAX,
+        ResolveLimits::default(),
+    );
+    loop {
+        let request = match resolution.choose(dependency_provider, &mut |_| {})? {
+            ControlFlow::Break(outcome) => return outcome_to_result(outcome),
+            ControlFlow::Continue(request) => request,
+        };
+        let decision = dependency_provider
+            .choose_version_with_tried(request.package, request.range, request.already_tried)
+            .await
+            .map_err(PubGrubError::ErrorChoosingPackageVersion)?;
+        let Some(version) = resolution.chosen(dependency_provider, decision)? else {
+            continue;
+        };
+        let package = resolution.package();
+        let dependencies = dependency_provider
+            .get_dependencies(package, &version)
+            .await
+            .map_err(|err| PubGrubError::ErrorRetrievingDependencies {
+                package: package.clone(),
+                version: version.clone(),
+                source: err,
+            })?;
+        resolution.add_dependencies(dependency_provider, version, dependencies)?;
+    }
+}
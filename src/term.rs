@@ -15,7 +15,13 @@ use crate::VersionSet;
 /// does not have that package selected. Specifically, `Positive(VS::empty())` means that there was
 /// a conflict, we need to select a version for the package but can't pick any, while
 /// `Negative(VS::full())` would mean it is fine as long as we don't select the package.
+///
+/// Terms form a boolean algebra with [intersection](Term::intersection),
+/// [union](Term::union) and [negate](Term::negate),
+/// where [Term::any] is the term that is always true
+/// and [Term::empty] is the term that is never true.
 #[derive(Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub enum Term<VS: VersionSet> {
     /// For example, "1.0.0 <= v < 2.0.0" is a positive expression
     /// that is evaluated true if a version is selected
@@ -30,22 +36,72 @@ pub enum Term<VS: VersionSet> {
 /// Base methods.
 impl<VS: VersionSet> Term<VS> {
     /// A term that is always true.
-    pub(crate) fn any() -> Self {
+    ///
+    /// This is the negative term of the empty set:
+    /// it is satisfied by any version, and also when no version is selected.
+    pub fn any() -> Self {
         Self::Negative(VS::empty())
     }
 
     /// A term that is never true.
-    pub(crate) fn empty() -> Self {
+    ///
+    /// This is the positive term of the empty set:
+    /// a version must be selected, but none is allowed.
+    pub fn empty() -> Self {
         Self::Positive(VS::empty())
     }
 
+    /// Check if this is the [empty](Term::empty) term, that is never true.
+    pub fn is_empty(&self) -> bool {
+        match self {
+            Self::Positive(set) => set == &VS::empty(),
+            Self::Negative(_) => false,
+        }
+    }
+
+    /// Check if this is the [any](Term::any) term, that is always true.
+    pub fn is_any(&self) -> bool {
+        match self {
+            Self::Positive(_) => false,
+            Self::Negative(set) => set == &VS::empty(),
+        }
+    }
+
+    /// A term requiring the package to be selected at a version in `set`,
+    /// the same as `Positive(set)`.
+    ///
+    /// ```
+    /// # use pubgrub::{Range, Term};
+    /// let term = Term::requiring(Range::<u32>::singleton(1u32));
+    /// assert!(term.contains(&1));
+    /// assert!(!term.contains(&2));
+    /// ```
+    pub fn requiring(set: VS) -> Self {
+        Self::Positive(set)
+    }
+
+    /// A term forbidding the versions of `set`:
+    /// the package must NOT be selected at a version in this set,
+    /// but it may not be selected at all.
+    /// This is the same as `Negative(set)`.
+    ///
+    /// ```
+    /// # use pubgrub::{Range, Term};
+    /// let term = Term::forbidding(Range::<u32>::singleton(1u32));
+    /// assert!(!term.contains(&1));
+    /// assert!(term.contains(&2));
+    /// ```
+    pub fn forbidding(set: VS) -> Self {
+        Self::Negative(set)
+    }
+
     /// A positive term containing exactly that version.
-    pub(crate) fn exact(version: VS::V) -> Self {
+    pub fn exact(version: VS::V) -> Self {
         Self::Positive(VS::singleton(version))
     }
 
     /// Simply check if a term is positive.
-    pub(crate) fn is_positive(&self) -> bool {
+    pub fn is_positive(&self) -> bool {
         match self {
             Self::Positive(_) => true,
             Self::Negative(_) => false,
@@ -55,7 +111,14 @@ impl<VS: VersionSet> Term<VS> {
     /// Negate a term.
     /// Evaluation of a negated term always returns
     /// the opposite of the evaluation of the original one.
-    pub(crate) fn negate(&self) -> Self {
+    ///
+    /// ```
+    /// # use pubgrub::{Range, Term};
+    /// let term = Term::Positive(Range::<u32>::between(1u32, 3u32));
+    /// assert_eq!(term.negate(), Term::Negative(Range::between(1u32, 3u32)));
+    /// assert_eq!(term.negate().negate(), term);
+    /// ```
+    pub fn negate(&self) -> Self {
         match self {
             Self::Positive(set) => Self::Negative(set.clone()),
             Self::Negative(set) => Self::Positive(set.clone()),
@@ -63,7 +126,7 @@ impl<VS: VersionSet> Term<VS> {
     }
 
     /// Evaluate a term regarding a given choice of version.
-    pub(crate) fn contains(&self, v: &VS::V) -> bool {
+    pub fn contains(&self, v: &VS::V) -> bool {
         match self {
             Self::Positive(set) => set.contains(v),
             Self::Negative(set) => !set.contains(v),
@@ -72,7 +135,7 @@ impl<VS: VersionSet> Term<VS> {
 
     /// Unwrap the set contained in a positive term.
     /// Will panic if used on a negative set.
-    pub(crate) fn unwrap_positive(&self) -> &VS {
+    pub fn unwrap_positive(&self) -> &VS {
         match self {
             Self::Positive(set) => set,
             _ => panic!("Negative term cannot unwrap positive set"),
@@ -94,7 +157,13 @@ impl<VS: VersionSet> Term<VS> {
     /// Compute the intersection of two terms.
     ///
     /// The intersection is positive if at least one of the two terms is positive.
-    pub(crate) fn intersection(&self, other: &Self) -> Self {
+    ///
+    /// ```
+    /// # use pubgrub::{Range, Term};
+    /// let term = Term::Positive(Range::<u32>::between(1u32, 3u32));
+    /// assert_eq!(term.intersection(&term.negate()), Term::empty());
+    /// ```
+    pub fn intersection(&self, other: &Self) -> Self {
         match (self, other) {
             (Self::Positive(r1), Self::Positive(r2)) => Self::Positive(r1.intersection(r2)),
             (Self::Positive(p), Self::Negative(n)) | (Self::Negative(n), Self::Positive(p)) => {
@@ -110,7 +179,8 @@ impl<VS: VersionSet> Term<VS> {
     pub(crate) fn is_disjoint(&self, other: &Self) -> bool {
         match (self, other) {
             (Self::Positive(r1), Self::Positive(r2)) => r1.is_disjoint(r2),
-            (Self::Negative(r1), Self::Negative(r2)) => r1 == &VS::empty() && r2 == &VS::empty(),
+            // The intersection of two negative terms is negative, so it is never the empty term.
+            (Self::Negative(_), Self::Negative(_)) => false,
             // If the positive term is a subset of the negative term, it lies fully in the region that the negative
             // term excludes.
             (Self::Positive(p), Self::Negative(n)) | (Self::Negative(n), Self::Positive(p)) => {
@@ -121,7 +191,14 @@ impl<VS: VersionSet> Term<VS> {
 
     /// Compute the union of two terms.
     /// If at least one term is negative, the union is also negative.
-    pub(crate) fn union(&self, other: &Self) -> Self {
+    ///
+    /// ```
+    /// # use pubgrub::{Range, Term};
+    /// let term = Term::Positive(Range::<u32>::between(1u32, 3u32));
+    /// assert_eq!(term.union(&term.negate()), Term::any());
+    /// assert_eq!(term.negate().union(&term), Term::any());
+    /// ```
+    pub fn union(&self, other: &Self) -> Self {
         match (self, other) {
             (Self::Positive(r1), Self::Positive(r2)) => Self::Positive(r1.union(r2)),
             (Self::Positive(p), Self::Negative(n)) | (Self::Negative(n), Self::Positive(p)) => {
@@ -148,7 +225,8 @@ impl<VS: VersionSet> Term<VS> {
 ///
 /// As a shorthand, we say that a term v
 /// satisfies or contradicts a term t if {v} satisfies or contradicts it.
-pub(crate) enum Relation {
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum TermRelation {
     /// We say that a set of terms S "satisfies" a term t
     /// if t must be true whenever every term in S is true.
     Satisfied,
@@ -188,13 +266,25 @@ impl<VS: VersionSet> Term<VS> {
 
     /// Check if a set of terms satisfies or contradicts a given term.
     /// Otherwise the relation is inconclusive.
-    pub(crate) fn relation_with(&self, other_terms_intersection: &Self) -> Relation {
+    ///
+    /// The set of terms is given by its intersection, so to compare two terms directly,
+    /// `t1.relation_with(&t2)` tells whether `t2` satisfies or contradicts `t1`.
+    ///
+    /// ```
+    /// # use pubgrub::{Range, Term, TermRelation};
+    /// let wide = Term::Positive(Range::<u32>::between(1u32, 10u32));
+    /// let narrow = Term::Positive(Range::<u32>::between(2u32, 3u32));
+    /// assert_eq!(wide.relation_with(&narrow), TermRelation::Satisfied);
+    /// assert_eq!(wide.negate().relation_with(&narrow), TermRelation::Contradicted);
+    /// assert_eq!(narrow.relation_with(&wide), TermRelation::Inconclusive);
+    /// ```
+    pub fn relation_with(&self, other_terms_intersection: &Self) -> TermRelation {
         if other_terms_intersection.subset_of(self) {
-            Relation::Satisfied
+            TermRelation::Satisfied
         } else if self.is_disjoint(other_terms_intersection) {
-            Relation::Contradicted
+            TermRelation::Contradicted
         } else {
-            Relation::Inconclusive
+            TermRelation::Inconclusive
         }
     }
 }
@@ -211,7 +301,8 @@ impl<VS: VersionSet + Display> Display for Term<VS> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             Self::Positive(set) => write!(f, "{}", set),
-            Self::Negative(set) => write!(f, "Not ( {} )", set),
+            Self::Negative(set) if set == &VS::empty() => write!(f, "*"),
+            Self::Negative(set) => write!(f, "not ( {} )", set),
         }
     }
 }
@@ -238,9 +329,9 @@ pub mod tests {
         #[test]
         fn relation_with(term1 in strategy(), term2 in strategy()) {
             match term1.relation_with(&term2) {
-                Relation::Satisfied => assert!(term1.satisfied_by(&term2)),
-                Relation::Contradicted => assert!(term1.contradicted_by(&term2)),
-                Relation::Inconclusive => {
+                TermRelation::Satisfied => assert!(term1.satisfied_by(&term2)),
+                TermRelation::Contradicted => assert!(term1.contradicted_by(&term2)),
+                TermRelation::Inconclusive => {
                     assert!(!term1.satisfied_by(&term2));
                     assert!(!term1.contradicted_by(&term2));
                 }
@@ -256,6 +347,34 @@ pub mod tests {
             assert_eq!(term1.union(&term2).is_positive(), union_positive);
         }
 
+        #[test]
+        fn relation_with_any_and_empty(term in strategy()) {
+            // The empty term satisfies every term, and every term satisfies any.
+            assert_eq!(term.relation_with(&Term::empty()), TermRelation::Satisfied);
+            assert_eq!(Term::any().relation_with(&term), TermRelation::Satisfied);
+            // The empty term is contradicted by everything, except by itself since it is a subset.
+            let expected = if term.is_empty() {
+                TermRelation::Satisfied
+            } else {
+                TermRelation::Contradicted
+            };
+            assert_eq!(Term::empty().relation_with(&term), expected);
+        }
+
+        #[test]
+        fn is_empty_and_is_any(term in strategy()) {
+            assert_eq!(term.is_empty(), term == Term::empty());
+            assert_eq!(term.is_any(), term == Term::any());
+            assert_eq!(term.negate().is_empty(), term.is_any());
+        }
+
+        #[test]
+        fn relation_with_negation(term in strategy()) {
+            if term != Term::empty() && term != Term::any() {
+                assert_eq!(term.negate().relation_with(&term), TermRelation::Contradicted);
+            }
+        }
+
         #[test]
         fn is_disjoint_through_intersection(r1 in strategy(), r2 in strategy()) {
             let disjoint_def = r1.intersection(&r2) == Term::empty();
@@ -277,4 +396,69 @@ pub mod tests {
             assert_eq!(r1.union(&r2), union_def);
         }
     }
+
+    #[test]
+    fn relation_with_positive_and_negative() {
+        let low = Range::<u32>::strictly_lower_than(5u32);
+        let high = Range::<u32>::higher_than(5u32);
+        let middle = Range::<u32>::between(3u32, 7u32);
+
+        // Positive vs positive.
+        assert_eq!(
+            Term::Positive(low.clone()).relation_with(&Term::Positive(Range::singleton(1u32))),
+            TermRelation::Satisfied
+        );
+        assert_eq!(
+            Term::Positive(low.clone()).relation_with(&Term::Positive(high.clone())),
+            TermRelation::Contradicted
+        );
+        assert_eq!(
+            Term::Positive(low.clone()).relation_with(&Term::Positive(middle.clone())),
+            TermRelation::Inconclusive
+        );
+
+        // A negative term never satisfies a positive one, since it allows no version at all.
+        assert_eq!(
+            Term::Positive(low.clone()).relation_with(&Term::Negative(high.clone())),
+            TermRelation::Inconclusive
+        );
+        assert_eq!(
+            Term::Positive(low.clone()).relation_with(&Term::Negative(low.clone())),
+            TermRelation::Contradicted
+        );
+
+        // Positive vs negative.
+        assert_eq!(
+            Term::Negative(low.clone()).relation_with(&Term::Positive(high.clone())),
+            TermRelation::Satisfied
+        );
+        assert_eq!(
+            Term::Negative(low.clone()).relation_with(&Term::Positive(Range::singleton(1u32))),
+            TermRelation::Contradicted
+        );
+        assert_eq!(
+            Term::Negative(low.clone()).relation_with(&Term::Positive(middle)),
+            TermRelation::Inconclusive
+        );
+
+        // Negative vs negative.
+        assert_eq!(
+            Term::Negative(Range::singleton(1u32)).relation_with(&Term::Negative(low.clone())),
+            TermRelation::Satisfied
+        );
+        assert_eq!(
+            Term::Negative(low).relation_with(&Term::Negative(high)),
+            TermRelation::Inconclusive
+        );
+    }
+
+    #[test]
+    fn display_empty_and_any() {
+        assert_eq!(Term::<Range<u32>>::empty().to_string(), "∅");
+        assert_eq!(Term::<Range<u32>>::any().to_string(), "*");
+        assert_eq!(
+            Term::Negative(Range::<u32>::singleton(3u32)).to_string(),
+            "not ( 3 )"
+        );
+    }
 }
@@ -2,6 +2,8 @@
 
 //! Publicly exported type aliases.
 
+use std::hash::Hash;
+
 use crate::DependencyProvider;
 
 /// Map implementation used by the library.
@@ -15,6 +17,37 @@ pub type Set<V> = rustc_hash::FxHashSet<V>;
 pub type SelectedDependencies<DP> =
     Map<<DP as DependencyProvider>::P, <DP as DependencyProvider>::V>;
 
+/// Conveniences on the [SelectedDependencies] returned by [resolve](crate::solver::resolve).
+///
+/// The solution is a hash map, so iterating over it directly gives an arbitrary order.
+/// Iterating in package order gives a stable output, for example to write a lockfile.
+pub trait SelectedDependenciesExt<P, V> {
+    /// The selected packages and their versions, sorted by package.
+    fn iter_sorted<'a>(&'a self) -> impl Iterator<Item = (&'a P, &'a V)>
+    where
+        P: Ord + 'a,
+        V: 'a;
+
+    /// The version selected for a package, if it is part of the solution.
+    fn version(&self, package: &P) -> Option<&V>;
+}
+
+impl<P: Eq + Hash, V> SelectedDependenciesExt<P, V> for Map<P, V> {
+    fn iter_sorted<'a>(&'a self) -> impl Iterator<Item = (&'a P, &'a V)>
+    where
+        P: Ord + 'a,
+        V: 'a,
+    {
+        let mut selected: Vec<(&P, &V)> = self.iter().collect();
+        selected.sort_unstable_by_key(|(p, _)| *p);
+        selected.into_iter()
+    }
+
+    fn version(&self, package: &P) -> Option<&V> {
+        self.get(package)
+    }
+}
+
 /// Holds information about all possible versions a given package can accept.
 /// There is a difference in semantics between an empty map
 /// inside [DependencyConstraints] and [Dependencies::Unavailable](crate::solver::Dependencies::Unavailable):
@@ -2,17 +2,64 @@
 
 //! Traits and implementations to create and compare versions.
 
+use std::cmp::Ordering;
 use std::fmt::{self, Debug, Display};
+use std::hash::{Hash, Hasher};
 use std::str::FromStr;
+use std::sync::Arc;
 
 use thiserror::Error;
 
-/// Type for semantic versions: major.minor.patch.
-#[derive(Debug, Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash)]
+use crate::Range;
+
+/// Type for semantic versions: major.minor.patch, with optional prerelease identifiers.
+///
+/// Versions are ordered as specified by [semver](https://semver.org/#spec-item-11):
+/// a prerelease such as `1.0.0-alpha.1` is lower than the release `1.0.0`.
+/// Build metadata (`1.0.0+build.5`) is accepted when parsing but not stored,
+/// so it does not take part in comparisons.
+///
+/// With the `semver` feature, versions convert to and from `semver::Version`.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
 pub struct SemanticVersion {
     major: u32,
     minor: u32,
     patch: u32,
+    /// Dot separated prerelease identifiers, [None] for a release.
+    /// They are shared, so that cloning a prerelease does not allocate.
+    prerelease: Option<Arc<[Identifier]>>,
+}
+
+/// A prerelease identifier.
+///
+/// The variant order matters for the derived [Ord]:
+/// numeric identifiers have lower precedence than alphanumeric ones.
+#[derive(Debug, Clone, Ord, PartialOrd, Eq, PartialEq, Hash)]
+enum Identifier {
+    Numeric(u64),
+    AlphaNumeric(String),
+}
+
+impl Ord for SemanticVersion {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (self.major, self.minor, self.patch)
+            .cmp(&(other.major, other.minor, other.patch))
+            .then_with(|| {
+                // A release has higher precedence than any of its prereleases.
+                match (&self.prerelease, &other.prerelease) {
+                    (None, None) => Ordering::Equal,
+                    (None, Some(_)) => Ordering::Greater,
+                    (Some(_), None) => Ordering::Less,
+                    (Some(pre1), Some(pre2)) => pre1.cmp(pre2),
+                }
+            })
+    }
+}
+
+impl PartialOrd for SemanticVersion {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
 }
 
 #[cfg(feature = "serde")]
@@ -45,6 +92,7 @@ impl SemanticVersion {
             major,
             minor,
             patch,
+            prerelease: None,
         }
     }
 
@@ -62,6 +110,11 @@ impl SemanticVersion {
     pub fn two() -> Self {
         Self::new(2, 0, 0)
     }
+
+    /// Whether this version has prerelease identifiers, like `1.0.0-alpha.1`.
+    pub fn is_prerelease(&self) -> bool {
+        self.prerelease.is_some()
+    }
 }
 
 // Convert a tuple (major, minor, patch) into a version.
@@ -83,33 +136,225 @@ impl From<&(u32, u32, u32)> for SemanticVersion {
 // Convert an &version into a version.
 impl From<&SemanticVersion> for SemanticVersion {
     fn from(v: &SemanticVersion) -> Self {
-        *v
+        v.clone()
     }
 }
 
 // Convert a version into a tuple (major, minor, patch).
+// The prerelease identifiers are dropped.
 impl From<SemanticVersion> for (u32, u32, u32) {
     fn from(v: SemanticVersion) -> Self {
         (v.major, v.minor, v.patch)
     }
 }
 
+// Convert a version of the semver crate, dropping its build metadata,
+// unless its numbers do not fit in a u32.
+#[cfg(feature = "semver")]
+impl TryFrom<semver::Version> for SemanticVersion {
+    type Error = VersionParseError;
+
+    fn try_from(v: semver::Version) -> Result<Self, Self::Error> {
+        v.to_string().parse()
+    }
+}
+
+// Convert a version into a version of the semver crate, without build metadata.
+#[cfg(feature = "semver")]
+impl From<SemanticVersion> for semver::Version {
+    fn from(v: SemanticVersion) -> Self {
+        let pre = if v.is_prerelease() {
+            let prerelease = v.to_string();
+            let (_, prerelease) = prerelease.split_once('-').unwrap();
+            // Parsed prerelease identifiers are always valid for semver.
+            semver::Prerelease::new(prerelease).unwrap()
+        } else {
+            semver::Prerelease::EMPTY
+        };
+        Self {
+            major: v.major.into(),
+            minor: v.minor.into(),
+            patch: v.patch.into(),
+            pre,
+            build: semver::BuildMetadata::EMPTY,
+        }
+    }
+}
+
+#[cfg(feature = "semver")]
+#[test]
+fn semver_round_trip() {
+    for version in [
+        "0.0.0",
+        "1.2.3",
+        "1.0.0-alpha",
+        "1.0.0-alpha.1.x-y",
+        "1.0.0-0",
+    ] {
+        let semantic: SemanticVersion = version.parse().unwrap();
+        let expected = semver::Version::parse(version).unwrap();
+        assert_eq!(
+            semver::Version::from(semantic.clone()),
+            expected,
+            "{version}"
+        );
+        assert_eq!(
+            SemanticVersion::try_from(expected),
+            Ok(semantic),
+            "{version}"
+        );
+    }
+
+    // Build metadata is dropped.
+    let build = semver::Version::parse("1.0.0-beta+build.5").unwrap();
+    let semantic = SemanticVersion::try_from(build).unwrap();
+    assert_eq!(semantic, "1.0.0-beta".parse::<SemanticVersion>().unwrap());
+    assert_eq!(
+        semver::Version::from(semantic).build,
+        semver::BuildMetadata::EMPTY
+    );
+
+    // Numbers too large for a u32 are rejected.
+    assert!(matches!(
+        SemanticVersion::try_from(semver::Version::new(1, 2, u64::from(u32::MAX) + 1)),
+        Err(VersionParseError::ParseIntError { .. })
+    ));
+}
+
+#[test]
+fn semantic_version_into_tuple() {
+    assert_eq!(
+        <(u32, u32, u32)>::from(SemanticVersion::new(1, 2, 3)),
+        (1, 2, 3)
+    );
+    let alpha: SemanticVersion = "1.2.3-alpha.1".parse().unwrap();
+    assert_eq!(<(u32, u32, u32)>::from(alpha), (1, 2, 3));
+}
+
 // Bump versions.
+// The prerelease identifiers are dropped.
 impl SemanticVersion {
     /// Bump the patch number of a version.
-    pub fn bump_patch(self) -> Self {
+    /// `1.2.3` becomes `1.2.4`.
+    pub fn bump_patch(&self) -> Self {
         Self::new(self.major, self.minor, self.patch + 1)
     }
 
-    /// Bump the minor number of a version.
-    pub fn bump_minor(self) -> Self {
+    /// Bump the minor number of a version, resetting the patch number.
+    /// `1.2.3` becomes `1.3.0`.
+    pub fn bump_minor(&self) -> Self {
         Self::new(self.major, self.minor + 1, 0)
     }
 
-    /// Bump the major number of a version.
-    pub fn bump_major(self) -> Self {
+    /// Bump the major number of a version, resetting the minor and patch numbers.
+    /// `1.2.3` becomes `2.0.0`.
+    pub fn bump_major(&self) -> Self {
         Self::new(self.major + 1, 0, 0)
     }
+
+    /// The lowest prerelease of a version, lower than all its other prereleases.
+    /// `2.0.0` becomes `2.0.0-0`.
+    fn lowest_prerelease(&self) -> Self {
+        Self {
+            prerelease: Some(Arc::new([Identifier::Numeric(0)])),
+            ..self.clone()
+        }
+    }
+}
+
+#[test]
+fn bump_semantic_version() {
+    let version = SemanticVersion::new(1, 2, 3);
+    assert_eq!(version.bump_patch(), SemanticVersion::new(1, 2, 4));
+    assert_eq!(version.bump_minor(), SemanticVersion::new(1, 3, 0));
+    assert_eq!(version.bump_major(), SemanticVersion::new(2, 0, 0));
+    // Bumping does not consume the version.
+    assert_eq!(version, SemanticVersion::new(1, 2, 3));
+
+    // Zero components are bumped like any other.
+    let zero = SemanticVersion::zero();
+    assert_eq!(zero.bump_patch(), SemanticVersion::new(0, 0, 1));
+    assert_eq!(zero.bump_minor(), SemanticVersion::new(0, 1, 0));
+    assert_eq!(zero.bump_major(), SemanticVersion::one());
+    assert_eq!(
+        SemanticVersion::new(0, 9, 9).bump_minor(),
+        SemanticVersion::new(0, 10, 0)
+    );
+
+    // Prerelease identifiers are dropped, and the bumped version is higher.
+    let alpha: SemanticVersion = "1.2.3-alpha.1".parse().unwrap();
+    assert_eq!(alpha.bump_patch(), SemanticVersion::new(1, 2, 4));
+    assert_eq!(alpha.bump_minor(), SemanticVersion::new(1, 3, 0));
+    assert_eq!(alpha.bump_major(), SemanticVersion::two());
+    assert!(alpha < alpha.bump_patch());
+}
+
+// Cargo style requirements.
+impl Range<SemanticVersion> {
+    /// Versions compatible with `v` according to the
+    /// [caret requirement](https://doc.rust-lang.org/cargo/reference/specifying-dependencies.html#caret-requirements)
+    /// `^v` used by Cargo and npm:
+    /// the leftmost non-zero component of `v` may not change.
+    ///
+    /// `^1.2.3` is `>=1.2.3, <2.0.0-0`, `^0.2.3` is `>=0.2.3, <0.3.0-0`
+    /// and `^0.0.3` is `>=0.0.3, <0.0.4-0`.
+    /// The upper bound also excludes the prereleases of the bumped version.
+    pub fn caret(v: impl Into<SemanticVersion>) -> Self {
+        let v = v.into();
+        let upper = if v.major > 0 {
+            v.bump_major()
+        } else if v.minor > 0 {
+            v.bump_minor()
+        } else {
+            v.bump_patch()
+        };
+        Self::between(v, upper.lowest_prerelease())
+    }
+
+    /// Versions compatible with `v` according to the
+    /// [tilde requirement](https://doc.rust-lang.org/cargo/reference/specifying-dependencies.html#tilde-requirements)
+    /// `~v`: only the patch number may change.
+    ///
+    /// `~1.2.3` is `>=1.2.3, <1.3.0-0`.
+    pub fn tilde(v: impl Into<SemanticVersion>) -> Self {
+        let v = v.into();
+        let upper = v.bump_minor().lowest_prerelease();
+        Self::between(v, upper)
+    }
+}
+
+#[test]
+fn caret_and_tilde_requirements() {
+    let v = |str: &str| str.parse::<SemanticVersion>().unwrap();
+    // Examples from the Cargo reference.
+    for (requirement, range, lower, upper) in [
+        ("^1.2.3", Range::caret((1, 2, 3)), "1.2.3", "2.0.0-0"),
+        ("^1.2.0", Range::caret((1, 2, 0)), "1.2.0", "2.0.0-0"),
+        ("^1.0.0", Range::caret((1, 0, 0)), "1.0.0", "2.0.0-0"),
+        ("^0.2.3", Range::caret((0, 2, 3)), "0.2.3", "0.3.0-0"),
+        ("^0.2.0", Range::caret((0, 2, 0)), "0.2.0", "0.3.0-0"),
+        ("^0.0.3", Range::caret((0, 0, 3)), "0.0.3", "0.0.4-0"),
+        ("^0.0.0", Range::caret((0, 0, 0)), "0.0.0", "0.0.1-0"),
+        ("~1.2.3", Range::tilde((1, 2, 3)), "1.2.3", "1.3.0-0"),
+        ("~1.2.0", Range::tilde((1, 2, 0)), "1.2.0", "1.3.0-0"),
+        ("~0.2.3", Range::tilde((0, 2, 3)), "0.2.3", "0.3.0-0"),
+        ("~0.0.3", Range::tilde((0, 0, 3)), "0.0.3", "0.1.0-0"),
+    ] {
+        assert_eq!(range, Range::between(v(lower), v(upper)), "{requirement}");
+        assert!(range.contains(&v(lower)), "{requirement}");
+        assert!(!range.contains(&v(upper)), "{requirement}");
+    }
+    assert!(Range::caret((1, 2, 3)).contains(&v("1.9.9")));
+    assert!(!Range::caret((1, 2, 3)).contains(&v("1.2.2")));
+    assert!(!Range::caret((0, 2, 3)).contains(&v("0.3.1")));
+    assert!(Range::tilde((1, 2, 3)).contains(&v("1.2.9")));
+
+    // Prereleases of the bumped version are excluded.
+    assert!(!Range::caret((1, 2, 3)).contains(&v("2.0.0-alpha")));
+    assert!(!Range::caret((1, 2, 3)).contains(&v("2.0.0")));
+    assert!(!Range::caret((0, 2, 3)).contains(&v("0.3.0-alpha")));
+    assert!(!Range::tilde((1, 2, 3)).contains(&v("1.3.0-alpha")));
+    assert!(!Range::tilde((1, 2, 3)).contains(&v("1.3.0")));
 }
 
 /// Error creating [SemanticVersion] from [String].
@@ -131,6 +376,15 @@ pub enum VersionParseError {
         /// A specific error resulted from parsing a part of the version as [u32].
         parse_error: String,
     },
+    /// A prerelease identifier is empty, contains characters other than
+    /// ASCII alphanumerics and hyphens, or is a number with leading zeros.
+    #[error("invalid prerelease identifier '{identifier}' in '{full_version}'")]
+    InvalidPrerelease {
+        /// [SemanticVersion] that was being parsed.
+        full_version: String,
+        /// The prerelease identifier that is invalid.
+        identifier: String,
+    },
 }
 
 impl FromStr for SemanticVersion {
@@ -144,17 +398,63 @@ impl FromStr for SemanticVersion {
                 parse_error: e.to_string(),
             })
         };
+        let parse_identifier = |identifier: &str| {
+            let invalid = || Self::Err::InvalidPrerelease {
+                full_version: s.to_string(),
+                identifier: identifier.to_string(),
+            };
+            if identifier.is_empty()
+                || !identifier
+                    .bytes()
+                    .all(|b| b.is_ascii_alphanumeric() || b == b'-')
+            {
+                Err(invalid())
+            } else if identifier.bytes().all(|b| b.is_ascii_digit()) {
+                if identifier.len() > 1 && identifier.starts_with('0') {
+                    return Err(invalid());
+                }
+                identifier
+                    .parse::<u64>()
+                    .map(Identifier::Numeric)
+                    .map_err(|_| invalid())
+            } else {
+                Ok(Identifier::AlphaNumeric(identifier.to_string()))
+            }
+        };
 
-        let mut parts = s.split('.');
+        // Build metadata is ignored.
+        let version = s.split_once('+').map_or(s, |(version, _build)| version);
+        // The prerelease starts at the first hyphen following a digit of the patch number,
+        // so that a negative number like in `1.2.-3` is still reported as such.
+        let prerelease_start = version
+            .match_indices('-')
+            .map(|(i, _)| i)
+            .find(|&i| i > 0 && version.as_bytes()[i - 1].is_ascii_digit());
+        let (version, prerelease) = match prerelease_start {
+            Some(i) => (&version[..i], Some(&version[i + 1..])),
+            None => (version, None),
+        };
+
+        let mut parts = version.split('.');
         match (parts.next(), parts.next(), parts.next(), parts.next()) {
             (Some(major), Some(minor), Some(patch), None) => {
                 let major = parse_u32(major)?;
                 let minor = parse_u32(minor)?;
                 let patch = parse_u32(patch)?;
+                let prerelease = match prerelease {
+                    Some(prerelease) => Some(
+                        prerelease
+                            .split('.')
+                            .map(parse_identifier)
+                            .collect::<Result<_, _>>()?,
+                    ),
+                    None => None,
+                };
                 Ok(Self {
                     major,
                     minor,
                     patch,
+                    prerelease,
                 })
             }
             _ => Err(Self::Err::NotThreeParts {
@@ -171,7 +471,8 @@ fn from_str_for_semantic_version() {
         &SemanticVersion {
             major: 0,
             minor: 1,
-            patch: 0
+            patch: 0,
+            prerelease: None,
         }
         .to_string()
     )
@@ -217,6 +518,545 @@ fn from_str_for_semantic_version() {
 
 impl Display for SemanticVersion {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)
+        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)?;
+        for (i, identifier) in self
+            .prerelease
+            .iter()
+            .flat_map(|pre| pre.iter())
+            .enumerate()
+        {
+            f.write_str(if i == 0 { "-" } else { "." })?;
+            match identifier {
+                Identifier::Numeric(n) => write!(f, "{}", n)?,
+                Identifier::AlphaNumeric(s) => f.write_str(s)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn serde_semantic_version_as_string() {
+    let version: SemanticVersion = serde_json::from_str(r#""1.2.3""#).unwrap();
+    assert_eq!(version, SemanticVersion::new(1, 2, 3));
+    assert_eq!(serde_json::to_string(&version).unwrap(), r#""1.2.3""#);
+    assert!(serde_json::from_str::<SemanticVersion>(r#""1.2.x""#).is_err());
+
+    // Ranges of versions contain their bounds as strings too.
+    let range = crate::Range::between((1, 2, 3), (2, 0, 0));
+    let json = serde_json::to_string(&range).unwrap();
+    assert!(json.contains(r#""1.2.3""#), "{json}");
+    assert_eq!(
+        serde_json::from_str::<crate::Range<SemanticVersion>>(&json).unwrap(),
+        range
+    );
+}
+
+#[test]
+fn prerelease_ordering() {
+    let parse = |str: &str| str.parse::<SemanticVersion>().unwrap();
+    assert!(parse("1.0.0-alpha") < parse("1.0.0"));
+    assert!(parse("1.0.0-alpha.1") < parse("1.0.0-alpha.2"));
+    assert!(parse("1.0.0-alpha") < parse("1.0.0-alpha.1"));
+    assert!(parse("0.9.9") < parse("1.0.0-alpha"));
+    // Numeric identifiers compare numerically and before alphanumeric ones.
+    assert!(parse("1.0.0-alpha.2") < parse("1.0.0-alpha.10"));
+    assert!(parse("1.0.0-alpha.1") < parse("1.0.0-alpha.beta"));
+    // The example from the semver specification.
+    let versions = [
+        "1.0.0-alpha",
+        "1.0.0-alpha.1",
+        "1.0.0-alpha.beta",
+        "1.0.0-beta",
+        "1.0.0-beta.2",
+        "1.0.0-beta.11",
+        "1.0.0-rc.1",
+        "1.0.0",
+    ];
+    for pair in versions.windows(2) {
+        assert!(parse(pair[0]) < parse(pair[1]), "{} < {}", pair[0], pair[1]);
+    }
+    // Build metadata is ignored.
+    assert_eq!(parse("1.0.0-alpha+build.5"), parse("1.0.0-alpha"));
+    assert_eq!(parse("1.0.0+20130313144700"), SemanticVersion::one());
+    assert!(!parse("1.0.0+build").is_prerelease());
+    assert!(parse("1.0.0-0").is_prerelease());
+}
+
+#[test]
+fn prerelease_display_and_errors() {
+    let parse = |str: &str| str.parse::<SemanticVersion>();
+    assert_eq!(
+        parse("1.2.3-alpha.1.x-y+build").unwrap().to_string(),
+        "1.2.3-alpha.1.x-y"
+    );
+    for (version, identifier) in [
+        ("1.0.0-", ""),
+        ("1.0.0-alpha..1", ""),
+        ("1.0.0-01", "01"),
+        ("1.0.0-alpha_1", "alpha_1"),
+        ("1.0.0-99999999999999999999", "99999999999999999999"),
+    ] {
+        assert_eq!(
+            parse(version),
+            Err(VersionParseError::InvalidPrerelease {
+                full_version: version.to_owned(),
+                identifier: identifier.to_owned(),
+            })
+        );
+    }
+}
+
+/// Type for calendar versions: `YYYY.MM.MICRO` or `YYYY.0M.0D`.
+///
+/// With the `YYYY.MM.MICRO` layout, like `2024.1.3`, the last number counts
+/// the releases of the month. With the zero padded `YYYY.0M.0D` layout,
+/// like `2024.01.03`, it is the day of the month.
+/// Versions are compared by year, month, and then micro number or day,
+/// regardless of the layout.
+#[derive(Debug, Copy, Clone)]
+pub struct CalendarVersion {
+    year: u32,
+    month: u32,
+    micro: u32,
+    /// Whether the month and day are zero padded when displayed.
+    zero_padded: bool,
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for CalendarVersion {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.serialize_str(&format!("{}", self))
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for CalendarVersion {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let s = String::deserialize(deserializer)?;
+        FromStr::from_str(&s).map_err(serde::de::Error::custom)
+    }
+}
+
+// Constructors
+impl CalendarVersion {
+    /// Create a version with the `YYYY.MM.MICRO` layout.
+    pub fn new(year: u32, month: u32, micro: u32) -> Self {
+        Self {
+            year,
+            month,
+            micro,
+            zero_padded: false,
+        }
+    }
+
+    /// Create a version with the `YYYY.0M.0D` layout.
+    pub fn from_date(year: u32, month: u32, day: u32) -> Self {
+        Self {
+            year,
+            month,
+            micro: day,
+            zero_padded: true,
+        }
+    }
+}
+
+// Convert a tuple (year, month, micro) into a version.
+impl From<(u32, u32, u32)> for CalendarVersion {
+    fn from(tuple: (u32, u32, u32)) -> Self {
+        let (year, month, micro) = tuple;
+        Self::new(year, month, micro)
+    }
+}
+
+// Convert a version into a tuple (year, month, micro).
+impl From<CalendarVersion> for (u32, u32, u32) {
+    fn from(v: CalendarVersion) -> Self {
+        (v.year, v.month, v.micro)
+    }
+}
+
+// The layout is only used for display.
+impl PartialEq for CalendarVersion {
+    fn eq(&self, other: &Self) -> bool {
+        (self.year, self.month, self.micro) == (other.year, other.month, other.micro)
+    }
+}
+
+impl Eq for CalendarVersion {}
+
+impl Hash for CalendarVersion {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        (self.year, self.month, self.micro).hash(state)
+    }
+}
+
+impl Ord for CalendarVersion {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (self.year, self.month, self.micro).cmp(&(other.year, other.month, other.micro))
+    }
+}
+
+impl PartialOrd for CalendarVersion {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Error creating [CalendarVersion] from [String].
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum CalVerParseError {
+    /// [CalendarVersion] must contain year, month and micro or day.
+    #[error("version {full_version} must contain 3 numbers separated by dot")]
+    NotThreeParts {
+        /// [CalendarVersion] that was being parsed.
+        full_version: String,
+    },
+    /// Wrapper around [ParseIntError](core::num::ParseIntError).
+    #[error("cannot parse '{version_part}' in '{full_version}' as u32: {parse_error}")]
+    ParseIntError {
+        /// [CalendarVersion] that was being parsed.
+        full_version: String,
+        /// A version part where parsing failed.
+        version_part: String,
+        /// A specific error resulted from parsing a part of the version as [u32].
+        parse_error: String,
+    },
+    /// The year must have four digits.
+    #[error("year '{year}' in '{full_version}' must have four digits")]
+    InvalidYear {
+        /// [CalendarVersion] that was being parsed.
+        full_version: String,
+        /// The invalid year.
+        year: String,
+    },
+    /// The month must be between 1 and 12,
+    /// and zero padded if and only if the day is.
+    #[error("invalid month '{month}' in '{full_version}'")]
+    InvalidMonth {
+        /// [CalendarVersion] that was being parsed.
+        full_version: String,
+        /// The invalid month.
+        month: String,
+    },
+    /// The day must be between 01 and 31, zero padded,
+    /// and a micro number must not have leading zeros.
+    #[error("invalid day or micro number '{micro}' in '{full_version}'")]
+    InvalidMicro {
+        /// [CalendarVersion] that was being parsed.
+        full_version: String,
+        /// The invalid day or micro number.
+        micro: String,
+    },
+}
+
+impl FromStr for CalendarVersion {
+    type Err = CalVerParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parse_u32 = |part: &str| {
+            part.parse::<u32>().map_err(|e| Self::Err::ParseIntError {
+                full_version: s.to_string(),
+                version_part: part.to_string(),
+                parse_error: e.to_string(),
+            })
+        };
+        let is_padded = |part: &str| part.len() == 2 && part.starts_with('0');
+
+        let mut parts = s.split('.');
+        let (Some(year_part), Some(month_part), Some(micro_part), None) =
+            (parts.next(), parts.next(), parts.next(), parts.next())
+        else {
+            return Err(Self::Err::NotThreeParts {
+                full_version: s.to_string(),
+            });
+        };
+        let year = parse_u32(year_part)?;
+        let month = parse_u32(month_part)?;
+        let micro = parse_u32(micro_part)?;
+
+        if year_part.len() != 4 || year_part.starts_with('0') {
+            return Err(Self::Err::InvalidYear {
+                full_version: s.to_string(),
+                year: year_part.to_string(),
+            });
+        }
+        let zero_padded = is_padded(month_part) || is_padded(micro_part);
+        let month_len_ok = if zero_padded {
+            month_part.len() == 2
+        } else {
+            !month_part.starts_with('0')
+        };
+        if !month_len_ok || !(1..=12).contains(&month) {
+            return Err(Self::Err::InvalidMonth {
+                full_version: s.to_string(),
+                month: month_part.to_string(),
+            });
+        }
+        let micro_ok = if zero_padded {
+            micro_part.len() == 2 && (1..=31).contains(&micro)
+        } else {
+            micro_part == "0" || !micro_part.starts_with('0')
+        };
+        if !micro_ok {
+            return Err(Self::Err::InvalidMicro {
+                full_version: s.to_string(),
+                micro: micro_part.to_string(),
+            });
+        }
+        Ok(Self {
+            year,
+            month,
+            micro,
+            zero_padded,
+        })
+    }
+}
+
+impl Display for CalendarVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.zero_padded {
+            write!(f, "{}.{:02}.{:02}", self.year, self.month, self.micro)
+        } else {
+            write!(f, "{}.{}.{}", self.year, self.month, self.micro)
+        }
+    }
+}
+
+#[test]
+fn from_str_for_calendar_version() {
+    let parse = |str: &str| str.parse::<CalendarVersion>();
+    assert_eq!(parse("2024.1.3"), Ok(CalendarVersion::new(2024, 1, 3)));
+    assert_eq!(parse("2024.12.0"), Ok(CalendarVersion::new(2024, 12, 0)));
+    assert_eq!(
+        parse("2024.01.03"),
+        Ok(CalendarVersion::from_date(2024, 1, 3))
+    );
+    assert_eq!(
+        parse("2024.11.05"),
+        Ok(CalendarVersion::from_date(2024, 11, 5))
+    );
+    for version in [
+        "2024.1.3",
+        "2024.12.0",
+        "2024.01.03",
+        "2024.11.05",
+        "2024.10.31",
+    ] {
+        assert_eq!(parse(version).unwrap().to_string(), version);
     }
+    // Both layouts of the same date are equal.
+    assert_eq!(parse("2024.1.3"), parse("2024.01.03"));
+
+    assert_eq!(
+        parse("2024.1"),
+        Err(CalVerParseError::NotThreeParts {
+            full_version: "2024.1".to_owned(),
+        })
+    );
+    assert_eq!(
+        parse("2024.x.1"),
+        Err(CalVerParseError::ParseIntError {
+            full_version: "2024.x.1".to_owned(),
+            version_part: "x".to_owned(),
+            parse_error: "invalid digit found in string".to_owned(),
+        })
+    );
+    assert_eq!(
+        parse("24.1.1"),
+        Err(CalVerParseError::InvalidYear {
+            full_version: "24.1.1".to_owned(),
+            year: "24".to_owned(),
+        })
+    );
+    for (version, month) in [("2024.13.1", "13"), ("2024.0.1", "0"), ("2024.1.05", "1")] {
+        assert_eq!(
+            parse(version),
+            Err(CalVerParseError::InvalidMonth {
+                full_version: version.to_owned(),
+                month: month.to_owned(),
+            })
+        );
+    }
+    for (version, micro) in [
+        ("2024.01.32", "32"),
+        ("2024.01.5", "5"),
+        ("2024.1.007", "007"),
+    ] {
+        assert_eq!(
+            parse(version),
+            Err(CalVerParseError::InvalidMicro {
+                full_version: version.to_owned(),
+                micro: micro.to_owned(),
+            })
+        );
+    }
+}
+
+#[test]
+fn calendar_version_ordering() {
+    let parse = |str: &str| str.parse::<CalendarVersion>().unwrap();
+    assert!(parse("2023.12.1") < parse("2024.1.1"));
+    assert!(parse("2024.1.9") < parse("2024.1.10"));
+    assert!(parse("2024.02.01") < parse("2024.10.01"));
+    assert!(parse("2024.01.31") < parse("2024.2.0"));
+
+    let range = Range::between(CalendarVersion::new(2024, 1, 0), (2025, 1, 0));
+    assert!(range.contains(&parse("2024.06.15")));
+    assert!(!range.contains(&parse("2023.12.31")));
+}
+
+/// Type for versions made of a fixed number of numeric components: `1.2.3.4` for `N = 4`.
+///
+/// Versions are compared component by component.
+/// When parsing, missing trailing components are zero,
+/// so `1.2` is the same version as `1.2.0.0`, and is displayed as such.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct GenericVersion<const N: usize>([u64; N]);
+
+#[cfg(feature = "serde")]
+impl<const N: usize> serde::Serialize for GenericVersion<N> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.serialize_str(&format!("{}", self))
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de, const N: usize> serde::Deserialize<'de> for GenericVersion<N> {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let s = String::deserialize(deserializer)?;
+        FromStr::from_str(&s).map_err(serde::de::Error::custom)
+    }
+}
+
+impl<const N: usize> GenericVersion<N> {
+    /// Create a version from its components.
+    pub fn new(components: [u64; N]) -> Self {
+        Self(components)
+    }
+
+    /// The components of the version.
+    pub fn components(&self) -> &[u64; N] {
+        &self.0
+    }
+}
+
+impl<const N: usize> From<[u64; N]> for GenericVersion<N> {
+    fn from(components: [u64; N]) -> Self {
+        Self::new(components)
+    }
+}
+
+/// Error creating [GenericVersion] from [String].
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum GenericVersionParseError {
+    /// [GenericVersion] must not contain more components than its size.
+    #[error("version {full_version} must contain at most {max} numbers separated by dot")]
+    TooManyParts {
+        /// [GenericVersion] that was being parsed.
+        full_version: String,
+        /// The number of components of the version type.
+        max: usize,
+    },
+    /// Wrapper around [ParseIntError](core::num::ParseIntError).
+    #[error("cannot parse '{version_part}' in '{full_version}' as u64: {parse_error}")]
+    ParseIntError {
+        /// [GenericVersion] that was being parsed.
+        full_version: String,
+        /// A version part where parsing failed.
+        version_part: String,
+        /// A specific error resulted from parsing a part of the version as [u64].
+        parse_error: String,
+    },
+}
+
+impl<const N: usize> FromStr for GenericVersion<N> {
+    type Err = GenericVersionParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut components = [0; N];
+        for (idx, part) in s.split('.').enumerate() {
+            let Some(component) = components.get_mut(idx) else {
+                return Err(Self::Err::TooManyParts {
+                    full_version: s.to_string(),
+                    max: N,
+                });
+            };
+            *component = part.parse::<u64>().map_err(|e| Self::Err::ParseIntError {
+                full_version: s.to_string(),
+                version_part: part.to_string(),
+                parse_error: e.to_string(),
+            })?;
+        }
+        Ok(Self(components))
+    }
+}
+
+impl<const N: usize> Display for GenericVersion<N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (idx, component) in self.0.iter().enumerate() {
+            if idx > 0 {
+                write!(f, ".")?;
+            }
+            write!(f, "{component}")?;
+        }
+        Ok(())
+    }
+}
+
+#[test]
+fn from_str_for_generic_version() {
+    let parse = |str: &str| str.parse::<GenericVersion<4>>();
+    assert_eq!(parse("1.2.3.4"), Ok(GenericVersion::new([1, 2, 3, 4])));
+    assert_eq!(parse("1.2"), Ok(GenericVersion::new([1, 2, 0, 0])));
+    assert_eq!(parse("7"), Ok(GenericVersion::new([7, 0, 0, 0])));
+    assert_eq!(
+        parse("5000000000.0.0.1"),
+        Ok(GenericVersion::new([5_000_000_000, 0, 0, 1]))
+    );
+    assert_eq!(parse("1.2").unwrap().to_string(), "1.2.0.0");
+    assert_eq!(parse("1.2.3.4").unwrap().to_string(), "1.2.3.4");
+
+    assert_eq!(
+        parse("1.2.3.4.5"),
+        Err(GenericVersionParseError::TooManyParts {
+            full_version: "1.2.3.4.5".to_owned(),
+            max: 4,
+        })
+    );
+    assert_eq!(
+        parse("1..3"),
+        Err(GenericVersionParseError::ParseIntError {
+            full_version: "1..3".to_owned(),
+            version_part: "".to_owned(),
+            parse_error: "cannot parse integer from empty string".to_owned(),
+        })
+    );
+}
+
+#[test]
+fn generic_version_ordering() {
+    let v = |str: &str| str.parse::<GenericVersion<4>>().unwrap();
+    assert!(v("1.2.3.4") < v("1.2.3.5"));
+    assert!(v("1.2.3.9") < v("1.2.4"));
+    assert!(v("1.10") > v("1.9.9.9"));
+    assert_eq!(v("1.2.3"), v("1.2.3.0"));
+
+    let range = Range::between(v("1.2"), v("1.3"));
+    assert!(range.contains(&v("1.2.99.1")));
+    assert!(!range.contains(&v("1.3.0.0")));
+    assert_eq!(range.to_string(), ">=1.2.0.0, <1.3.0.0");
 }
@@ -58,6 +58,30 @@ pub trait VersionSet: Debug + Display + Clone + Eq {
             .complement()
     }
 
+    /// Constructor for a set containing exactly one version if one is provided,
+    /// or an empty set otherwise.
+    fn singleton_or_empty(v: Option<Self::V>) -> Self {
+        v.map_or_else(Self::empty, Self::singleton)
+    }
+
+    /// If the set contains exactly one version, return it.
+    /// Otherwise, returns [None].
+    ///
+    /// The default implementation always returns [None],
+    /// implementors are encouraged to override it when they can tell cheaply.
+    fn as_singleton(&self) -> Option<&Self::V> {
+        None
+    }
+
+    /// An estimate of the size of the set, to prioritize packages with fewer allowed versions,
+    /// as done by [prioritize_by_width](crate::DependencyProvider::prioritize_by_width).
+    ///
+    /// It only needs to be cheap and to grow with the set, not to count versions exactly.
+    /// The default implementation returns 0 for the empty set and 1 otherwise.
+    fn approximate_len(&self) -> usize {
+        usize::from(self != &Self::empty())
+    }
+
     /// Whether the range have no overlapping segments.
     fn is_disjoint(&self, other: &Self) -> bool {
         self.intersection(other) == Self::empty()
@@ -68,3 +92,99 @@ pub trait VersionSet: Debug + Display + Clone + Eq {
         self == &self.intersection(other)
     }
 }
+
+/// Implements [VersionSet] for a newtype by forwarding every method to its inner version set,
+/// including the ones with a default implementation.
+///
+/// The newtype is given first, then its inner version set and the field holding it,
+/// a name or a tuple index.
+/// The newtype must implement the supertraits of [VersionSet] itself.
+///
+/// ```
+/// # use std::fmt::{self, Display, Formatter};
+/// # use pubgrub::{impl_version_set_newtype, Range, VersionSet};
+/// #[derive(Debug, Clone, PartialEq, Eq)]
+/// struct MyRange(Range<u32>);
+///
+/// impl Display for MyRange {
+///     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+///         self.0.fmt(f)
+///     }
+/// }
+///
+/// impl_version_set_newtype!(MyRange => Range<u32>, field: 0);
+///
+/// let range = MyRange::singleton(1).union(&MyRange::singleton(3));
+/// assert!(range.contains(&3));
+/// assert_eq!(range.0, Range::singleton(1u32).union(&Range::singleton(3u32)));
+/// ```
+#[macro_export]
+macro_rules! impl_version_set_newtype {
+    ($newtype:ty => $inner:ty, field: $field:tt) => {
+        impl $crate::VersionSet for $newtype {
+            type V = <$inner as $crate::VersionSet>::V;
+
+            fn empty() -> Self {
+                Self {
+                    $field: <$inner as $crate::VersionSet>::empty(),
+                }
+            }
+
+            fn singleton(v: Self::V) -> Self {
+                Self {
+                    $field: <$inner as $crate::VersionSet>::singleton(v),
+                }
+            }
+
+            fn complement(&self) -> Self {
+                Self {
+                    $field: $crate::VersionSet::complement(&self.$field),
+                }
+            }
+
+            fn intersection(&self, other: &Self) -> Self {
+                Self {
+                    $field: $crate::VersionSet::intersection(&self.$field, &other.$field),
+                }
+            }
+
+            fn contains(&self, v: &Self::V) -> bool {
+                $crate::VersionSet::contains(&self.$field, v)
+            }
+
+            fn full() -> Self {
+                Self {
+                    $field: <$inner as $crate::VersionSet>::full(),
+                }
+            }
+
+            fn union(&self, other: &Self) -> Self {
+                Self {
+                    $field: $crate::VersionSet::union(&self.$field, &other.$field),
+                }
+            }
+
+            fn singleton_or_empty(v: Option<Self::V>) -> Self {
+                Self {
+                    $field: <$inner as $crate::VersionSet>::singleton_or_empty(v),
+                }
+            }
+
+            fn as_singleton(&self) -> Option<&Self::V> {
+                $crate::VersionSet::as_singleton(&self.$field)
+            }
+
+            fn approximate_len(&self) -> usize {
+                $crate::VersionSet::approximate_len(&self.$field)
+            }
+
+            fn is_disjoint(&self, other: &Self) -> bool {
+                $crate::VersionSet::is_disjoint(&self.$field, &other.$field)
+            }
+
+            fn subset_of(&self, other: &Self) -> bool {
+                $crate::VersionSet::subset_of(&self.$field, &other.$field)
+            }
+        }
+    };
+}
@@ -0,0 +1,187 @@
+// SPDX-License-Identifier: MPL-2.0
+
+#![cfg(feature = "async")]
+
+use std::convert::Infallible;
+use std::future::Future;
+use std::pin::{pin, Pin};
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll, Wake};
+use std::thread::{self, Thread};
+
+use pubgrub::{
+    resolve, resolve_async, AsyncDependencyProvider, AsyncPubGrubError, AsyncSelectedDependencies,
+    BlockingDependencyProvider, Dependencies, DependencyProvider, OfflineDependencyProvider,
+    PubGrubError, Range,
+};
+
+type NumVS = Range<u32>;
+
+/// A future that is pending once before completing, like a network call would.
+#[derive(Default)]
+struct YieldNow(bool);
+
+impl Future for YieldNow {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        if self.0 {
+            Poll::Ready(())
+        } else {
+            self.0 = true;
+            cx.waker().wake_by_ref();
+            Poll::Pending
+        }
+    }
+}
+
+/// An in-memory provider, yielding before answering each request,
+/// and recording the packages given to `prefetch`.
+struct InMemoryAsyncProvider(
+    OfflineDependencyProvider<&'static str, NumVS>,
+    Mutex<Vec<&'static str>>,
+);
+
+impl AsyncDependencyProvider for InMemoryAsyncProvider {
+    type P = &'static str;
+    type V = u32;
+    type VS = NumVS;
+    type M = String;
+
+    fn prioritize(&self, package: &&'static str, range: &NumVS) -> Self::Priority {
+        self.0.prioritize(package, range)
+    }
+
+    type Priority =
+        <OfflineDependencyProvider<&'static str, NumVS> as DependencyProvider>::Priority;
+
+    type Err = Infallible;
+
+    async fn choose_version(
+        &self,
+        package: &&'static str,
+        range: &NumVS,
+    ) -> Result<Option<u32>, Infallible> {
+        YieldNow::default().await;
+        self.0.choose_version(package, range)
+    }
+
+    async fn get_dependencies(
+        &self,
+        package: &&'static str,
+        version: &u32,
+    ) -> Result<Dependencies<&'static str, NumVS, String>, Infallible> {
+        YieldNow::default().await;
+        self.0.get_dependencies(package, version)
+    }
+
+    fn prefetch(&self, requests: &[(&&'static str, &NumVS)]) {
+        let mut prefetched = self.1.lock().unwrap();
+        prefetched.extend(requests.iter().map(|(package, _)| **package));
+    }
+}
+
+struct ThreadWaker(Thread);
+
+impl Wake for ThreadWaker {
+    fn wake(self: Arc<Self>) {
+        self.0.unpark();
+    }
+}
+
+/// Minimal executor running a future to completion on the current thread.
+fn block_on<F: Future>(future: F) -> F::Output {
+    let mut future = pin!(future);
+    let waker = Arc::new(ThreadWaker(thread::current())).into();
+    let mut cx = Context::from_waker(&waker);
+    loop {
+        match future.as_mut().poll(&mut cx) {
+            Poll::Ready(output) => return output,
+            Poll::Pending => thread::park(),
+        }
+    }
+}
+
+fn dependency_provider() -> InMemoryAsyncProvider {
+    let mut dependency_provider = OfflineDependencyProvider::<&str, NumVS>::new();
+    dependency_provider.add_dependencies(
+        "root",
+        1u32,
+        [("menu", Range::full()), ("icons", Range::full())],
+    );
+    dependency_provider.add_dependencies("menu", 1u32, [("dropdown", Range::full())]);
+    dependency_provider.add_dependencies("menu", 2u32, [("dropdown", Range::higher_than(2u32))]);
+    dependency_provider.add_dependencies("dropdown", 1u32, [("icons", Range::full())]);
+    dependency_provider.add_dependencies("icons", 1u32, []);
+    InMemoryAsyncProvider(dependency_provider, Mutex::default())
+}
+
+#[test]
+fn resolve_with_async_provider() {
+    let dependency_provider = dependency_provider();
+    let solution = block_on(resolve_async(&dependency_provider, "root", 1u32)).unwrap();
+    let mut solution: Vec<_> = solution.into_iter().collect();
+    solution.sort();
+    assert_eq!(
+        solution,
+        vec![("dropdown", 1), ("icons", 1), ("menu", 1), ("root", 1)]
+    );
+
+    // Blocking on the same provider finds the same solution.
+    let blocking_provider = BlockingDependencyProvider::new(dependency_provider, |future| {
+        block_on(future);
+    });
+    let blocking_solution = resolve(&blocking_provider, "root", 1u32).unwrap();
+    assert_eq!(blocking_solution.len(), solution.len());
+    for (package, version) in solution {
+        assert_eq!(blocking_solution[package], version);
+    }
+}
+
+#[test]
+fn no_solution_with_async_provider() {
+    let dependency_provider = dependency_provider();
+    let result: Result<_, AsyncPubGrubError<InMemoryAsyncProvider>> =
+        block_on(resolve_async(&dependency_provider, "menu", 2u32));
+    assert!(matches!(result, Err(PubGrubError::NoSolution(_))));
+}
+
+#[test]
+fn prefetch_with_async_provider() {
+    let dependency_provider = dependency_provider();
+    block_on(resolve_async(&dependency_provider, "root", 1u32)).unwrap();
+    let mut prefetched = dependency_provider.1.into_inner().unwrap();
+    prefetched.sort();
+    // The dependencies of root 1, menu 2, menu 1 and dropdown 1.
+    assert_eq!(
+        prefetched,
+        vec!["dropdown", "dropdown", "icons", "icons", "menu"]
+    );
+}
+
+/// Only compiles if the future of [resolve_async] is [Send] for any `Sync` provider
+/// with `Send` and `Sync` types, so that it can be spawned on a multi-threaded runtime.
+#[allow(clippy::type_complexity)]
+fn spawnable<ADP>(
+    dependency_provider: &ADP,
+    package: ADP::P,
+    version: ADP::V,
+) -> impl Future<Output = Result<AsyncSelectedDependencies<ADP>, AsyncPubGrubError<ADP>>> + Send + '_
+where
+    ADP: AsyncDependencyProvider + Sync,
+    ADP::P: Send + Sync,
+    ADP::V: Send + Sync,
+    ADP::VS: Send + Sync,
+    ADP::M: Send + Sync,
+    ADP::Priority: Send + Sync,
+    ADP::Err: Send,
+{
+    resolve_async(dependency_provider, package, version)
+}
+
+#[test]
+fn resolve_async_future_is_send() {
+    let dependency_provider = dependency_provider();
+    let solution = block_on(spawnable(&dependency_provider, "root", 1u32)).unwrap();
+    assert_eq!(solution.len(), 4);
+}
@@ -1,10 +1,21 @@
 // SPDX-License-Identifier: MPL-2.0
 
+use std::sync::Arc;
+
 use pubgrub::{
-    resolve, DefaultStringReporter, Map, OfflineDependencyProvider, PubGrubError, Range,
-    Reporter as _, SemanticVersion, Set,
+    resolve, resolve_all_conflicts, resolve_bounded, resolve_constraints, resolve_latest,
+    resolve_multi, resolve_with_constraints, resolve_with_history, resolve_with_inspector,
+    resolve_with_limits, resolve_with_preferences, resolve_with_stats, DefaultStringReporter,
+    Dependencies, DependencyProvider, DerivationTree, Derived, External, IndentedTreeReporter, Map,
+    OfflineDependencyProvider, PubGrubError, Range, ReportPhrases, Reporter as _, ResolveEvent,
+    ResolveLimits, ResolveOutcome, ResolveStats, SemanticVersion, Set, Suggestion,
+    SuggestionReporter, Term,
 };
 
+use crate::spy_dependency_provider::SpyDependencyProvider;
+
+mod spy_dependency_provider;
+
 type NumVS = Range<u32>;
 type SemVS = Range<SemanticVersion>;
 
@@ -237,7 +248,7 @@ fn confusing_with_lots_of_holes() {
     };
     assert_eq!(
         &DefaultStringReporter::report(&derivation_tree),
-        r#"Because there is no available version for bar and foo 1 | 2 | 3 | 4 | 5 depends on bar, foo 1 | 2 | 3 | 4 | 5 is forbidden.
+        r#"Because package bar does not exist and foo 1 | 2 | 3 | 4 | 5 depends on bar, foo 1 | 2 | 3 | 4 | 5 is forbidden.
 And because there is no version of foo in <1 | >1, <2 | >2, <3 | >3, <4 | >4, <5 | >5 and root 1 depends on foo, root 1 is forbidden."#
     );
     derivation_tree.collapse_no_versions();
@@ -251,3 +262,954 @@ And because there is no version of foo in <1 | >1, <2 | >2, <3 | >3, <4 | >4, <5
         Set::from_iter(&["root", "foo", "bar"])
     );
 }
+
+#[test]
+fn collapse_no_versions_into_derived() {
+    let mut dependency_provider = OfflineDependencyProvider::<&str, NumVS>::new();
+
+    dependency_provider.add_dependencies(
+        "root",
+        1u32,
+        vec![
+            ("foo", Range::between(1u32, 10u32)),
+            ("baz", Range::singleton(1u32)),
+        ],
+    );
+    for i in [1u32, 3] {
+        dependency_provider.add_dependencies("foo", i, vec![("bar", Range::between(1u32, 5u32))]);
+    }
+    for i in [2u32, 4] {
+        dependency_provider.add_dependencies("bar", i, vec![("baz", Range::singleton(2u32))]);
+    }
+    dependency_provider.add_dependencies("baz", 1u32, vec![]);
+    dependency_provider.add_dependencies("baz", 2u32, vec![]);
+
+    let Err(PubGrubError::NoSolution(mut derivation_tree)) =
+        resolve(&dependency_provider, "root", 1u32)
+    else {
+        unreachable!()
+    };
+    assert_eq!(
+        &DefaultStringReporter::report(&derivation_tree),
+        r#"Because bar 2 | 4 depends on baz 2 and there is no version of bar in >=1, <2 | >2, <4 | >4, <5, bar >=1, <5 depends on baz 2.
+And because foo 1 | 3 depends on bar >=1, <5 and there is no version of foo in >1, <3 | >3, <10, foo >=1, <10 depends on baz 2.
+And because root 1 depends on foo >=1, <10 and root 1 depends on baz 1, root 1 is forbidden."#
+    );
+    derivation_tree.collapse_no_versions();
+    // The missing versions are merged into the derived incompatibilities,
+    // so every conclusion covers the same versions as the dependency it explains.
+    assert_eq!(
+        &DefaultStringReporter::report(&derivation_tree),
+        r#"Because bar >=1, <5 depends on baz 2 and foo >=1, <10 depends on bar >=1, <5, foo >=1, <10 depends on baz 2.
+And because root 1 depends on foo >=1, <10 and root 1 depends on baz 1, root 1 is forbidden."#
+    );
+}
+
+#[test]
+fn package_not_found() {
+    let mut dependency_provider = OfflineDependencyProvider::<&str, NumVS>::new();
+    dependency_provider.add_dependencies("root", 1u32, [("missing", Range::full())]);
+    dependency_provider.add_dependencies("root", 2u32, [("a", Range::singleton(2u32))]);
+    dependency_provider.add_dependencies("a", 1u32, []);
+
+    // The package was never registered.
+    let Err(PubGrubError::NoSolution(derivation_tree)) =
+        resolve(&dependency_provider, "root", 1u32)
+    else {
+        panic!("there should be no solution");
+    };
+    assert!(derivation_tree
+        .externals()
+        .any(|external| matches!(external, External::PackageNotFound("missing"))));
+    assert_eq!(
+        &DefaultStringReporter::report(&derivation_tree),
+        "Because package missing does not exist and root 1 depends on missing, root 1 is forbidden."
+    );
+
+    // The package exists, without a matching version.
+    let Err(PubGrubError::NoSolution(derivation_tree)) =
+        resolve(&dependency_provider, "root", 2u32)
+    else {
+        panic!("there should be no solution");
+    };
+    assert_eq!(
+        &DefaultStringReporter::report(&derivation_tree),
+        "Because there is no version of a in 2 and root 2 depends on a 2, root 2 is forbidden."
+    );
+}
+
+/// The example from `examples/doc_interface_error.rs`:
+///
+/// - `root` depends on `menu`, `icons 1.0.0` and `intl 5.0.0`
+/// - `menu 1.0.0` depends on `dropdown < 2.0.0`
+/// - `menu >= 1.1.0` depends on `dropdown >= 2.0.0`
+/// - `dropdown 1.8.0` depends on `intl 3.0.0`
+/// - `dropdown >= 2.0.0` depends on `icons 2.0.0`
+/// - `icons` and `intl` have no dependency
+#[rustfmt::skip]
+fn dropdown_menu_icons() -> OfflineDependencyProvider<&'static str, SemVS> {
+    let mut dependency_provider = OfflineDependencyProvider::<&str, SemVS>::new();
+    dependency_provider.add_dependencies("root", (1, 0, 0), [
+        ("menu", Range::full()),
+        ("icons", Range::singleton((1, 0, 0))),
+        ("intl", Range::singleton((5, 0, 0))),
+    ]);
+    dependency_provider.add_dependencies("menu", (1, 0, 0), [
+        ("dropdown", Range::from_range_bounds(..(2, 0, 0))),
+    ]);
+    for minor in 1..6 {
+        dependency_provider.add_dependencies("menu", (1, minor, 0), [
+            ("dropdown", Range::from_range_bounds((2, 0, 0)..)),
+        ]);
+    }
+    dependency_provider.add_dependencies("dropdown", (1, 8, 0), [
+        ("intl", Range::singleton((3, 0, 0))),
+    ]);
+    for minor in 0..4 {
+        dependency_provider.add_dependencies("dropdown", (2, minor, 0), [
+            ("icons", Range::singleton((2, 0, 0))),
+        ]);
+    }
+    dependency_provider.add_dependencies("icons", (1, 0, 0), []);
+    dependency_provider.add_dependencies("icons", (2, 0, 0), []);
+    dependency_provider.add_dependencies("intl", (3, 0, 0), []);
+    dependency_provider.add_dependencies("intl", (4, 0, 0), []);
+    dependency_provider.add_dependencies("intl", (5, 0, 0), []);
+    dependency_provider
+}
+
+#[test]
+fn preferred_versions() {
+    let mut dependency_provider = OfflineDependencyProvider::<&str, NumVS>::new();
+    dependency_provider.add_dependencies(
+        "root",
+        1u32,
+        [("foo", Range::full()), ("bar", Range::full())],
+    );
+    for v in 1u32..4 {
+        dependency_provider.add_dependencies("foo", v, []);
+    }
+    dependency_provider.add_dependencies("bar", 1u32, [("baz", Range::higher_than(2u32))]);
+    dependency_provider.add_dependencies("baz", 1u32, []);
+    dependency_provider.add_dependencies("baz", 2u32, []);
+    dependency_provider.add_dependencies("baz", 3u32, []);
+
+    // Without preferences, the newest versions are chosen.
+    let solution = resolve(&dependency_provider, "root", 1u32).unwrap();
+    assert_eq!(solution["foo"], 3);
+    assert_eq!(solution["baz"], 3);
+
+    // A preferred version that is still valid is chosen over the newest,
+    // and an infeasible one does not prevent finding a solution.
+    let preferences = Map::from_iter([("foo", 2u32), ("baz", 1u32)]);
+    let solution =
+        resolve_with_preferences(&dependency_provider, "root", 1u32, &preferences).unwrap();
+    assert_eq!(solution["foo"], 2);
+    assert_eq!(solution["baz"], 3);
+
+    // A preferred version that no longer exists is not chosen.
+    let preferences = Map::from_iter([("foo", 7u32)]);
+    let solution =
+        resolve_with_preferences(&dependency_provider, "root", 1u32, &preferences).unwrap();
+    assert_eq!(solution["foo"], 3);
+}
+
+#[test]
+fn bounded_resolution() {
+    // A long chain of packages, each one depending on the next.
+    let mut dependency_provider = OfflineDependencyProvider::<u32, NumVS>::new();
+    for package in 0u32..20 {
+        dependency_provider.add_dependencies(package, 1u32, [(package + 1, Range::full())]);
+    }
+    dependency_provider.add_dependencies(20, 1u32, []);
+
+    let Ok(ResolveOutcome::Exhausted {
+        steps,
+        partial_solution_summary,
+    }) = resolve_bounded(&dependency_provider, 0, 1u32, 3)
+    else {
+        panic!("the budget should be exhausted")
+    };
+    assert_eq!(steps, 3);
+    assert_eq!(
+        partial_solution_summary.decided,
+        Map::from_iter([(0, 1), (1, 1), (2, 1)])
+    );
+    assert_eq!(partial_solution_summary.undecided, vec![3]);
+
+    let Ok(ResolveOutcome::Solved(solution)) = resolve_bounded(&dependency_provider, 0, 1u32, 100)
+    else {
+        panic!("the budget should be enough")
+    };
+    assert_eq!(solution.len(), 21);
+
+    dependency_provider.add_dependencies(20, 1u32, [(0, Range::higher_than(2u32))]);
+    assert!(matches!(
+        resolve_bounded(&dependency_provider, 0, 1u32, 100),
+        Ok(ResolveOutcome::NoSolution(_))
+    ));
+}
+
+#[test]
+fn latest_root_version_with_a_solution() {
+    let mut dependency_provider = OfflineDependencyProvider::<&str, NumVS>::new();
+    dependency_provider.add_dependencies("root", 1u32, [("a", Range::full())]);
+    dependency_provider.add_dependencies("root", 2u32, [("a", Range::full())]);
+    dependency_provider.add_dependencies("root", 3u32, [("a", Range::higher_than(2u32))]);
+    dependency_provider.add_dependencies("a", 1u32, []);
+
+    // The newest root version needs a missing version of a.
+    let (version, solution) = resolve_latest(&dependency_provider, "root", Range::full()).unwrap();
+    assert_eq!(version, 2);
+    assert_eq!(solution, Map::from_iter([("root", 2), ("a", 1)]));
+
+    // Without any working version, the conflict of the last one tried is reported.
+    let Err(PubGrubError::NoSolution(derivation_tree)) =
+        resolve_latest(&dependency_provider, "root", Range::higher_than(3u32))
+    else {
+        panic!("there should be no solution");
+    };
+    assert_eq!(derivation_tree.packages(), Set::from_iter([&"root", &"a"]));
+    assert!(matches!(
+        resolve_latest(&dependency_provider, "root", Range::higher_than(4u32)),
+        Err(PubGrubError::NoSolution(DerivationTree::External(
+            External::NoVersions("root", _)
+        )))
+    ));
+}
+
+#[test]
+fn resolution_limits() {
+    // Each version of a depends on a version of b needing the missing c,
+    // so that every version of a is tried and rejected in turn.
+    let mut dependency_provider = OfflineDependencyProvider::<&str, NumVS>::new();
+    dependency_provider.add_dependencies("root", 1u32, [("a", Range::full())]);
+    for version in 1u32..=50 {
+        dependency_provider.add_dependencies("a", version, [("b", Range::singleton(version))]);
+        dependency_provider.add_dependencies("b", version, [("c", Range::full())]);
+    }
+    let limits = ResolveLimits {
+        max_incompatibilities: 20,
+        ..ResolveLimits::default()
+    };
+    let Err(PubGrubError::LimitExceeded {
+        incompatibilities, ..
+    }) = resolve_with_limits(&dependency_provider, "root", 1u32, limits)
+    else {
+        panic!("the incompatibilities limit should be exceeded")
+    };
+    assert!(incompatibilities > 20);
+    assert!(matches!(
+        resolve_with_limits(&dependency_provider, "root", 1u32, ResolveLimits::default()),
+        Err(PubGrubError::NoSolution(_))
+    ));
+
+    // The limit is checked as the dependencies of root are added,
+    // before any decision is made.
+    let mut dependency_provider = OfflineDependencyProvider::<u32, NumVS>::new();
+    dependency_provider.add_dependencies(0, 1u32, (1u32..=10).map(|p| (p, Range::full())));
+    let limits = ResolveLimits {
+        max_incompatibilities: 5,
+        ..ResolveLimits::default()
+    };
+    assert!(matches!(
+        resolve_with_limits(&dependency_provider, 0, 1u32, limits),
+        Err(PubGrubError::LimitExceeded {
+            incompatibilities: 11,
+            decision_level: 0
+        })
+    ));
+
+    // A long chain of packages, each one depending on the next.
+    let mut dependency_provider = OfflineDependencyProvider::<u32, NumVS>::new();
+    for package in 0u32..20 {
+        dependency_provider.add_dependencies(package, 1u32, [(package + 1, Range::full())]);
+    }
+    dependency_provider.add_dependencies(20, 1u32, []);
+    let limits = ResolveLimits {
+        max_decision_level: 5,
+        ..ResolveLimits::default()
+    };
+    let error = resolve_with_limits(&dependency_provider, 0, 1u32, limits).unwrap_err();
+    assert!(!error.is_no_solution() && !error.is_provider_error());
+    assert!(matches!(
+        error,
+        PubGrubError::LimitExceeded {
+            decision_level: 6,
+            ..
+        }
+    ));
+    let limits = ResolveLimits {
+        max_decision_level: 21,
+        ..limits
+    };
+    let solution = resolve_with_limits(&dependency_provider, 0, 1u32, limits).unwrap();
+    assert_eq!(solution.len(), 21);
+}
+
+#[test]
+fn progress_callback() {
+    let mut dependency_provider = OfflineDependencyProvider::<&str, NumVS>::new();
+    dependency_provider.add_dependencies(
+        "root",
+        1u32,
+        [("foo", Range::full()), ("bar", Range::full())],
+    );
+    // foo 2 conflicts with bar, so a conflict is resolved to pick foo 1.
+    dependency_provider.add_dependencies("foo", 1u32, []);
+    dependency_provider.add_dependencies("foo", 2u32, [("bar", Range::higher_than(2u32))]);
+    dependency_provider.add_dependencies("bar", 1u32, []);
+    let dependency_provider = SpyDependencyProvider::new(dependency_provider);
+
+    let solution = resolve(&dependency_provider, "root", 1u32).unwrap();
+    assert_eq!(solution["foo"], 1);
+    let progress = dependency_provider.progress.into_inner();
+    let max_decision_level = progress.iter().map(|info| info.decision_level).max();
+    assert!(max_decision_level.unwrap() > 0);
+    assert_eq!(progress.last().unwrap().decided_count, 3);
+    assert_eq!(progress.last().unwrap().undecided_count, 0);
+    assert!(progress.last().unwrap().conflict_count > 0);
+}
+
+#[test]
+fn policy_constraints() {
+    let mut dependency_provider = OfflineDependencyProvider::<&str, NumVS>::new();
+    dependency_provider.add_dependencies("root", 1u32, [("foo", Range::higher_than(2u32))]);
+    dependency_provider.add_dependencies("foo", 1u32, []);
+    dependency_provider.add_dependencies("foo", 2u32, []);
+    dependency_provider.add_dependencies("foo", 3u32, []);
+
+    // Banning the newest version falls back to another one.
+    let policy = (
+        "foo",
+        Range::singleton(3u32),
+        "because of a security advisory".to_string(),
+    );
+    let solution =
+        resolve_with_constraints(&dependency_provider, "root", 1u32, [policy.clone()]).unwrap();
+    assert_eq!(solution["foo"], 2);
+
+    // Banning the only remaining compatible version leaves no solution.
+    let Err(PubGrubError::NoSolution(derivation_tree)) = resolve_with_constraints(
+        &dependency_provider,
+        "root",
+        1u32,
+        [
+            policy,
+            (
+                "foo",
+                Range::singleton(2u32),
+                "because of its license".to_string(),
+            ),
+        ],
+    ) else {
+        unreachable!()
+    };
+    assert_eq!(
+        DefaultStringReporter::report(&derivation_tree),
+        "Because there is no version of foo in >2, <3 | >3 and dependencies of foo at version 3 are unavailable because of a security advisory, dependencies of foo at version >2 are unavailable because of a security advisory.\n\
+        And because dependencies of foo at version 2 are unavailable because of its license and root 1 depends on foo >=2, root 1 is forbidden."
+    );
+}
+
+#[test]
+fn resolution_stats() {
+    // Without conflicts, no backtracking is needed.
+    let mut dependency_provider = OfflineDependencyProvider::<&str, NumVS>::new();
+    dependency_provider.add_dependencies("root", 1u32, [("foo", Range::full())]);
+    dependency_provider.add_dependencies("foo", 1u32, []);
+    let (solution, stats) = resolve_with_stats(&dependency_provider, "root", 1u32);
+    assert!(solution.is_ok());
+    assert_eq!(
+        stats,
+        ResolveStats {
+            decisions: 2,
+            backtracks: 0,
+            derived_incompatibilities: 0,
+            peak_decision_level: 2,
+        }
+    );
+
+    // Each newest version of foo conflicts with bar, so they are tried one after the other.
+    let mut dependency_provider = OfflineDependencyProvider::<&str, NumVS>::new();
+    dependency_provider.add_dependencies(
+        "root",
+        1u32,
+        [("foo", Range::full()), ("bar", Range::full())],
+    );
+    dependency_provider.add_dependencies("foo", 1u32, []);
+    for v in 2..5u32 {
+        dependency_provider.add_dependencies("foo", v, [("bar", Range::higher_than(2u32))]);
+    }
+    dependency_provider.add_dependencies("bar", 1u32, []);
+    let (solution, stats) = resolve_with_stats(&dependency_provider, "root", 1u32);
+    assert_eq!(solution.unwrap()["foo"], 1);
+    assert!(stats.backtracks > 0);
+    assert!(stats.decisions >= 3);
+    assert_eq!(stats.peak_decision_level, 3);
+}
+
+#[test]
+fn resolution_history() {
+    // Each newest version of foo conflicts with bar, so they are tried one after the other.
+    let mut dependency_provider = OfflineDependencyProvider::<&str, NumVS>::new();
+    dependency_provider.add_dependencies(
+        "root",
+        1u32,
+        [("foo", Range::full()), ("bar", Range::full())],
+    );
+    dependency_provider.add_dependencies("foo", 1u32, []);
+    for v in 2..5u32 {
+        dependency_provider.add_dependencies("foo", v, [("bar", Range::higher_than(2u32))]);
+    }
+    dependency_provider.add_dependencies("bar", 1u32, []);
+    let (solution, history) = resolve_with_history(&dependency_provider, "root", 1u32);
+    assert_eq!(solution.unwrap()["foo"], 1);
+    // bar is decided first, so foo 4 is tried before backtracking and picking foo 1.
+    assert_eq!(
+        history,
+        vec![
+            ResolveEvent::Decided {
+                package: "root",
+                version: 1,
+                level: 1,
+            },
+            ResolveEvent::Decided {
+                package: "bar",
+                version: 1,
+                level: 2,
+            },
+            ResolveEvent::Decided {
+                package: "foo",
+                version: 4,
+                level: 3,
+            },
+            ResolveEvent::Backtracked { to_level: 2 },
+            ResolveEvent::Decided {
+                package: "foo",
+                version: 1,
+                level: 3,
+            },
+        ]
+    );
+}
+
+#[test]
+fn inspector() {
+    let mut dependency_provider = OfflineDependencyProvider::<&str, NumVS>::new();
+    dependency_provider.add_dependencies(
+        "root",
+        1u32,
+        [("foo", Range::full()), ("bar", Range::full())],
+    );
+    dependency_provider.add_dependencies("foo", 1u32, []);
+    dependency_provider.add_dependencies("bar", 1u32, []);
+
+    let mut decisions: Vec<Vec<(&str, u32)>> = Vec::new();
+    let mut decision_levels = Vec::new();
+    let solution = resolve_with_inspector(&dependency_provider, "root", 1u32, |state| {
+        decisions.push(state.decided().map(|(p, v)| (*p, *v)).collect());
+        decision_levels.push(state.current_decision_level());
+    })
+    .unwrap();
+
+    // Nothing is decided before the root package, which is decided first.
+    assert_eq!(decisions[0], vec![]);
+    assert_eq!(decisions[1], vec![("root", 1)]);
+    assert_eq!(decisions.last().unwrap().len(), 3);
+    assert_eq!(decision_levels, vec![0, 1, 2, 3]);
+    assert_eq!(
+        Map::from_iter(decisions.last().unwrap().iter().copied()),
+        solution
+    );
+}
+
+#[test]
+fn inspector_pinned_version() {
+    let mut dependency_provider = OfflineDependencyProvider::<&str, NumVS>::new();
+    dependency_provider.add_dependencies(
+        "root",
+        1u32,
+        [("foo", Range::singleton(2u32)), ("bar", Range::full())],
+    );
+    dependency_provider.add_dependencies("foo", 1u32, []);
+    dependency_provider.add_dependencies("foo", 2u32, []);
+    dependency_provider.add_dependencies("bar", 1u32, []);
+    dependency_provider.add_dependencies("bar", 2u32, []);
+
+    let mut pinned = [None; 4];
+    resolve_with_inspector(&dependency_provider, "root", 1u32, |state| {
+        if state.current_decision_level() == 1 {
+            pinned = ["root", "foo", "bar", "baz"].map(|p| state.pinned_version(&p).copied());
+        }
+    })
+    .unwrap();
+
+    // After deciding root, foo is not decided yet but can only be 2.
+    assert_eq!(pinned, [Some(1), Some(2), None, None]);
+}
+
+#[test]
+fn multiple_roots() {
+    let mut dependency_provider = OfflineDependencyProvider::<&str, NumVS>::new();
+    dependency_provider.add_dependencies("a", 1u32, [("c", Range::full())]);
+    dependency_provider.add_dependencies("b", 1u32, [("c", Range::strictly_lower_than(3u32))]);
+    dependency_provider.add_dependencies("b", 2u32, [("c", Range::singleton(3u32))]);
+    dependency_provider.add_dependencies("c", 1u32, []);
+    dependency_provider.add_dependencies("c", 2u32, []);
+    dependency_provider.add_dependencies("c", 3u32, []);
+
+    // The shared dependency c satisfies both roots.
+    let solution = resolve_multi(&dependency_provider, [("a", 1), ("b", 1)]).unwrap();
+    assert_eq!(solution, Map::from_iter([("a", 1), ("b", 1), ("c", 2)]));
+
+    // The conflict is reported without any hidden root package.
+    let error = resolve_multi(&dependency_provider, [("a", 1), ("b", 2), ("c", 1)]).unwrap_err();
+    assert_eq!(
+        error.involved_packages(),
+        Some(Set::from_iter([&"b", &"c"]))
+    );
+    let PubGrubError::NoSolution(derivation_tree) = error else {
+        unreachable!()
+    };
+    assert_eq!(
+        DefaultStringReporter::report(&derivation_tree),
+        "Because b 2 depends on c 3 and we are solving dependencies of c 1, b 2 is forbidden.\n\
+        And because we are solving dependencies of b 2, version solving failed."
+    );
+
+    assert!(resolve_multi(&dependency_provider, []).unwrap().is_empty());
+}
+
+#[test]
+fn root_constraints() {
+    let mut dependency_provider = OfflineDependencyProvider::<&str, NumVS>::new();
+    dependency_provider.add_dependencies("a", 1u32, [("c", Range::singleton(1u32))]);
+    dependency_provider.add_dependencies("a", 2u32, [("c", Range::singleton(2u32))]);
+    dependency_provider.add_dependencies("b", 1u32, [("c", Range::singleton(1u32))]);
+    dependency_provider.add_dependencies("b", 2u32, [("c", Range::singleton(3u32))]);
+    dependency_provider.add_dependencies("c", 1u32, []);
+    dependency_provider.add_dependencies("c", 2u32, []);
+    dependency_provider.add_dependencies("c", 3u32, []);
+
+    // No root package is registered, the solution only has the constrained packages
+    // and their dependencies.
+    let constraints = Map::from_iter([("a", Range::full()), ("b", Range::full())]);
+    let solution = resolve_constraints(&dependency_provider, constraints).unwrap();
+    assert_eq!(solution, Map::from_iter([("a", 1), ("b", 1), ("c", 1)]));
+
+    // The conflict is reported between the constraints, without the hidden root.
+    let constraints =
+        Map::from_iter([("a", Range::singleton(2u32)), ("b", Range::singleton(2u32))]);
+    let PubGrubError::NoSolution(derivation_tree) =
+        resolve_constraints(&dependency_provider, constraints).unwrap_err()
+    else {
+        unreachable!()
+    };
+    assert_eq!(
+        derivation_tree.packages(),
+        Set::from_iter([&"a", &"b", &"c"])
+    );
+    assert_eq!(
+        DefaultStringReporter::report(&derivation_tree),
+        "Because b 2 depends on c 3 and a 2 depends on c 2, a 2 and b 2 are incompatible."
+    );
+
+    // The hidden root has its own version, even when no constrained package has any.
+    let constraints = Map::from_iter([("a", Range::higher_than(3u32))]);
+    let PubGrubError::NoSolution(derivation_tree) =
+        resolve_constraints(&dependency_provider, constraints).unwrap_err()
+    else {
+        unreachable!()
+    };
+    assert_eq!(
+        DefaultStringReporter::report(&derivation_tree),
+        "there is no version of a in >=3"
+    );
+
+    assert!(resolve_constraints(&dependency_provider, Map::default())
+        .unwrap()
+        .is_empty());
+}
+
+#[test]
+fn all_independent_conflicts() {
+    let mut dependency_provider = OfflineDependencyProvider::<&str, NumVS>::new();
+    dependency_provider.add_dependencies(
+        "root",
+        1u32,
+        [
+            ("a", Range::full()),
+            ("b", Range::singleton(1u32)),
+            ("c", Range::full()),
+            ("d", Range::singleton(1u32)),
+            ("e", Range::full()),
+        ],
+    );
+    // a and b are incompatible.
+    dependency_provider.add_dependencies("a", 1u32, [("b", Range::singleton(2u32))]);
+    dependency_provider.add_dependencies("b", 1u32, []);
+    dependency_provider.add_dependencies("b", 2u32, []);
+    // c and d are incompatible.
+    dependency_provider.add_dependencies("c", 1u32, [("d", Range::singleton(2u32))]);
+    dependency_provider.add_dependencies("d", 1u32, []);
+    dependency_provider.add_dependencies("d", 2u32, []);
+    // e is fine.
+    dependency_provider.add_dependencies("e", 1u32, []);
+
+    let Err(PubGrubError::IndependentConflicts(conflicts)) =
+        resolve_all_conflicts(&dependency_provider, "root", 1u32)
+    else {
+        panic!("both conflicts should be found")
+    };
+    let mut conflicting: Vec<Vec<&str>> = conflicts
+        .iter()
+        .map(|derivation_tree| {
+            derivation_tree
+                .root_causes(&"root")
+                .into_iter()
+                .map(|(package, _)| package)
+                .collect()
+        })
+        .collect();
+    conflicting.sort();
+    assert_eq!(conflicting, vec![vec!["a", "b"], vec!["c", "d"]]);
+
+    // Without conflicts, this is the same as resolve.
+    let solution = resolve_all_conflicts(&dependency_provider, "e", 1u32).unwrap();
+    assert_eq!(solution, resolve(&dependency_provider, "e", 1u32).unwrap());
+}
+
+#[test]
+fn custom_reason_in_conclusion() {
+    let mut dependency_provider = OfflineDependencyProvider::<&str, NumVS>::new();
+    dependency_provider.add_dependencies("root", 1u32, vec![("x", Range::full())]);
+    dependency_provider.add_dependencies("x", 1u32, vec![]);
+    // The dependencies of x are reported as unavailable.
+    let dependency_provider = SpyDependencyProvider::new(dependency_provider)
+        .with_get_dependencies(|spy, p, v| {
+            if p == &"x" {
+                return Ok(Dependencies::Unavailable(
+                    "because it was yanked".to_string(),
+                ));
+            }
+            spy.inner.get_dependencies(p, v)
+        });
+
+    let Err(PubGrubError::NoSolution(derivation_tree)) =
+        resolve(&dependency_provider, "root", 1u32)
+    else {
+        unreachable!()
+    };
+    // The conclusion forbidding x keeps the reason instead of reading "x * is forbidden".
+    assert_eq!(
+        &DefaultStringReporter::report(&derivation_tree),
+        r#"Because there is no version of x in != 1 and dependencies of x at version 1 are unavailable because it was yanked, dependencies of x are unavailable because it was yanked.
+And because root 1 depends on x, root 1 is forbidden."#
+    );
+}
+
+/// A formatter only overriding the phrases, to write reports in French.
+struct FrenchReportFormatter;
+
+impl ReportPhrases for FrenchReportFormatter {
+    fn no_version_phrase(&self, package: &str, set: Option<&str>) -> String {
+        match set {
+            None => format!("aucune version de {} n'est disponible", package),
+            Some(set) => format!("aucune version de {} n'est dans {}", package, set),
+        }
+    }
+
+    fn depends_on_phrase(&self, package: &str, dependency: &str) -> String {
+        format!("{} dépend de {}", package, dependency)
+    }
+
+    fn forbidden_phrase(&self, term: &str) -> String {
+        format!("{} est interdit", term)
+    }
+
+    fn and_phrase(&self, cause1: &str, cause2: &str) -> String {
+        format!("{} et {}", cause1, cause2)
+    }
+
+    fn because_phrase(&self, cause: &str, conclusion: &str) -> String {
+        format!("Puisque {}, {}.", cause, conclusion)
+    }
+
+    fn and_because_phrase(&self, cause: &str, conclusion: &str) -> String {
+        format!("Et puisque {}, {}.", cause, conclusion)
+    }
+}
+
+#[test]
+fn localized_report() {
+    let mut dependency_provider = OfflineDependencyProvider::<&str, NumVS>::new();
+    dependency_provider.add_dependencies("root", 1u32, vec![("foo", Range::singleton(1u32))]);
+    dependency_provider.add_dependencies("foo", 1u32, vec![("bar", Range::singleton(2u32))]);
+    dependency_provider.add_dependencies("bar", 1u32, vec![]);
+
+    let Err(PubGrubError::NoSolution(derivation_tree)) =
+        resolve(&dependency_provider, "root", 1u32)
+    else {
+        unreachable!()
+    };
+    assert_eq!(
+        &DefaultStringReporter::report_with_formatter(&derivation_tree, &FrenchReportFormatter),
+        r#"Puisque aucune version de bar n'est dans 2 et foo 1 dépend de bar 2, foo 1 est interdit.
+Et puisque root 1 dépend de foo 1, root 1 est interdit."#
+    );
+}
+
+#[test]
+fn indented_tree_report() {
+    let Err(PubGrubError::NoSolution(mut derivation_tree)) =
+        resolve(&dropdown_menu_icons(), "root", (1, 0, 0))
+    else {
+        unreachable!()
+    };
+    derivation_tree.collapse_no_versions();
+    assert_eq!(
+        &IndentedTreeReporter::report(&derivation_tree),
+        r#"root 1.0.0 is forbidden
+  root 1.0.0 depends on intl 3.0.0
+    menu *, root 1.0.0 and intl not ( 3.0.0 ) are incompatible
+      menu *, icons not ( 2.0.0 ) and intl not ( 3.0.0 ) are incompatible
+        menu *, dropdown not ( 2.0.0 | 2.1.0 | 2.2.0 | 2.3.0 ) and intl not ( 3.0.0 ) are incompatible
+          menu depends on dropdown <=2.0.0 | 2.1.0 | 2.2.0 | 2.3.0
+            menu != 1.0.0 depends on dropdown >=2.0.0
+            menu 1.0.0 depends on dropdown <2.0.0 | >2.0.0, <2.1.0 | >2.1.0, <2.2.0 | >2.2.0, <2.3.0 | >2.3.0
+          dropdown <2.0.0 | >2.0.0, <2.1.0 | >2.1.0, <2.2.0 | >2.2.0, <2.3.0 | >2.3.0 depends on intl 3.0.0
+        dropdown 2.0.0 | 2.1.0 | 2.2.0 | 2.3.0 depends on icons 2.0.0
+      root 1.0.0 depends on icons 1.0.0
+    root 1.0.0 depends on menu
+  root 1.0.0 depends on intl 5.0.0"#
+    );
+}
+
+#[test]
+fn derivation_tree_externals() {
+    let Err(PubGrubError::NoSolution(mut derivation_tree)) =
+        resolve(&dropdown_menu_icons(), "root", (1, 0, 0))
+    else {
+        unreachable!()
+    };
+    derivation_tree.collapse_no_versions();
+    let externals: Set<String> = derivation_tree
+        .externals()
+        .map(|external| external.to_string())
+        .collect();
+    let expected: Set<String> = [
+        "dropdown 2.0.0 | 2.1.0 | 2.2.0 | 2.3.0 depends on icons 2.0.0",
+        "menu != 1.0.0 depends on dropdown >=2.0.0",
+        "menu 1.0.0 depends on dropdown <2.0.0 | >2.0.0, <2.1.0 | >2.1.0, <2.2.0 | >2.2.0, <2.3.0 | >2.3.0",
+        "dropdown <2.0.0 | >2.0.0, <2.1.0 | >2.1.0, <2.2.0 | >2.2.0, <2.3.0 | >2.3.0 depends on intl 3.0.0",
+        "root 1.0.0 depends on icons 1.0.0",
+        "root 1.0.0 depends on menu",
+        "root 1.0.0 depends on intl 5.0.0",
+    ]
+    .into_iter()
+    .map(String::from)
+    .collect();
+    assert_eq!(externals, expected);
+}
+
+#[test]
+fn derivation_tree_root_causes() {
+    let Err(PubGrubError::NoSolution(derivation_tree)) =
+        resolve(&dropdown_menu_icons(), "root", (1, 0, 0))
+    else {
+        unreachable!()
+    };
+    assert_eq!(
+        derivation_tree.root_causes(&"root"),
+        vec![
+            ("icons", Range::singleton((1, 0, 0))),
+            ("menu", Range::full()),
+            ("intl", Range::singleton((5, 0, 0))),
+        ]
+    );
+}
+
+#[test]
+fn derivation_tree_unavailable_packages() {
+    let mut dependency_provider = OfflineDependencyProvider::<&str, NumVS>::new();
+    dependency_provider.add_dependencies("root", 1u32, vec![("x", Range::full())]);
+    dependency_provider.add_dependencies("x", 1u32, vec![]);
+    // The dependencies of x are reported as unavailable.
+    let dependency_provider = SpyDependencyProvider::new(dependency_provider)
+        .with_get_dependencies(|spy, p, v| {
+            if p == &"x" {
+                return Ok(Dependencies::Unavailable(
+                    "because it was yanked".to_string(),
+                ));
+            }
+            spy.inner.get_dependencies(p, v)
+        });
+
+    let Err(PubGrubError::NoSolution(derivation_tree)) =
+        resolve(&dependency_provider, "root", 1u32)
+    else {
+        unreachable!()
+    };
+    assert_eq!(
+        derivation_tree.unavailable_packages(),
+        vec![(
+            &"x",
+            &Range::singleton(1u32),
+            &"because it was yanked".to_string()
+        )]
+    );
+
+    // Missing versions are not reported as unavailable.
+    let Err(PubGrubError::NoSolution(derivation_tree)) =
+        resolve(&dropdown_menu_icons(), "root", (1, 0, 0))
+    else {
+        unreachable!()
+    };
+    assert!(derivation_tree.unavailable_packages().is_empty());
+}
+
+#[test]
+fn suggestion_report() {
+    let Err(PubGrubError::NoSolution(derivation_tree)) =
+        resolve(&dropdown_menu_icons(), "root", (1, 0, 0))
+    else {
+        unreachable!()
+    };
+    let suggestions = SuggestionReporter::report(&derivation_tree);
+    // Root requires intl 5.0.0, but dropdown <2.0.0 requires intl 3.0.0.
+    assert!(suggestions.contains(&Suggestion::RelaxConstraint {
+        package: "intl",
+        current: Range::singleton((5, 0, 0)),
+        suggested: Range::singleton((3, 0, 0)).union(&Range::singleton((5, 0, 0))),
+    }));
+    assert!(!suggestions
+        .iter()
+        .any(|s| matches!(s, Suggestion::RemoveDependency { .. })));
+
+    // A dependency without any version can only be removed.
+    let mut dependency_provider = OfflineDependencyProvider::<&str, NumVS>::new();
+    dependency_provider.add_dependencies("root", 1u32, [("a", Range::full())]);
+    let Err(PubGrubError::NoSolution(derivation_tree)) =
+        resolve(&dependency_provider, "root", 1u32)
+    else {
+        unreachable!()
+    };
+    assert_eq!(
+        SuggestionReporter::report(&derivation_tree),
+        vec![Suggestion::RemoveDependency {
+            dependent: "root",
+            dependency: "a"
+        }]
+    );
+}
+
+#[test]
+fn involved_packages() {
+    let error = resolve(&dropdown_menu_icons(), "root", (1, 0, 0)).unwrap_err();
+    assert_eq!(
+        error.involved_packages(),
+        Some(Set::from_iter([
+            &"root",
+            &"menu",
+            &"dropdown",
+            &"icons",
+            &"intl"
+        ]))
+    );
+    let failure = PubGrubError::<OfflineDependencyProvider<&str, SemVS>>::Failure("oops".into());
+    assert_eq!(failure.involved_packages(), None);
+}
+
+#[test]
+fn indented_tree_report_shared() {
+    // a depends on b, b depends on c, and the derived "a depends on c" is used twice.
+    let a_c = Arc::new(DerivationTree::Derived(Derived {
+        terms: Map::from_iter([
+            ("a", Term::Positive(NumVS::full())),
+            ("c", Term::Negative(NumVS::full())),
+        ]),
+        shared_id: Some(7),
+        cause1: Arc::new(DerivationTree::External(External::FromDependencyOf(
+            "a",
+            NumVS::full(),
+            "b",
+            NumVS::full(),
+        ))),
+        cause2: Arc::new(DerivationTree::External(External::FromDependencyOf(
+            "b",
+            NumVS::full(),
+            "c",
+            NumVS::full(),
+        ))),
+    }));
+    let tree = DerivationTree::Derived(Derived::<_, _, String> {
+        terms: Map::default(),
+        shared_id: None,
+        cause1: a_c.clone(),
+        cause2: a_c,
+    });
+    assert_eq!(
+        &IndentedTreeReporter::report(&tree),
+        r#"version solving failed
+  a depends on c [1]
+    a depends on b
+    b depends on c
+  a depends on c [see 1]"#
+    );
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn serialize_derivation_tree() {
+    let mut dependency_provider = OfflineDependencyProvider::<&str, SemVS>::new();
+    #[rustfmt::skip]
+    dependency_provider.add_dependencies(
+        "root", (1, 0, 0),
+        [("foo", Range::between((1, 0, 0), (2, 0, 0)))],
+    );
+    dependency_provider.add_dependencies("foo", (1, 0, 0), [("bar", Range::singleton((4, 0, 0)))]);
+    dependency_provider.add_dependencies("bar", (5, 0, 0), []);
+
+    let Err(PubGrubError::NoSolution(derivation_tree)) =
+        resolve(&dependency_provider, "root", (1, 0, 0))
+    else {
+        unreachable!()
+    };
+    let json: serde_json::Value = serde_json::to_value(&derivation_tree).unwrap();
+
+    // The root of the tree is derived, with its terms and both causes serialized recursively.
+    let derived = &json["Derived"];
+    assert!(derived["terms"]["root"]["Positive"].is_array());
+    assert!(derived["shared_id"].is_null());
+    assert!(derived["cause1"].is_object());
+    assert!(derived["cause2"].is_object());
+
+    let json = json.to_string();
+    for package in ["root", "foo", "bar"] {
+        assert!(
+            json.contains(&format!("\"{package}\"")),
+            "{package} missing"
+        );
+    }
+    assert!(json.contains(r#""FromDependencyOf":["foo",[[{"Included":"1.0.0"},{"Included":"1.0.0"}]],"bar",[[{"Included":"4.0.0"},{"Included":"4.0.0"}]]]"#));
+    assert!(json.contains(r#""NoVersions":["bar",[[{"Included":"4.0.0"},{"Included":"4.0.0"}]]]"#));
+}
+
+#[test]
+fn root_conflicting_requirements() {
+    let dependency_provider = OfflineDependencyProvider::<_, NumVS>::from_edges([
+        ("root", 1u32, "x", Range::strictly_lower_than(2u32)),
+        ("root", 1u32, "x", Range::higher_than(2u32)),
+    ]);
+    let Err(PubGrubError::NoSolution(derivation_tree)) =
+        resolve(&dependency_provider, "root", 1u32)
+    else {
+        panic!("there should be no solution");
+    };
+    // Both requirements of the root are intersected into an empty set of versions of x.
+    assert_eq!(
+        DefaultStringReporter::report(&derivation_tree),
+        "root 1 has conflicting requirements on x, no version can satisfy all of them"
+    );
+}
@@ -0,0 +1,129 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use pubgrub::{
+    resolve, DefaultStringReporter, Feature, FeatureDependencies, FeatureDependencyProvider, Map,
+    PubGrubError, Range, Reporter,
+};
+
+type NumVS = Range<u32>;
+
+/// - `app` depends on `lib` with its `json` feature
+/// - `lib 1` has a `json` feature enabling its `std` feature and depending on `serde_json`
+/// - `lib 2` has no `json` feature
+/// - `serde_json` depends on `serde`, whose `derive` feature depends on `serde_derive`
+fn feature_graph() -> FeatureDependencyProvider<&'static str, NumVS> {
+    let mut dependency_provider = FeatureDependencyProvider::new();
+    dependency_provider.add_dependencies(
+        "app",
+        1u32,
+        FeatureDependencies::new().with_dependency("lib", Range::full(), ["json"]),
+    );
+    dependency_provider.add_dependencies(
+        "lib",
+        1u32,
+        FeatureDependencies::new()
+            .with_feature("std")
+            .with_feature_dependency("json", "lib", Range::full(), ["std"])
+            .with_feature_dependency("json", "serde_json", Range::full(), Vec::<String>::new()),
+    );
+    dependency_provider.add_dependencies(
+        "lib",
+        2u32,
+        FeatureDependencies::new().with_feature("std"),
+    );
+    dependency_provider.add_dependencies(
+        "serde_json",
+        1u32,
+        FeatureDependencies::new().with_dependency("serde", Range::full(), Vec::<String>::new()),
+    );
+    dependency_provider.add_dependencies(
+        "serde",
+        1u32,
+        FeatureDependencies::new().with_feature_dependency(
+            "derive",
+            "serde_derive",
+            Range::full(),
+            Vec::<String>::new(),
+        ),
+    );
+    dependency_provider.add_dependencies("serde_derive", 1u32, FeatureDependencies::new());
+    dependency_provider
+}
+
+#[test]
+fn optional_dependencies() {
+    let solution = resolve(&feature_graph(), Feature::Base("app"), 1u32).unwrap();
+
+    // lib 2 does not have the json feature, and serde_derive is not enabled.
+    let expected: Map<_, _> = Map::from_iter([
+        (Feature::Base("app"), 1),
+        (Feature::Base("lib"), 1),
+        (Feature::Enabled("lib", "json".to_string()), 1),
+        (Feature::Enabled("lib", "std".to_string()), 1),
+        (Feature::Base("serde_json"), 1),
+        (Feature::Base("serde"), 1),
+    ]);
+    assert_eq!(solution, expected);
+}
+
+#[test]
+fn feature_dependencies() {
+    let dependencies = FeatureDependencies::<&str, NumVS>::new()
+        .with_dependency("a", Range::higher_than(1u32), ["x"])
+        .with_feature_dependency("f", "a", Range::strictly_lower_than(3u32), ["y"])
+        .with_feature_dependency("f", "self", Range::full(), ["g"])
+        .with_feature("g");
+    let mut features: Vec<_> = dependencies.features().collect();
+    features.sort();
+    assert_eq!(features, vec!["f", "g"]);
+
+    assert_eq!(
+        dependencies.dependencies(&Feature::Base("self"), &1),
+        Some(Map::from_iter([
+            (Feature::Base("a"), Range::higher_than(1u32)),
+            (
+                Feature::Enabled("a", "x".to_string()),
+                Range::higher_than(1u32)
+            ),
+        ]))
+    );
+    // A feature depends on its own version of the package and of its other features.
+    assert_eq!(
+        dependencies.dependencies(&Feature::Enabled("self", "f".to_string()), &1),
+        Some(Map::from_iter([
+            (Feature::Base("self"), Range::singleton(1u32)),
+            (
+                Feature::Enabled("self", "g".to_string()),
+                Range::singleton(1u32)
+            ),
+            (Feature::Base("a"), Range::strictly_lower_than(3u32)),
+            (
+                Feature::Enabled("a", "y".to_string()),
+                Range::strictly_lower_than(3u32)
+            ),
+        ]))
+    );
+    assert_eq!(
+        dependencies.dependencies(&Feature::Enabled("self", "h".to_string()), &1),
+        None
+    );
+}
+
+#[test]
+fn missing_feature() {
+    let mut dependency_provider = feature_graph();
+    dependency_provider.add_dependencies(
+        "app",
+        2u32,
+        FeatureDependencies::new().with_dependency("lib", Range::singleton(2u32), ["json"]),
+    );
+    let Err(PubGrubError::NoSolution(derivation_tree)) =
+        resolve(&dependency_provider, Feature::Base("app"), 2u32)
+    else {
+        unreachable!()
+    };
+    assert_eq!(
+        DefaultStringReporter::report(&derivation_tree),
+        "Because dependencies of lib[json] at version 2 are unavailable because the feature json does not exist and app 2 depends on lib[json] 2, app 2 is forbidden."
+    );
+}
@@ -3,7 +3,6 @@
 #![allow(clippy::type_complexity)]
 
 use std::collections::BTreeSet as Set;
-use std::convert::Infallible;
 use std::fmt::{Debug, Display};
 
 use proptest::collection::{btree_map, btree_set, vec};
@@ -13,54 +12,14 @@ use proptest::string::string_regex;
 
 use pubgrub::{
     resolve, DefaultStringReporter, Dependencies, DependencyProvider, DerivationTree, External,
-    OfflineDependencyProvider, Package, PubGrubError, Range, Reporter, SelectedDependencies,
-    VersionSet,
+    OfflineDependencyProvider, Package, PubGrubError, Range, Reporter, Resolver,
+    SelectedDependencies, VersionPreference, VersionSet,
 };
 
 use crate::sat_dependency_provider::SatResolve;
 
 mod sat_dependency_provider;
 
-/// The same as [OfflineDependencyProvider] but takes versions from the opposite end:
-/// if [OfflineDependencyProvider] returns versions from newest to oldest, this returns them from oldest to newest.
-#[derive(Clone)]
-struct OldestVersionsDependencyProvider<P: Package, VS: VersionSet>(
-    OfflineDependencyProvider<P, VS>,
-);
-
-impl<P: Package, VS: VersionSet> DependencyProvider for OldestVersionsDependencyProvider<P, VS> {
-    fn get_dependencies(
-        &self,
-        p: &P,
-        v: &VS::V,
-    ) -> Result<Dependencies<P, VS, Self::M>, Infallible> {
-        self.0.get_dependencies(p, v)
-    }
-
-    fn choose_version(&self, package: &P, range: &VS) -> Result<Option<VS::V>, Infallible> {
-        Ok(self
-            .0
-            .versions(package)
-            .into_iter()
-            .flatten()
-            .find(|&v| range.contains(v))
-            .cloned())
-    }
-
-    type Priority = <OfflineDependencyProvider<P, VS> as DependencyProvider>::Priority;
-
-    fn prioritize(&self, package: &P, range: &VS) -> Self::Priority {
-        self.0.prioritize(package, range)
-    }
-
-    type Err = Infallible;
-
-    type P = P;
-    type V = VS::V;
-    type VS = VS;
-    type M = String;
-}
-
 /// The same as DP but it has a timeout.
 #[derive(Clone)]
 struct TimeoutDependencyProvider<DP> {
@@ -308,18 +267,13 @@ fn retain_versions<N: Package + Ord, VS: VersionSet>(
     dependency_provider: &OfflineDependencyProvider<N, VS>,
     mut retain: impl FnMut(&N, &VS::V) -> bool,
 ) -> OfflineDependencyProvider<N, VS> {
-    let mut smaller_dependency_provider = OfflineDependencyProvider::new();
+    let mut smaller_dependency_provider = dependency_provider.clone();
 
     for n in dependency_provider.packages() {
         for v in dependency_provider.versions(n).unwrap() {
             if !retain(n, v) {
-                continue;
+                smaller_dependency_provider.remove_version(n, v);
             }
-            let deps = match dependency_provider.get_dependencies(n, v).unwrap() {
-                Dependencies::Unavailable(_) => panic!(),
-                Dependencies::Available(deps) => deps,
-            };
-            smaller_dependency_provider.add_dependencies(n.clone(), v.clone(), deps)
         }
     }
     smaller_dependency_provider
@@ -482,7 +436,7 @@ proptest! {
     fn prop_reversed_version_errors_the_same(
         (dependency_provider, cases) in registry_strategy(0u16..665)
     )  {
-        let reverse_provider = OldestVersionsDependencyProvider(dependency_provider.clone());
+        let reverse_provider = dependency_provider.clone().with_default_version_preference(VersionPreference::Oldest);
         for (name, ver) in cases {
             let l = timeout_resolve(dependency_provider.clone(), name, ver);
             let r = timeout_resolve(reverse_provider.clone(), name, ver);
@@ -534,6 +488,38 @@ proptest! {
         }
     }
 
+    #[test]
+    /// A [Resolver] reused after publishing some versions
+    /// finds the same solutions as a new resolution.
+    fn prop_incremental_resolver_errors_the_same(
+        (dependency_provider, cases) in registry_strategy(0u16..665),
+        indexes_to_remove in vec(any::<Index>(), 1..10)
+    ) {
+        let all_versions: Vec<(u16, u32)> = dependency_provider
+            .packages()
+            .flat_map(|&p| {
+                dependency_provider
+                    .versions(&p)
+                    .unwrap()
+                    .map(move |&v| (p, v))
+            })
+            .collect();
+        let to_remove: Set<(_, _)> = indexes_to_remove.iter().map(|x| x.get(&all_versions)).cloned().collect();
+        let smaller_dependency_provider = retain_versions(&dependency_provider, |n, v| {
+            !to_remove.contains(&(*n, *v))
+        });
+        let mut sat = SatResolve::new(&dependency_provider);
+        for (name, ver) in cases {
+            let mut resolver = Resolver::new(name, ver);
+            let _ = resolver.resolve(&smaller_dependency_provider);
+            for (package, _) in &to_remove {
+                resolver.invalidate_package(*package);
+            }
+            let res = resolver.resolve(&dependency_provider);
+            sat.check_resolve(&res, &name, &ver);
+        }
+    }
+
     #[test]
     fn prop_limited_independence_of_irrelevant_alternatives(
         (dependency_provider, cases) in registry_strategy(0u16..665),
@@ -0,0 +1,175 @@
+// SPDX-License-Identifier: MPL-2.0
+
+// Each test file only uses some of the overrides.
+#![allow(dead_code)]
+
+use std::cell::RefCell;
+use std::collections::BTreeSet;
+use std::error::Error;
+
+use pubgrub::{Dependencies, DependencyProvider, ProgressInfo, VersionPreference};
+
+/// Forwards to an inner dependency provider, recording the calls it receives.
+///
+/// Each test can override some of the methods,
+/// with closures given the spy itself to forward to the inner provider or look at the records.
+/// Errors are of the inner provider error type,
+/// unless created with [with_error_type](SpyDependencyProvider::with_error_type).
+#[allow(clippy::type_complexity)]
+pub struct SpyDependencyProvider<DP: DependencyProvider, E = <DP as DependencyProvider>::Err> {
+    pub inner: DP,
+    /// Package, range and versions already tried, of each call to `choose_version_with_tried`.
+    pub choose_version_calls: RefCell<Vec<(DP::P, DP::VS, BTreeSet<DP::V>)>>,
+    pub get_dependencies_calls: RefCell<Vec<(DP::P, DP::V)>>,
+    pub prefetch_calls: RefCell<Vec<Vec<(DP::P, DP::VS)>>>,
+    pub progress: RefCell<Vec<ProgressInfo>>,
+    choose_version: Option<Box<dyn Fn(&Self, &DP::P, &DP::VS) -> Result<Option<DP::V>, E>>>,
+    get_dependencies:
+        Option<Box<dyn Fn(&Self, &DP::P, &DP::V) -> Result<Dependencies<DP::P, DP::VS, DP::M>, E>>>,
+    is_deprioritized: Option<Box<dyn Fn(&Self, &DP::P, &DP::V) -> bool>>,
+    should_cancel: Option<Box<dyn Fn(&Self) -> Result<(), E>>>,
+}
+
+impl<DP: DependencyProvider> SpyDependencyProvider<DP> {
+    pub fn new(inner: DP) -> Self {
+        Self::with_error_type(inner)
+    }
+}
+
+impl<DP: DependencyProvider, E: Error + From<DP::Err> + 'static> SpyDependencyProvider<DP, E> {
+    /// Same as [new](SpyDependencyProvider::new),
+    /// for overrides returning errors that the inner provider cannot return.
+    pub fn with_error_type(inner: DP) -> Self {
+        Self {
+            inner,
+            choose_version_calls: RefCell::default(),
+            get_dependencies_calls: RefCell::default(),
+            prefetch_calls: RefCell::default(),
+            progress: RefCell::default(),
+            choose_version: None,
+            get_dependencies: None,
+            is_deprioritized: None,
+            should_cancel: None,
+        }
+    }
+
+    /// Override `choose_version_with_tried`, after recording the call.
+    pub fn with_choose_version(
+        mut self,
+        f: impl Fn(&Self, &DP::P, &DP::VS) -> Result<Option<DP::V>, E> + 'static,
+    ) -> Self {
+        self.choose_version = Some(Box::new(f));
+        self
+    }
+
+    /// Override `get_dependencies`, after recording the call.
+    pub fn with_get_dependencies(
+        mut self,
+        f: impl Fn(&Self, &DP::P, &DP::V) -> Result<Dependencies<DP::P, DP::VS, DP::M>, E> + 'static,
+    ) -> Self {
+        self.get_dependencies = Some(Box::new(f));
+        self
+    }
+
+    pub fn with_is_deprioritized(
+        mut self,
+        f: impl Fn(&Self, &DP::P, &DP::V) -> bool + 'static,
+    ) -> Self {
+        self.is_deprioritized = Some(Box::new(f));
+        self
+    }
+
+    pub fn with_should_cancel(mut self, f: impl Fn(&Self) -> Result<(), E> + 'static) -> Self {
+        self.should_cancel = Some(Box::new(f));
+        self
+    }
+}
+
+impl<DP: DependencyProvider, E: Error + From<DP::Err> + 'static> DependencyProvider
+    for SpyDependencyProvider<DP, E>
+{
+    type P = DP::P;
+    type V = DP::V;
+    type VS = DP::VS;
+    type M = DP::M;
+    type Priority = DP::Priority;
+    type Err = E;
+
+    fn prioritize(&self, package: &DP::P, range: &DP::VS) -> Self::Priority {
+        self.inner.prioritize(package, range)
+    }
+
+    fn choose_version(&self, package: &DP::P, range: &DP::VS) -> Result<Option<DP::V>, E> {
+        self.choose_version_with_tried(package, range, &BTreeSet::new())
+    }
+
+    fn choose_version_with_tried(
+        &self,
+        package: &DP::P,
+        range: &DP::VS,
+        already_tried: &BTreeSet<DP::V>,
+    ) -> Result<Option<DP::V>, E> {
+        self.choose_version_calls.borrow_mut().push((
+            package.clone(),
+            range.clone(),
+            already_tried.clone(),
+        ));
+        match &self.choose_version {
+            Some(f) => f(self, package, range),
+            None => Ok(self
+                .inner
+                .choose_version_with_tried(package, range, already_tried)?),
+        }
+    }
+
+    fn package_exists(&self, package: &DP::P) -> bool {
+        self.inner.package_exists(package)
+    }
+
+    fn is_deprioritized(&self, package: &DP::P, version: &DP::V) -> bool {
+        match &self.is_deprioritized {
+            Some(f) => f(self, package, version),
+            None => self.inner.is_deprioritized(package, version),
+        }
+    }
+
+    fn version_preference(&self, package: &DP::P) -> VersionPreference<DP::V> {
+        self.inner.version_preference(package)
+    }
+
+    fn get_dependencies(
+        &self,
+        package: &DP::P,
+        version: &DP::V,
+    ) -> Result<Dependencies<DP::P, DP::VS, DP::M>, E> {
+        self.get_dependencies_calls
+            .borrow_mut()
+            .push((package.clone(), version.clone()));
+        match &self.get_dependencies {
+            Some(f) => f(self, package, version),
+            None => Ok(self.inner.get_dependencies(package, version)?),
+        }
+    }
+
+    fn prefetch(&self, requests: &[(&DP::P, &DP::VS)]) {
+        self.prefetch_calls.borrow_mut().push(
+            requests
+                .iter()
+                .map(|(p, r)| ((*p).clone(), (*r).clone()))
+                .collect(),
+        );
+        self.inner.prefetch(requests)
+    }
+
+    fn should_cancel(&self) -> Result<(), E> {
+        match &self.should_cancel {
+            Some(f) => f(self),
+            None => Ok(self.inner.should_cancel()?),
+        }
+    }
+
+    fn on_progress(&self, info: ProgressInfo) {
+        self.progress.borrow_mut().push(info);
+        self.inner.on_progress(info)
+    }
+}
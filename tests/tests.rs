@@ -1,6 +1,19 @@
 // SPDX-License-Identifier: MPL-2.0
 
-use pubgrub::{resolve, OfflineDependencyProvider, PubGrubError, Range};
+use std::collections::BTreeSet;
+use std::convert::Infallible;
+
+use pubgrub::{
+    impl_version_set_newtype, resolve, resolve_deterministic, resolve_with_deprioritized,
+    resolve_with_preferences, resolve_with_stats, CachingDependencyProvider, Dependencies,
+    DependencyProvider, External, FilteredDependencyProvider, Map, MergeConflict,
+    OfflineDependencyProvider, PriorityStrategy, PubGrubError, Range, ReplayDependencyProvider,
+    Resolver, SelectedDependenciesExt, UnrecordedQuery, VersionPreference, VersionSet,
+};
+
+use crate::spy_dependency_provider::SpyDependencyProvider;
+
+mod spy_dependency_provider;
 
 type NumVS = Range<u32>;
 
@@ -50,3 +63,940 @@ fn depend_on_self() {
     dependency_provider.add_dependencies("a", 66u32, [("a", Range::singleton(111u32))]);
     assert!(resolve(&dependency_provider, "a", 66u32).is_err());
 }
+
+#[test]
+fn remove_versions_and_packages() {
+    let mut dependency_provider = OfflineDependencyProvider::<_, NumVS>::new();
+    dependency_provider.add_dependencies("a", 0u32, [("b", Range::full())]);
+    dependency_provider.add_dependencies("b", 1u32, []);
+    dependency_provider.add_dependencies("b", 2u32, []);
+    assert_eq!(resolve(&dependency_provider, "a", 0u32).unwrap()["b"], 2);
+
+    // Removing what does not exist does nothing.
+    dependency_provider.remove_version(&"b", &3);
+    dependency_provider.remove_version(&"c", &1);
+    dependency_provider.remove_package(&"c");
+    assert_eq!(resolve(&dependency_provider, "a", 0u32).unwrap()["b"], 2);
+
+    // Yanking the newest version falls back to the older one.
+    dependency_provider.remove_version(&"b", &2);
+    assert_eq!(resolve(&dependency_provider, "a", 0u32).unwrap()["b"], 1);
+
+    // Without any version of b, there is no solution.
+    dependency_provider.remove_version(&"b", &1);
+    assert!(dependency_provider.versions(&"b").is_none());
+    assert!(matches!(
+        resolve(&dependency_provider, "a", 0u32),
+        Err(PubGrubError::NoSolution { .. })
+    ));
+
+    dependency_provider.add_dependencies("b", 1u32, []);
+    dependency_provider.remove_package(&"b");
+    assert_eq!(
+        dependency_provider.packages().collect::<Vec<_>>(),
+        vec![&"a"]
+    );
+}
+
+#[test]
+fn merge_overwrites() {
+    let mut base = OfflineDependencyProvider::<_, NumVS>::new();
+    base.add_dependencies("a", 0u32, [("b", Range::full())]);
+    base.add_dependencies("b", 0u32, []);
+    let mut overrides = OfflineDependencyProvider::<_, NumVS>::new();
+    overrides.add_dependencies("a", 0u32, [("b", Range::higher_than(1u32))]);
+    overrides.add_dependencies("b", 1u32, []);
+
+    base.merge(overrides);
+    assert_eq!(
+        base.versions(&"b").unwrap().collect::<Vec<_>>(),
+        vec![&0, &1]
+    );
+    let Ok(Dependencies::Available(dependencies)) = base.get_dependencies(&"a", &0) else {
+        unreachable!()
+    };
+    assert_eq!(
+        dependencies,
+        Map::from_iter([("b", Range::higher_than(1u32))])
+    );
+}
+
+#[test]
+fn try_merge_detects_conflicts() {
+    let mut base = OfflineDependencyProvider::<_, NumVS>::new();
+    base.add_dependencies("a", 0u32, [("b", Range::full())]);
+    base.add_dependencies("b", 0u32, []);
+
+    // The same dependencies are not a conflict.
+    let mut same = OfflineDependencyProvider::<_, NumVS>::new();
+    same.add_dependencies("b", 0u32, []);
+    same.add_dependencies("b", 1u32, []);
+    assert_eq!(base.try_merge(same), Ok(()));
+    assert_eq!(base.versions(&"b").unwrap().count(), 2);
+
+    let mut conflicting = OfflineDependencyProvider::<_, NumVS>::new();
+    conflicting.add_dependencies("a", 0u32, [("b", Range::higher_than(1u32))]);
+    conflicting.add_dependencies("c", 0u32, []);
+    assert_eq!(
+        base.try_merge(conflicting),
+        Err(MergeConflict {
+            package: "a",
+            version: 0
+        })
+    );
+    // Nothing was merged.
+    assert!(base.versions(&"c").is_none());
+    let Ok(Dependencies::Available(dependencies)) = base.get_dependencies(&"a", &0) else {
+        unreachable!()
+    };
+    assert_eq!(dependencies, Map::from_iter([("b", Range::full())]));
+}
+
+#[test]
+fn dependency_graph_as_dot() {
+    let mut dependency_provider = OfflineDependencyProvider::<_, NumVS>::new();
+    dependency_provider.add_dependencies("a", 0u32, [("b", Range::higher_than(1u32))]);
+    dependency_provider.add_dependencies("a", 1u32, [("c", Range::full())]);
+    dependency_provider.add_dependencies("b", 0u32, []);
+    dependency_provider.add_dependencies("b", 1u32, []);
+    dependency_provider.add_dependencies("b", 2u32, []);
+
+    let dot = dependency_provider.to_dot();
+    assert!(dot.starts_with("digraph"));
+    assert!(dot.contains("\"b 0\";"));
+    // Edges go to the versions in the range.
+    assert!(dot.contains(r#""a 0" -> "b 1" [label=">=1"];"#));
+    assert!(dot.contains(r#""a 0" -> "b 2" [label=">=1"];"#));
+    assert!(!dot.contains(r#""a 0" -> "b 0""#));
+    // Or to the package if no version is saved.
+    assert!(dot.contains(r#""a 1" -> "c" [label="*"];"#));
+}
+
+#[test]
+fn dependency_graph_as_dot_escapes_quotes() {
+    let mut dependency_provider = OfflineDependencyProvider::<_, NumVS>::new();
+    dependency_provider.add_dependencies(r#"say "hi""#, 0u32, [(r"back\slash", Range::full())]);
+
+    let dot = dependency_provider.to_dot();
+    assert!(dot.contains(r#"    "say \"hi\" 0";"#));
+    assert!(dot.contains(r#""say \"hi\" 0" -> "back\\slash" [label="*"];"#));
+}
+
+#[test]
+fn iter_dependencies() {
+    let mut dependency_provider = OfflineDependencyProvider::<_, NumVS>::new();
+    assert_eq!(dependency_provider.iter().count(), 0);
+    dependency_provider.add_dependencies("a", 0u32, [("b", Range::full()), ("c", Range::full())]);
+    dependency_provider.add_dependencies("a", 1u32, [("b", Range::full())]);
+    dependency_provider.add_dependencies("b", 0u32, []);
+    dependency_provider.add_dependencies("c", 0u32, []);
+
+    assert_eq!(dependency_provider.iter().count(), 4);
+    let dependency_count: usize = dependency_provider
+        .iter()
+        .map(|(_, _, dependencies)| dependencies.len())
+        .sum();
+    assert_eq!(dependency_count, 3);
+    let a_versions: Vec<_> = dependency_provider
+        .iter()
+        .filter(|(package, _, _)| **package == "a")
+        .map(|(_, version, _)| *version)
+        .collect();
+    assert_eq!(a_versions, vec![0, 1]);
+}
+
+#[test]
+fn from_edges() {
+    let edges = [
+        ("b", 1u32, "c", Range::higher_than(1u32)),
+        ("a", 0u32, "c", Range::full()),
+        ("a", 1u32, "b", Range::full()),
+        ("a", 0u32, "b", Range::full()),
+        ("b", 1u32, "c", Range::strictly_lower_than(3u32)),
+        ("a", 1u32, "c", Range::singleton(2u32)),
+    ];
+    let dependency_provider = OfflineDependencyProvider::<_, NumVS>::from_edges(edges);
+
+    let mut grouped: Vec<_> = dependency_provider
+        .iter()
+        .map(|(package, version, dependencies)| {
+            let mut dependencies: Vec<_> = dependencies
+                .iter()
+                .map(|(dep, range)| (*dep, range.clone()))
+                .collect();
+            dependencies.sort_by_key(|(dep, _)| *dep);
+            (*package, *version, dependencies)
+        })
+        .collect();
+    grouped.sort_by_key(|(package, version, _)| (*package, *version));
+    assert_eq!(
+        grouped,
+        vec![
+            ("a", 0, vec![("b", Range::full()), ("c", Range::full())]),
+            (
+                "a",
+                1,
+                vec![("b", Range::full()), ("c", Range::singleton(2u32))]
+            ),
+            // Both edges to c are intersected.
+            ("b", 1, vec![("c", Range::between(1u32, 3u32))]),
+        ]
+    );
+}
+
+#[test]
+fn version_strategies() {
+    let mut dependency_provider = OfflineDependencyProvider::<_, NumVS>::new();
+    dependency_provider.add_dependencies("a", 0u32, [("b", Range::full()), ("c", Range::full())]);
+    dependency_provider.add_dependencies("b", 1u32, [("c", Range::higher_than(1u32))]);
+    dependency_provider.add_dependencies("b", 2u32, [("c", Range::higher_than(2u32))]);
+    dependency_provider.add_dependencies("c", 0u32, []);
+    dependency_provider.add_dependencies("c", 1u32, []);
+    dependency_provider.add_dependencies("c", 2u32, []);
+
+    let newest = resolve(&dependency_provider, "a", 0u32).unwrap();
+    assert_eq!((newest["b"], newest["c"]), (2, 2));
+
+    let dependency_provider =
+        dependency_provider.with_default_version_preference(VersionPreference::Oldest);
+    let oldest = resolve(&dependency_provider, "a", 0u32).unwrap();
+    assert_eq!((oldest["b"], oldest["c"]), (1, 1));
+
+    // The priority strategy only changes the order of decisions, not the chosen versions here.
+    let dependency_provider =
+        dependency_provider.with_priority_strategy(PriorityStrategy::MostVersions);
+    assert_eq!(resolve(&dependency_provider, "a", 0u32).unwrap(), oldest);
+}
+
+#[test]
+fn version_preferences() {
+    let mut dependency_provider = OfflineDependencyProvider::<_, NumVS>::new();
+    dependency_provider.add_dependencies("root", 0u32, [("a", Range::full())]);
+    for version in [1u32, 3, 5, 7] {
+        dependency_provider.add_dependencies("a", version, []);
+    }
+    let chosen = |version_preference: VersionPreference<u32>| {
+        let dependency_provider = dependency_provider
+            .clone()
+            .with_version_preference("a", version_preference);
+        resolve(&dependency_provider, "root", 0u32).unwrap()["a"]
+    };
+
+    assert_eq!(chosen(VersionPreference::Newest), 7);
+    assert_eq!(chosen(VersionPreference::Oldest), 1);
+    assert_eq!(chosen(VersionPreference::ClosestTo(3)), 3);
+    // Newer versions are preferred to older ones.
+    assert_eq!(chosen(VersionPreference::ClosestTo(4)), 5);
+    assert_eq!(chosen(VersionPreference::ClosestTo(0)), 1);
+    assert_eq!(chosen(VersionPreference::ClosestTo(8)), 7);
+
+    // The preference of a package takes precedence over the default one.
+    let dependency_provider = dependency_provider
+        .with_default_version_preference(VersionPreference::Oldest)
+        .with_version_preference("a", VersionPreference::ClosestTo(6));
+    assert_eq!(
+        dependency_provider.version_preference(&"root"),
+        VersionPreference::Oldest
+    );
+    assert_eq!(resolve(&dependency_provider, "root", 0u32).unwrap()["a"], 7);
+}
+
+#[test]
+fn incremental_resolver() {
+    let mut offline = OfflineDependencyProvider::<&str, NumVS>::new();
+    offline.add_dependencies("root", 1u32, [("a", Range::full()), ("b", Range::full())]);
+    offline.add_dependencies("a", 1u32, [("c", Range::strictly_lower_than(2u32))]);
+    offline.add_dependencies("a", 2u32, [("c", Range::higher_than(2u32))]);
+    offline.add_dependencies("b", 1u32, [("c", Range::strictly_lower_than(2u32))]);
+    offline.add_dependencies("c", 1u32, []);
+    offline.add_dependencies("c", 2u32, []);
+    let mut dependency_provider = SpyDependencyProvider::new(offline);
+
+    let mut resolver = Resolver::new("root", 1u32);
+    let first = resolver.resolve(&dependency_provider).unwrap();
+    assert_eq!(first, resolve(&dependency_provider, "root", 1u32).unwrap());
+    assert_eq!((first["a"], first["b"], first["c"]), (1, 1, 1));
+
+    // Nothing changed, so no dependencies are retrieved again.
+    dependency_provider
+        .get_dependencies_calls
+        .borrow_mut()
+        .clear();
+    assert_eq!(resolver.resolve(&dependency_provider).unwrap(), first);
+    assert_eq!(dependency_provider.get_dependencies_calls.borrow().len(), 0);
+
+    // A new version of b allows a newer c and a.
+    dependency_provider
+        .inner
+        .add_dependencies("b", 2u32, [("c", Range::full())]);
+    resolver.invalidate_package("b");
+    dependency_provider
+        .get_dependencies_calls
+        .borrow_mut()
+        .clear();
+    let second = resolver.resolve(&dependency_provider).unwrap();
+    let incremental_calls = dependency_provider.get_dependencies_calls.borrow().len();
+    dependency_provider
+        .get_dependencies_calls
+        .borrow_mut()
+        .clear();
+    assert_eq!(second, resolve(&dependency_provider, "root", 1u32).unwrap());
+    assert!(incremental_calls < dependency_provider.get_dependencies_calls.borrow().len());
+    assert_eq!((second["a"], second["b"], second["c"]), (2, 2, 2));
+
+    // Changing the dependencies of a version can make the resolution fail, and succeed again.
+    dependency_provider
+        .inner
+        .add_dependencies("c", 1u32, [("d", Range::full())]);
+    dependency_provider
+        .inner
+        .add_dependencies("c", 2u32, [("d", Range::full())]);
+    resolver.invalidate_package("c");
+    assert!(matches!(
+        resolver.resolve(&dependency_provider),
+        Err(PubGrubError::NoSolution(_))
+    ));
+    dependency_provider.inner.add_dependencies("c", 2u32, []);
+    resolver.invalidate_package("c");
+    assert_eq!(resolver.resolve(&dependency_provider).unwrap(), second);
+}
+
+#[test]
+fn deterministic_tie_break() {
+    // b and a have the same priority, with one version each.
+    for root_dependencies in [
+        [("b", Range::full()), ("a", Range::full())],
+        [("a", Range::full()), ("b", Range::full())],
+    ] {
+        let mut offline = OfflineDependencyProvider::<&str, NumVS>::new();
+        offline.add_dependencies("root", 1u32, root_dependencies);
+        offline.add_dependencies("b", 1u32, []);
+        offline.add_dependencies("a", 1u32, []);
+        let dependency_provider = SpyDependencyProvider::new(offline);
+
+        resolve_deterministic(&dependency_provider, "root", 1u32).unwrap();
+        assert_eq!(
+            dependency_provider.get_dependencies_calls.into_inner(),
+            vec![("root", 1), ("a", 1), ("b", 1)]
+        );
+    }
+}
+
+#[test]
+fn caching_dependency_provider() {
+    let mut offline = OfflineDependencyProvider::<&str, NumVS>::new();
+    offline.add_dependencies("root", 1u32, [("a", Range::full())]);
+    offline.add_dependencies("a", 1u32, []);
+    offline.add_dependencies("a", 2u32, []);
+    let dependency_provider = CachingDependencyProvider::new(SpyDependencyProvider::new(offline));
+
+    let solution = resolve(&dependency_provider, "root", 1u32).unwrap();
+    let inner = dependency_provider.inner();
+    assert_eq!(
+        inner.get_dependencies_calls.borrow().as_slice(),
+        [("root", 1), ("a", 2)]
+    );
+    let choose_version_calls = inner.choose_version_calls.borrow().len();
+
+    // The same queries are served from the cache.
+    assert_eq!(
+        resolve(&dependency_provider, "root", 1u32).unwrap(),
+        solution
+    );
+    assert_eq!(inner.get_dependencies_calls.borrow().len(), 2);
+    assert_eq!(
+        inner.choose_version_calls.borrow().len(),
+        choose_version_calls
+    );
+
+    // A query that was not asked yet reaches the inner provider.
+    assert_eq!(
+        dependency_provider.choose_version(&"a", &Range::strictly_lower_than(2u32)),
+        Ok(Some(1))
+    );
+    assert_eq!(
+        inner.choose_version_calls.borrow().len(),
+        choose_version_calls + 1
+    );
+
+    // A version chosen while avoiding already tried ones is not cached.
+    let tried = BTreeSet::from([2]);
+    for _ in 0..2 {
+        dependency_provider
+            .choose_version_with_tried(&"a", &Range::full(), &tried)
+            .unwrap();
+    }
+    assert_eq!(
+        inner.choose_version_calls.borrow().len(),
+        choose_version_calls + 3
+    );
+}
+
+#[test]
+fn filtered_dependency_provider() {
+    let mut offline = OfflineDependencyProvider::<_, NumVS>::new();
+    offline.add_dependencies("root", 1u32, [("a", Range::full()), ("b", Range::full())]);
+    offline.add_dependencies("a", 1u32, [("b", Range::singleton(1u32))]);
+    offline.add_dependencies("b", 1u32, []);
+    offline.add_dependencies("b", 2u32, []);
+
+    // Denying the newest version of b makes the older one chosen.
+    let dependency_provider =
+        FilteredDependencyProvider::new(offline.clone()).deny("b", Range::singleton(2u32));
+    let solution = resolve(&dependency_provider, "root", 1u32).unwrap();
+    assert_eq!(solution, Map::from_iter([("root", 1), ("a", 1), ("b", 1)]));
+
+    // Denying the only version of b compatible with a leaves no solution.
+    let dependency_provider = FilteredDependencyProvider::new(offline)
+        .allow("b", Range::full())
+        .deny("b", Range::singleton(1u32));
+    let Err(PubGrubError::NoSolution(derivation_tree)) =
+        resolve(&dependency_provider, "root", 1u32)
+    else {
+        panic!("there should be no solution");
+    };
+    assert!(derivation_tree.packages().contains(&"b"));
+    // The denied version is reported as missing.
+    let b_1 = Range::singleton(1u32);
+    assert!(derivation_tree
+        .externals()
+        .any(|external| matches!(external, External::NoVersions("b", range) if range == &b_1)));
+}
+
+#[test]
+fn selected_dependencies_sorted() {
+    let mut dependency_provider = OfflineDependencyProvider::<&str, NumVS>::new();
+    dependency_provider.add_dependencies(
+        "root",
+        1u32,
+        [
+            ("d", Range::full()),
+            ("b", Range::full()),
+            ("c", Range::full()),
+        ],
+    );
+    dependency_provider.add_dependencies("d", 4u32, [("a", Range::full())]);
+    dependency_provider.add_dependencies("c", 3u32, []);
+    dependency_provider.add_dependencies("b", 2u32, []);
+    dependency_provider.add_dependencies("a", 1u32, []);
+
+    let solution = resolve(&dependency_provider, "root", 1u32).unwrap();
+    assert_eq!(
+        solution.iter_sorted().collect::<Vec<_>>(),
+        [
+            (&"a", &1),
+            (&"b", &2),
+            (&"c", &3),
+            (&"d", &4),
+            (&"root", &1)
+        ]
+    );
+    assert_eq!(solution.version(&"c"), Some(&3));
+    assert_eq!(solution.version(&"e"), None);
+}
+
+thread_local! {
+    static PACKAGE_CLONES: std::cell::Cell<usize> = const { std::cell::Cell::new(0) };
+}
+
+/// A package counting how many times it is cloned.
+#[derive(Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+struct CountedPackage(u32);
+
+impl Clone for CountedPackage {
+    fn clone(&self) -> Self {
+        PACKAGE_CLONES.with(|clones| clones.set(clones.get() + 1));
+        Self(self.0)
+    }
+}
+
+impl std::fmt::Display for CountedPackage {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "p{}", self.0)
+    }
+}
+
+#[test]
+fn few_package_clones_in_wide_registry() {
+    let packages = 200;
+    let mut dependency_provider = OfflineDependencyProvider::<CountedPackage, NumVS>::new();
+    let mut dependencies_count = packages as usize;
+    dependency_provider.add_dependencies(
+        CountedPackage(0),
+        0u32,
+        (1..=packages).map(|p| (CountedPackage(p), Range::full())),
+    );
+    for p in 1..=packages {
+        for v in 0..5u32 {
+            // Each package depends on a few of the next ones.
+            let dependencies: Vec<_> = (p + 1..=packages)
+                .take(3)
+                .map(|d| (CountedPackage(d), Range::higher_than(v)))
+                .collect();
+            if v == 0 {
+                dependencies_count += dependencies.len();
+            }
+            dependency_provider.add_dependencies(CountedPackage(p), v, dependencies);
+        }
+    }
+
+    PACKAGE_CLONES.with(|clones| clones.set(0));
+    let solution = resolve(&dependency_provider, CountedPackage(0), 0u32).unwrap();
+    let clones = PACKAGE_CLONES.with(|clones| clones.get());
+    assert_eq!(solution.len(), packages as usize + 1);
+    // The provider clones the dependencies it returns, and the solution clones its packages.
+    // Within the solver, packages are interned and manipulated by id,
+    // so the other clones are only a few for the root package.
+    assert!(clones <= dependencies_count + solution.len() + 2);
+}
+
+/// The error of a dependency provider failing in the method with the given name.
+#[derive(Debug)]
+struct MethodError(&'static str);
+
+impl std::fmt::Display for MethodError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} failed", self.0)
+    }
+}
+
+impl std::error::Error for MethodError {}
+
+impl From<Infallible> for MethodError {
+    fn from(never: Infallible) -> Self {
+        match never {}
+    }
+}
+
+type FailingDependencyProvider =
+    SpyDependencyProvider<OfflineDependencyProvider<&'static str, NumVS>, MethodError>;
+
+/// Fails in the method with the given name.
+fn failing(
+    offline: OfflineDependencyProvider<&'static str, NumVS>,
+    method: &'static str,
+) -> FailingDependencyProvider {
+    let fails = move |name| {
+        if method == name {
+            Err(MethodError(name))
+        } else {
+            Ok(())
+        }
+    };
+    SpyDependencyProvider::with_error_type(offline)
+        .with_choose_version(move |spy, package, range| {
+            fails("choose_version")?;
+            if method == "choose_version_out_of_range" {
+                return Ok(Some(42));
+            }
+            Ok(spy.inner.choose_version(package, range)?)
+        })
+        .with_get_dependencies(move |spy, package, version| {
+            fails("get_dependencies")?;
+            Ok(spy.inner.get_dependencies(package, version)?)
+        })
+        .with_should_cancel(move |_| fails("should_cancel"))
+}
+
+#[test]
+fn error_classification() {
+    let mut offline = OfflineDependencyProvider::<_, NumVS>::new();
+    offline.add_dependencies("root", 1u32, [("a", Range::full())]);
+    offline.add_dependencies("a", 1u32, []);
+
+    let classify = |error: PubGrubError<FailingDependencyProvider>| {
+        (error.is_no_solution(), error.is_provider_error())
+    };
+    for method in ["choose_version", "get_dependencies", "should_cancel"] {
+        let dependency_provider = failing(offline.clone(), method);
+        let error = resolve(&dependency_provider, "root", 1u32).unwrap_err();
+        assert_eq!(classify(error), (false, true), "{method}");
+    }
+
+    let mut conflicting = offline.clone();
+    conflicting.add_dependencies("root", 2u32, [("a", Range::higher_than(2u32))]);
+    let dependency_provider = failing(conflicting, "");
+    let error = resolve(&dependency_provider, "root", 2u32).unwrap_err();
+    assert_eq!(classify(error), (true, false));
+
+    let dependency_provider = failing(offline.clone(), "choose_version_out_of_range");
+    let error = resolve(&dependency_provider, "root", 1u32).unwrap_err();
+    let PubGrubError::InvalidVersionChoice {
+        package,
+        version,
+        range,
+    } = &error
+    else {
+        panic!("unexpected error: {error:?}")
+    };
+    assert_eq!(
+        (*package, *version, range),
+        ("root", 42, &Range::singleton(1u32))
+    );
+    assert_eq!(classify(error), (false, false));
+
+    let error = PubGrubError::Failure("resolution steps exhausted".into());
+    assert_eq!(classify(error), (false, false));
+}
+
+#[test]
+fn prioritize_by_width_favors_narrower_ranges() {
+    let dependency_provider = OfflineDependencyProvider::<&str, NumVS>::new();
+    let narrow = Range::between(1u32, 3u32);
+    let wide = narrow.union(&Range::higher_than(5u32));
+    assert_eq!(wide.segment_count(), 2);
+    assert!(
+        dependency_provider.prioritize_by_width(&narrow)
+            > dependency_provider.prioritize_by_width(&wide)
+    );
+    assert!(
+        dependency_provider.prioritize_by_width(&Range::empty())
+            > dependency_provider.prioritize_by_width(&narrow)
+    );
+}
+
+#[test]
+fn choose_version_told_about_tried_versions() {
+    let mut offline = OfflineDependencyProvider::<_, NumVS>::new();
+    offline.add_dependencies("root", 1u32, [("a", Range::full())]);
+    // a 2 is picked first, then rejected when no version of c matches.
+    offline.add_dependencies("a", 1u32, []);
+    offline.add_dependencies("a", 2u32, [("c", Range::singleton(1u32))]);
+    offline.add_dependencies("c", 2u32, []);
+
+    let dependency_provider = SpyDependencyProvider::new(offline);
+    let solution = resolve(&dependency_provider, "root", 1u32).unwrap();
+    assert_eq!(solution["a"], 1);
+
+    let a_calls: Vec<_> = dependency_provider
+        .choose_version_calls
+        .borrow()
+        .iter()
+        .filter(|(p, _, _)| *p == "a")
+        .map(|(_, _, tried)| tried.clone())
+        .collect();
+    assert_eq!(a_calls, vec![BTreeSet::new(), BTreeSet::from([2])]);
+}
+
+#[test]
+fn prefetch_dependencies_of_decided_version() {
+    let mut offline = OfflineDependencyProvider::<_, NumVS>::new();
+    offline.add_dependencies(
+        "root",
+        1u32,
+        [
+            ("a", Range::full()),
+            ("b", Range::strictly_lower_than(2u32)),
+        ],
+    );
+    offline.add_dependencies("a", 1u32, []);
+    offline.add_dependencies("a", 2u32, [("c", Range::full())]);
+    offline.add_dependencies("b", 1u32, []);
+    offline.add_dependencies("b", 2u32, []);
+    offline.add_dependencies("c", 1u32, []);
+
+    let dependency_provider = SpyDependencyProvider::new(offline);
+    let solution = resolve(&dependency_provider, "root", 1u32).unwrap();
+    assert_eq!(
+        solution,
+        Map::from_iter([("root", 1), ("a", 2), ("b", 1), ("c", 1)])
+    );
+
+    // The dependencies of root 1 and a 2 are prefetched when these versions are decided.
+    let mut prefetched = dependency_provider.prefetch_calls.into_inner();
+    for requests in &mut prefetched {
+        requests.sort();
+    }
+    assert_eq!(
+        prefetched[0],
+        vec![
+            ("a", Range::full()),
+            ("b", Range::strictly_lower_than(2u32))
+        ]
+    );
+    assert!(prefetched.contains(&vec![("c", Range::full())]));
+    assert_eq!(prefetched.len(), 2);
+    // No version is chosen for prefetching, only one per decided package.
+    assert_eq!(dependency_provider.choose_version_calls.borrow().len(), 4);
+}
+
+/// Deprioritizes some versions, like yanked versions of a registry.
+fn yanked(
+    offline: OfflineDependencyProvider<&'static str, NumVS>,
+    versions: BTreeSet<(&'static str, u32)>,
+) -> SpyDependencyProvider<OfflineDependencyProvider<&'static str, NumVS>> {
+    SpyDependencyProvider::new(offline)
+        .with_is_deprioritized(move |_, package, version| versions.contains(&(*package, *version)))
+}
+
+#[test]
+fn deprioritized_versions_as_last_resort() {
+    let mut offline = OfflineDependencyProvider::<_, NumVS>::new();
+    offline.add_dependencies("root", 1u32, [("a", Range::full())]);
+    offline.add_dependencies("root", 2u32, [("a", Range::higher_than(2u32))]);
+    offline.add_dependencies("a", 1u32, [("b", Range::full())]);
+    offline.add_dependencies("a", 2u32, [("b", Range::full())]);
+    offline.add_dependencies("a", 3u32, [("b", Range::singleton(2u32))]);
+    offline.add_dependencies("b", 1u32, []);
+    offline.add_dependencies("b", 2u32, []);
+    offline.add_dependencies("c", 1u32, []);
+    offline.add_dependencies("c", 2u32, []);
+    let mut dependency_provider = yanked(offline, BTreeSet::from([("a", 2), ("b", 2), ("c", 2)]));
+
+    // The yanked versions are skipped when another version works,
+    // even if they are newer.
+    let solution = resolve_with_deprioritized(&dependency_provider, "root", 1u32).unwrap();
+    assert_eq!(solution, Map::from_iter([("root", 1), ("a", 1), ("b", 1)]));
+    // Without skipping them, the newest versions are chosen.
+    let solution = resolve(&dependency_provider, "root", 1u32).unwrap();
+    assert_eq!(solution, Map::from_iter([("root", 1), ("a", 3), ("b", 2)]));
+
+    // Only yanked versions can satisfy root 2, a 3 needing the yanked b 2.
+    let solution = resolve_with_deprioritized(&dependency_provider, "root", 2u32).unwrap();
+    assert_eq!(solution, Map::from_iter([("root", 2), ("a", 3), ("b", 2)]));
+
+    // Other packages still skip their yanked versions, even newer ones.
+    dependency_provider.inner.add_dependencies(
+        "root",
+        4u32,
+        [("a", Range::higher_than(2u32)), ("c", Range::full())],
+    );
+    let solution = resolve_with_deprioritized(&dependency_provider, "root", 4u32).unwrap();
+    assert_eq!(
+        solution,
+        Map::from_iter([("root", 4), ("a", 3), ("b", 2), ("c", 1)])
+    );
+
+    // There is no solution at all when root 3 needs a missing version.
+    dependency_provider
+        .inner
+        .add_dependencies("root", 3u32, [("a", Range::higher_than(4u32))]);
+    assert!(matches!(
+        resolve_with_deprioritized(&dependency_provider, "root", 3u32),
+        Err(PubGrubError::NoSolution(_))
+    ));
+}
+
+/// Cancels the resolution once a number of versions were decided.
+fn stopping(
+    offline: OfflineDependencyProvider<&'static str, NumVS>,
+    decisions: usize,
+) -> FailingDependencyProvider {
+    SpyDependencyProvider::with_error_type(offline).with_should_cancel(move |spy| {
+        if spy.get_dependencies_calls.borrow().len() >= decisions {
+            Err(MethodError("should_cancel"))
+        } else {
+            Ok(())
+        }
+    })
+}
+
+#[test]
+fn cancel_gracefully_with_partial_solution() {
+    let mut offline = OfflineDependencyProvider::<_, NumVS>::new();
+    offline.add_dependencies("root", 1u32, [("a", Range::full())]);
+    offline.add_dependencies("a", 1u32, [("b", Range::full())]);
+    offline.add_dependencies("b", 1u32, []);
+
+    // Cancelled after the first decision, only the root package is decided.
+    let dependency_provider = stopping(offline.clone(), 1);
+    let Err(PubGrubError::ErrorInShouldCancel { decided, .. }) =
+        resolve(&dependency_provider, "root", 1u32)
+    else {
+        panic!("the resolution should be cancelled");
+    };
+    assert_eq!(decided, Map::from_iter([("root", 1)]));
+
+    // Cancelled after the second one, its dependency is decided too.
+    let dependency_provider = stopping(offline.clone(), 2);
+    let error = resolve(&dependency_provider, "root", 1u32).unwrap_err();
+    assert!(!error.is_no_solution() && error.is_provider_error());
+    let PubGrubError::ErrorInShouldCancel { source, decided } = error else {
+        panic!("the resolution should be cancelled");
+    };
+    assert_eq!(source.0, "should_cancel");
+    assert_eq!(decided, Map::from_iter([("root", 1), ("a", 1)]));
+
+    // Given enough decisions, the resolution completes.
+    let dependency_provider = stopping(offline, 4);
+    let solution = resolve(&dependency_provider, "root", 1u32).unwrap();
+    assert_eq!(solution, Map::from_iter([("root", 1), ("a", 1), ("b", 1)]));
+}
+
+#[test]
+fn warm_start_from_previous_solution() {
+    let mut dependency_provider = OfflineDependencyProvider::<_, NumVS>::new();
+    dependency_provider.add_dependencies(
+        "root",
+        1u32,
+        [("a", Range::full()), ("b", Range::full())],
+    );
+    // The newest a and b are tried first, and rejected because of c.
+    dependency_provider.add_dependencies("a", 1u32, [("c", Range::full())]);
+    dependency_provider.add_dependencies("a", 2u32, [("c", Range::singleton(1u32))]);
+    dependency_provider.add_dependencies("a", 3u32, [("c", Range::singleton(4u32))]);
+    dependency_provider.add_dependencies("b", 1u32, []);
+    dependency_provider.add_dependencies("b", 2u32, [("c", Range::singleton(3u32))]);
+    dependency_provider.add_dependencies("b", 3u32, [("c", Range::singleton(5u32))]);
+    dependency_provider.add_dependencies("c", 2u32, []);
+    dependency_provider.add_dependencies("c", 3u32, []);
+
+    // Each conflict is followed by a backtrack, the last progress counts them all.
+    let backtracks =
+        |spy: &SpyDependencyProvider<_>| spy.progress.borrow().last().unwrap().conflict_count;
+
+    let (cold, cold_stats) = resolve_with_stats(&dependency_provider, "root", 1u32);
+    let cold = cold.unwrap();
+    assert!(cold_stats.backtracks > 0);
+    let spy = SpyDependencyProvider::new(dependency_provider.clone());
+    assert_eq!(resolve(&spy, "root", 1u32).unwrap(), cold);
+    assert_eq!(backtracks(&spy), cold_stats.backtracks);
+
+    // The previous solution as preferences finds the same solution without backtracking.
+    let spy = SpyDependencyProvider::new(dependency_provider.clone());
+    let warm = resolve_with_preferences(&spy, "root", 1u32, &cold).unwrap();
+    assert_eq!(warm, cold);
+    assert!(backtracks(&spy) < cold_stats.backtracks);
+
+    // A stale hint, with versions that no longer exist, still finds the same solution.
+    let mut stale = cold.clone();
+    stale.insert("a", 7);
+    stale.insert("d", 1);
+    let warm = resolve_with_preferences(&dependency_provider, "root", 1u32, &stale).unwrap();
+    assert_eq!(warm, cold);
+}
+
+#[test]
+fn replay_recorded_resolution() {
+    let mut offline = OfflineDependencyProvider::<&str, NumVS>::new();
+    offline.add_dependencies("root", 1u32, [("a", Range::full()), ("b", Range::full())]);
+    offline.add_dependencies("a", 1u32, [("c", Range::singleton(1u32))]);
+    offline.add_dependencies("a", 2u32, [("c", Range::singleton(2u32))]);
+    offline.add_dependencies("b", 1u32, [("c", Range::singleton(1u32))]);
+    offline.add_dependencies("c", 1u32, []);
+    offline.add_dependencies("c", 2u32, []);
+
+    // Not the `RecordingDependencyProvider` of these tests, recording the decisions.
+    let recording = pubgrub::RecordingDependencyProvider::new(offline);
+    let solution = resolve(&recording, "root", 1u32).unwrap();
+    let trace = recording.trace();
+    // The trace can be saved and loaded.
+    #[cfg(feature = "serde")]
+    let json = serde_json::to_string(&trace).unwrap();
+    #[cfg(feature = "serde")]
+    let trace: pubgrub::TraceOf<OfflineDependencyProvider<&str, NumVS>> =
+        serde_json::from_str(&json).unwrap();
+
+    let replay = ReplayDependencyProvider::new(trace);
+    assert_eq!(resolve(&replay, "root", 1u32).unwrap(), solution);
+
+    // Questions of another resolution are not in the trace.
+    let error = resolve(&replay, "a", 1u32).unwrap_err();
+    assert!(
+        matches!(&error, PubGrubError::ErrorChoosingPackageVersion(UnrecordedQuery(query)) if query == "version of a 1"),
+        "{error:?}"
+    );
+}
+
+/// Package name hashed with a salt, to change the iteration order of hash maps.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Salted<const SALT: u64>(&'static str);
+
+impl<const SALT: u64> std::hash::Hash for Salted<SALT> {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        SALT.hash(state);
+        self.0.hash(state);
+    }
+}
+
+impl<const SALT: u64> std::fmt::Display for Salted<SALT> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+fn resolve_salted<const SALT: u64>() -> Vec<(&'static str, u32)> {
+    let mut dependency_provider = OfflineDependencyProvider::<Salted<SALT>, NumVS>::new();
+    let deps = |deps: &[(&'static str, NumVS)]| {
+        deps.iter()
+            .map(|(p, r)| (Salted(p), r.clone()))
+            .collect::<Vec<_>>()
+    };
+    // `a` has a single version and is decided first.
+    // Then `z`, seen with `a`, and `b`, seen in the dependencies of `a`, tie,
+    // and `z` is decided first because it was seen first, whatever the hashes.
+    dependency_provider.add_dependencies(
+        Salted("root"),
+        1u32,
+        deps(&[("a", Range::full()), ("z", Range::full())]),
+    );
+    dependency_provider.add_dependencies(Salted("a"), 1u32, deps(&[("b", Range::full())]));
+    // The first of `z` and `b` to be decided gets its newest version.
+    dependency_provider.add_dependencies(Salted("z"), 1u32, []);
+    dependency_provider.add_dependencies(Salted("z"), 2u32, deps(&[("b", Range::singleton(1u32))]));
+    dependency_provider.add_dependencies(Salted("b"), 1u32, []);
+    dependency_provider.add_dependencies(Salted("b"), 2u32, deps(&[("z", Range::singleton(1u32))]));
+    let solution = resolve(&dependency_provider, Salted("root"), 1u32).unwrap();
+    let mut solution: Vec<_> = solution.into_iter().map(|(p, v)| (p.0, v)).collect();
+    solution.sort();
+    solution
+}
+
+#[test]
+fn priority_ties_independent_of_hashes() {
+    let expected = vec![("a", 1), ("b", 1), ("root", 1), ("z", 2)];
+    assert_eq!(resolve_salted::<0>(), expected);
+    assert_eq!(resolve_salted::<1>(), expected);
+    assert_eq!(resolve_salted::<0xdead_beef>(), expected);
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct NewtypeVS(NumVS);
+
+impl std::fmt::Display for NewtypeVS {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl_version_set_newtype!(NewtypeVS => NumVS, field: 0);
+
+#[test]
+fn version_set_newtype_forwards_to_inner() {
+    let ranges = [
+        NumVS::empty(),
+        NumVS::full(),
+        NumVS::singleton(3u32),
+        NumVS::between(1u32, 4u32),
+        NumVS::strictly_lower_than(2u32).union(&NumVS::higher_than(5u32)),
+    ];
+    assert_eq!(NewtypeVS::empty().0, NumVS::empty());
+    assert_eq!(NewtypeVS::full().0, NumVS::full());
+    assert_eq!(NewtypeVS::singleton(2).0, NumVS::singleton(2u32));
+    assert_eq!(NewtypeVS::singleton_or_empty(None).0, NumVS::empty());
+    for r1 in &ranges {
+        let n1 = NewtypeVS(r1.clone());
+        assert_eq!(n1.complement().0, r1.complement());
+        assert_eq!(VersionSet::as_singleton(&n1), VersionSet::as_singleton(r1));
+        assert_eq!(n1.approximate_len(), r1.approximate_len());
+        for v in 0..7 {
+            assert_eq!(n1.contains(&v), r1.contains(&v));
+        }
+        for r2 in &ranges {
+            let n2 = NewtypeVS(r2.clone());
+            assert_eq!(n1.intersection(&n2).0, r1.intersection(r2));
+            assert_eq!(n1.union(&n2).0, r1.union(r2));
+            assert_eq!(n1.is_disjoint(&n2), r1.is_disjoint(r2));
+            assert_eq!(n1.subset_of(&n2), r1.subset_of(r2));
+        }
+    }
+
+    // Resolution is the same with the newtype.
+    let mut dependency_provider = OfflineDependencyProvider::<_, NewtypeVS>::new();
+    dependency_provider.add_dependencies("a", 0u32, [("b", NewtypeVS(NumVS::lower_than(1u32)))]);
+    dependency_provider.add_dependencies("b", 0u32, []);
+    dependency_provider.add_dependencies("b", 1u32, []);
+    dependency_provider.add_dependencies("b", 2u32, []);
+    let solution = resolve(&dependency_provider, "a", 0u32).unwrap();
+    assert_eq!(solution[&"b"], 1);
+}